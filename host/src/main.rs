@@ -0,0 +1,280 @@
+//! A command-line tool for talking to a Delta Pico over its USB serial port.
+
+use std::{env, fs, io::{self, Write}, process::exit};
+
+mod protocol;
+use protocol::*;
+
+const USAGE: &str = "\
+Usage: delta-pico-host <port> <command>
+
+Commands:
+  ping                        Check the connection and show the hardware revision
+  ls                          List stored files (notes and programs)
+  get <name> [out]            Print a file, or save it to <out>
+  put <path> [name]           Upload a file, named after <path> unless <name> is given
+  rm <name>                   Delete a file
+  history get <out>           Save the calculation history
+  history put <path>          Replace the calculation history
+  settings get [out]          Print the settings, or save them to <out>
+  settings put <path>         Replace the settings
+  screenshot <out.ppm>        Save the current screen contents
+  mirror [out.ppm]            Show the screen live in the terminal, or keep <out.ppm> updated
+                              with it, until interrupted
+  eval <expression>           Evaluate an expression, e.g. `eval 'sqrt(2)/3+sin(1)^2'`
+  press <keys...>             Press keys, e.g. `press 1 + 2 exe`
+                              Keys: 0-9, menu, exe, shift, list, text, left, right, up, down,
+                              del, clear, point, paren, +, -, *, /, ^, sqrt";
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.len() < 2 {
+        eprintln!("{}", USAGE);
+        exit(1);
+    }
+
+    let result = Connection::open(&args[0])
+        .and_then(|mut connection| run(&mut connection, &args[1..]));
+    if let Err(error) = result {
+        eprintln!("error: {}", error);
+        exit(1);
+    }
+}
+
+fn run(connection: &mut Connection, args: &[String]) -> Result<(), Error> {
+    let args = args.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+    match args.as_slice() {
+        ["ping"] => {
+            let response = connection.request(PING, &[])?;
+            let (version, revision) = response.split_first().ok_or(Error::Protocol("empty ping response"))?;
+            println!("Connected: {} (protocol version {})", String::from_utf8_lossy(revision), version);
+        }
+
+        ["ls"] => {
+            let response = connection.request(LIST_FILES, &[])?;
+            for name in parse_strings(&response).ok_or(Error::Protocol("invalid file list"))? {
+                println!("{}", name);
+            }
+        }
+
+        ["get", name, rest @ ..] if rest.len() <= 1 => {
+            let mut payload = vec![];
+            push_string(name, &mut payload);
+            let contents = connection.request(READ_FILE, &payload)?;
+            output(rest.first(), &contents)?;
+        }
+
+        ["put", path, rest @ ..] if rest.len() <= 1 => {
+            let contents = fs::read_to_string(path)?;
+            let name = match rest.first() {
+                Some(name) => name.to_string(),
+                None => std::path::Path::new(path).file_name().unwrap().to_string_lossy().into_owned(),
+            };
+            let mut payload = vec![];
+            push_string(&name, &mut payload);
+            push_string(&contents, &mut payload);
+            connection.request(WRITE_FILE, &payload)?;
+        }
+
+        ["rm", name] => {
+            let mut payload = vec![];
+            push_string(name, &mut payload);
+            connection.request(DELETE_FILE, &payload)?;
+        }
+
+        ["history", "get", out] => {
+            let history = connection.request(READ_HISTORY, &[])?;
+            fs::write(out, history)?;
+        }
+
+        ["history", "put", path] => {
+            connection.request(WRITE_HISTORY, &fs::read(path)?)?;
+        }
+
+        ["settings", "get", rest @ ..] if rest.len() <= 1 => {
+            let settings = connection.request(READ_SETTINGS, &[])?;
+            output(rest.first(), &settings)?;
+        }
+
+        ["settings", "put", path] => {
+            connection.request(WRITE_SETTINGS, &fs::read(path)?)?;
+        }
+
+        ["screenshot", out] => {
+            let response = connection.request(SCREENSHOT, &[])?;
+            fs::write(out, screenshot_to_ppm(&response).ok_or(Error::Protocol("invalid screenshot"))?)?;
+        }
+
+        ["mirror", rest @ ..] if rest.len() <= 1 => mirror(connection, rest.first())?,
+
+        ["eval", expression @ ..] if !expression.is_empty() => {
+            let result = connection.request(EVALUATE, expression.join(" ").as_bytes())?;
+            println!("{}", String::from_utf8_lossy(&result));
+        }
+
+        ["press", keys @ ..] if !keys.is_empty() => {
+            let mut codes = vec![];
+            for key in keys {
+                match key_codes(key) {
+                    Some(key_codes) => codes.extend(key_codes),
+                    None => {
+                        eprintln!("unknown key: {}", key);
+                        exit(1);
+                    }
+                }
+            }
+            connection.request(PRESS_KEYS, &codes)?;
+        }
+
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes to a file if one was given, or otherwise to standard output.
+fn output(path: Option<&&str>, contents: &[u8]) -> Result<(), Error> {
+    match path {
+        Some(path) => fs::write(path, contents)?,
+        None => print!("{}", String::from_utf8_lossy(contents)),
+    }
+    Ok(())
+}
+
+/// Mirrors the screen until the program is interrupted. Closing the port stops the calculator
+/// sending updates.
+fn mirror(connection: &mut Connection, out: Option<&&str>) -> Result<(), Error> {
+    let response = connection.request(START_MIRRORING, &[])?;
+    let (width, height) = match response.as_slice() {
+        [w1, w2, h1, h2] => (u16::from_be_bytes([*w1, *w2]) as usize, u16::from_be_bytes([*h1, *h2]) as usize),
+        _ => return Err(Error::Protocol("invalid mirroring response")),
+    };
+    let mut screen = vec![0; width * height];
+
+    if out.is_none() {
+        // Clear the terminal
+        print!("\x1B[2J");
+    }
+
+    loop {
+        let (kind, payload) = match connection.read_frame() {
+            Ok(frame) => frame,
+
+            // Nothing has changed on the screen for a while
+            Err(Error::Io(e)) if e.kind() == io::ErrorKind::TimedOut => continue,
+
+            Err(e) => return Err(e),
+        };
+        if kind != SCREEN_UPDATE {
+            continue;
+        }
+
+        let (start, rows, pixels) = match payload.as_slice() {
+            [s1, s2, r1, r2, pixels @ ..] => (u16::from_be_bytes([*s1, *s2]) as usize, u16::from_be_bytes([*r1, *r2]) as usize, pixels),
+            _ => return Err(Error::Protocol("invalid screen update")),
+        };
+        if start + rows > height || pixels.len() != rows * width {
+            return Err(Error::Protocol("invalid screen update"));
+        }
+        screen[start * width..(start + rows) * width].copy_from_slice(pixels);
+
+        match out {
+            Some(path) => {
+                let mut image = (width as u16).to_be_bytes().to_vec();
+                image.extend_from_slice(&(height as u16).to_be_bytes());
+                image.extend_from_slice(&screen);
+                fs::write(path, screenshot_to_ppm(&image).unwrap())?;
+            }
+            None => draw_to_terminal(&screen, width, start, rows)?,
+        }
+    }
+}
+
+/// Redraws the lines of the terminal which show the given rows of the screen. The screen is drawn
+/// at half size, with each character cell showing two pixels, one above the other.
+fn draw_to_terminal(screen: &[u8], width: usize, start: usize, rows: usize) -> Result<(), Error> {
+    const SCALE: usize = 2;
+    let height = screen.len() / width;
+    let first_line = start / (SCALE * 2);
+    let last_line = (start + rows - 1) / (SCALE * 2);
+
+    let mut output = String::new();
+    for line in first_line..=last_line {
+        output.push_str(&format!("\x1B[{};1H", line + 1));
+        for column in 0..width / SCALE {
+            let top = screen[line * SCALE * 2 * width + column * SCALE];
+            let bottom_y = (line * SCALE * 2 + SCALE).min(height - 1);
+            let bottom = screen[bottom_y * width + column * SCALE];
+            let (tr, tg, tb) = rgb332_to_rgb(top);
+            let (br, bg, bb) = rgb332_to_rgb(bottom);
+            output.push_str(&format!("\x1B[38;2;{};{};{}m\x1B[48;2;{};{};{}m\u{2580}", tr, tg, tb, br, bg, bb));
+        }
+        output.push_str("\x1B[0m");
+    }
+
+    let mut stdout = io::stdout();
+    stdout.write_all(output.as_bytes())?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Converts a key name into the codes to send. A run of digits presses each digit in turn.
+fn key_codes(key: &str) -> Option<Vec<u8>> {
+    if !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit()) {
+        return Some(key.bytes().collect());
+    }
+
+    let code = match key.to_lowercase().as_str() {
+        "menu" => 1,
+        "exe" => 2,
+        "shift" => 3,
+        "list" => 4,
+        "text" => 5,
+        "left" => 6,
+        "right" => 7,
+        "up" => 8,
+        "down" => 9,
+        "del" => 10,
+        "clear" => 11,
+        "point" | "." => 12,
+        "paren" | "()" => 13,
+        "+" => 14,
+        "-" => 15,
+        "*" => 16,
+        "/" => 17,
+        "^" => 18,
+        "sqrt" => 19,
+        _ => return None,
+    };
+    Some(vec![code])
+}
+
+/// Converts a screenshot response, made of RGB332 pixels, into a binary PPM image.
+fn screenshot_to_ppm(response: &[u8]) -> Option<Vec<u8>> {
+    let (width, height, pixels) = match response {
+        [w1, w2, h1, h2, pixels @ ..] =>
+            (u16::from_be_bytes([*w1, *w2]) as usize, u16::from_be_bytes([*h1, *h2]) as usize, pixels),
+        _ => return None,
+    };
+    if pixels.len() != width * height {
+        return None;
+    }
+
+    let mut ppm = format!("P6\n{} {}\n255\n", width, height).into_bytes();
+    for pixel in pixels {
+        let (red, green, blue) = rgb332_to_rgb(*pixel);
+        ppm.extend_from_slice(&[red, green, blue]);
+    }
+    Some(ppm)
+}
+
+/// Expands an RGB332 colour into 8-bit red, green and blue components.
+fn rgb332_to_rgb(pixel: u8) -> (u8, u8, u8) {
+    let red = (pixel >> 5) & 0b111;
+    let green = (pixel >> 2) & 0b111;
+    let blue = pixel & 0b11;
+    ((red as u16 * 255 / 7) as u8, (green as u16 * 255 / 7) as u8, (blue as u16 * 255 / 3) as u8)
+}
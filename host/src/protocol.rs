@@ -0,0 +1,168 @@
+//! The host side of the host link protocol. The protocol itself is documented in the OS crate, in
+//! `rust/src/host_link/mod.rs`; the constants here must be kept in sync with it.
+
+use std::{convert::TryInto, fmt::Display, io::{self, Read, Write}, time::Duration};
+
+use serialport::SerialPort;
+
+pub const FRAME_START: u8 = 0x7E;
+pub const RESPONSE_BIT: u8 = 0x80;
+pub const ERROR_KIND: u8 = 0xFF;
+
+pub const PING: u8 = 0x01;
+pub const LIST_FILES: u8 = 0x02;
+pub const READ_FILE: u8 = 0x03;
+pub const WRITE_FILE: u8 = 0x04;
+pub const DELETE_FILE: u8 = 0x05;
+pub const READ_HISTORY: u8 = 0x06;
+pub const WRITE_HISTORY: u8 = 0x07;
+pub const READ_SETTINGS: u8 = 0x08;
+pub const WRITE_SETTINGS: u8 = 0x09;
+pub const SCREENSHOT: u8 = 0x0A;
+pub const PRESS_KEYS: u8 = 0x0B;
+pub const START_MIRRORING: u8 = 0x0C;
+pub const EVALUATE: u8 = 0x0E;
+pub const SCREEN_UPDATE: u8 = 0x40;
+
+/// Calculates the CRC-16/CCITT-FALSE checksum of some bytes.
+pub fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc = 0xFFFF_u16;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Encodes a frame ready to be sent.
+pub fn encode_frame(kind: u8, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![FRAME_START, kind];
+    bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    let checksum = crc16(&bytes[1..]);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    bytes
+}
+
+/// Appends a string in the protocol's encoding: a 16-bit big-endian length, then UTF-8.
+pub fn push_string(string: &str, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&(string.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+/// Splits a payload made up entirely of encoded strings.
+pub fn parse_strings(mut bytes: &[u8]) -> Option<Vec<String>> {
+    let mut strings = vec![];
+    while !bytes.is_empty() {
+        if bytes.len() < 2 {
+            return None;
+        }
+        let length = u16::from_be_bytes([bytes[0], bytes[1]]) as usize;
+        let string = bytes.get(2..2 + length)?;
+        strings.push(String::from_utf8(string.to_vec()).ok()?);
+        bytes = &bytes[2 + length..];
+    }
+    Some(strings)
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Serial(serialport::Error),
+
+    /// The calculator rejected the request, with this message.
+    Calculator(String),
+
+    /// The calculator sent something which doesn't follow the protocol.
+    Protocol(&'static str),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error: {}", e),
+            Error::Serial(e) => write!(f, "serial port error: {}", e),
+            Error::Calculator(message) => write!(f, "calculator reported an error: {}", message),
+            Error::Protocol(message) => write!(f, "protocol error: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self { Error::Io(e) }
+}
+
+impl From<serialport::Error> for Error {
+    fn from(e: serialport::Error) -> Self { Error::Serial(e) }
+}
+
+/// A connection to a calculator.
+pub struct Connection {
+    port: Box<dyn SerialPort>,
+}
+
+impl Connection {
+    /// Opens the serial port at the given path.
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let mut port = serialport::new(path, 115200)
+            .timeout(Duration::from_secs(10))
+            .open()?;
+
+        // The calculator only talks to us once the port is marked as open by DTR
+        port.write_data_terminal_ready(true)?;
+
+        Ok(Connection { port })
+    }
+
+    /// Sends a request, and waits for its response. Returns the response payload.
+    pub fn request(&mut self, kind: u8, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        self.port.write_all(&encode_frame(kind, payload))?;
+        self.port.flush()?;
+
+        loop {
+            let (response_kind, payload) = self.read_frame()?;
+            if response_kind == SCREEN_UPDATE {
+                // Sent by itself while mirroring, rather than in response to this request
+                continue;
+            } else if response_kind == ERROR_KIND {
+                return Err(Error::Calculator(String::from_utf8_lossy(&payload).into_owned()));
+            } else if response_kind != kind | RESPONSE_BIT {
+                return Err(Error::Protocol("response does not match request"));
+            } else {
+                return Ok(payload);
+            }
+        }
+    }
+
+    /// Waits for the next frame from the calculator, and returns its kind and payload.
+    pub fn read_frame(&mut self) -> Result<(u8, Vec<u8>), Error> {
+        // Find the start of the frame
+        let mut byte = [0];
+        loop {
+            self.port.read_exact(&mut byte)?;
+            if byte[0] == FRAME_START {
+                break;
+            }
+        }
+
+        let mut header = [0; 5];
+        self.port.read_exact(&mut header)?;
+        let kind = header[0];
+        let length = u32::from_le_bytes(header[1..].try_into().unwrap()) as usize;
+
+        let mut payload = vec![0; length];
+        self.port.read_exact(&mut payload)?;
+        let mut checksum = [0; 2];
+        self.port.read_exact(&mut checksum)?;
+
+        let mut checked = header.to_vec();
+        checked.extend_from_slice(&payload);
+        if crc16(&checked) != u16::from_le_bytes(checksum) {
+            return Err(Error::Protocol("frame checksum mismatch"));
+        }
+
+        Ok((kind, payload))
+    }
+}
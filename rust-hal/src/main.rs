@@ -10,16 +10,20 @@ mod pcf8574;
 mod cat24c;
 mod button_matrix;
 mod rev;
+mod mass_storage;
 
-use alloc::string::{String, ToString};
+use alloc::{string::{String, ToString}, vec::Vec};
 use alloc_cortex_m::CortexMHeap;
 use button_matrix::{RawButtonEvent, ButtonMatrix};
 use cat24c::Cat24C;
 use cortex_m_rt::entry;
-use delta_pico_rust::{interface::{DisplayInterface, ApplicationFramework, ButtonsInterface, ButtonEvent, StorageInterface, ButtonInput}, delta_pico_main, graphics::Sprite};
+use delta_pico_rust::{interface::{DisplayInterface, ApplicationFramework, ButtonsInterface, ButtonEvent, StorageInterface, ButtonInput, UsbMassStorageInterface, SerialInterface}, delta_pico_main, graphics::Sprite};
 use embedded_hal::{digital::v2::{OutputPin}, spi::MODE_0, blocking::delay::DelayMs, blocking::i2c::{Write, Read}};
 use embedded_time::{fixed_point::FixedPoint, rate::Extensions};
 use ili9341::Ili9341;
+use mass_storage::MassStorage;
+use usb_device::{class_prelude::UsbBusAllocator, prelude::{UsbDevice, UsbDeviceBuilder, UsbDeviceState, UsbVidPid}};
+use usbd_serial::SerialPort;
 use rp_pico as bsp;
 use bsp::{hal::{
     clocks::{init_clocks_and_plls, Clock},
@@ -27,7 +31,8 @@ use bsp::{hal::{
     sio::{Sio, SioFifo, Spinlock},
     watchdog::Watchdog,
     spi::{Spi, SpiDevice}, gpio::{FunctionSpi, PinId, FunctionI2C, Pin, bank0::{Gpio20, Gpio21}}, I2C, Timer, multicore::{Stack, Multicore},
-}, pac::I2C0};
+    usb::UsbBus,
+}, pac::{I2C0, interrupt}};
 
 #[global_allocator]
 static ALLOCATOR: CortexMHeap = CortexMHeap::empty();
@@ -58,6 +63,14 @@ static mut I2C: Option<&'static mut I2C<I2C0, (Pin<Gpio20, FunctionI2C>, Pin<Gpi
 /// A spinlock with an arbitrarily-chosen number, used to sychronise access to the I2C bus.
 type I2CSpinlock = Spinlock<8>;
 
+/// The USB bus, device, and its mass storage and serial classes. The device is serviced from the
+/// USB interrupt, so these are globals which the interrupt handler can access. Elsewhere, they must
+/// only be accessed with interrupts disabled.
+static mut USB_BUS: Option<UsbBusAllocator<UsbBus>> = None;
+static mut USB_DEVICE: Option<UsbDevice<UsbBus>> = None;
+static mut USB_MASS_STORAGE: Option<MassStorage<UsbBus>> = None;
+static mut USB_SERIAL: Option<SerialPort<UsbBus>> = None;
+
 /// The clock speed of the system clock in hertz. Global so that it can be read by core 1 to set up
 /// delay timing.
 static mut SYSTEM_CLOCK_HZ: u32 = 0;
@@ -113,6 +126,34 @@ fn main() -> ! {
         &mut pac.RESETS,
     );
 
+    // Set up USB. The calculator always appears as a mass storage device, but without a medium until
+    // the OS inserts one, and as a serial port for the host link
+    unsafe {
+        USB_BUS = Some(UsbBusAllocator::new(UsbBus::new(
+            pac.USBCTRL_REGS,
+            pac.USBCTRL_DPRAM,
+            clocks.usb_clock,
+            true,
+            &mut pac.RESETS,
+        )));
+        let bus = USB_BUS.as_ref().unwrap();
+        USB_MASS_STORAGE = Some(MassStorage::new(bus));
+        USB_SERIAL = Some(SerialPort::new(bus));
+        USB_DEVICE = Some(
+            UsbDeviceBuilder::new(bus, UsbVidPid(0x1209, 0x0001))
+                .manufacturer("Delta Pico")
+                .product("Delta Pico")
+                .serial_number("0001")
+                // Miscellaneous device using interface association descriptors, since this is a
+                // composite device
+                .device_class(0xEF)
+                .device_sub_class(0x02)
+                .device_protocol(0x01)
+                .build()
+        );
+        pac::NVIC::unmask(pac::Interrupt::USBCTRL_IRQ);
+    }
+
     let mut led = pins.led.into_push_pull_output();
     led.set_high().unwrap();
 
@@ -178,7 +219,9 @@ fn main() -> ! {
         display: DisplayImpl { ili },
         buttons: ButtonsImpl { fifo: lives_forever(&mut sio.fifo) },
         storage: StorageImpl { flash },
-        
+        usb_mass_storage: UsbMassStorageImpl,
+        serial: SerialImpl,
+
         timer,
     };
     delta_pico_main(framework);
@@ -205,25 +248,29 @@ struct ButtonsImpl {
     fifo: &'static mut SioFifo,
 }
 
-impl ButtonsInterface for ButtonsImpl {
-    fn wait_event(&mut self) -> delta_pico_rust::interface::ButtonEvent {
-        let raw_button = self.fifo.read_blocking();
-
+impl ButtonsImpl {
+    fn decode(raw_button: u32) -> ButtonEvent {
         match RawButtonEvent::from_u32(raw_button) {
             RawButtonEvent::Press(row, col) => {
                 let input = rev::BUTTON_MAPPING[row as usize][col as usize];
-                return ButtonEvent::Press(input)
+                ButtonEvent::Press(input)
             }
 
             RawButtonEvent::Release(row, col) => {
                 let input = rev::BUTTON_MAPPING[row as usize][col as usize];
-                return ButtonEvent::Release(input)
+                ButtonEvent::Release(input)
             }
-        };
+        }
+    }
+}
+
+impl ButtonsInterface for ButtonsImpl {
+    fn wait_event(&mut self) -> delta_pico_rust::interface::ButtonEvent {
+        Self::decode(self.fifo.read_blocking())
     }
 
     fn poll_event(&mut self) -> Option<delta_pico_rust::interface::ButtonEvent> {
-        todo!()
+        self.fifo.read().map(Self::decode)
     }
 }
 
@@ -264,6 +311,76 @@ impl<
     fn release_priority(&mut self) {}
 }
 
+struct UsbMassStorageImpl;
+
+impl UsbMassStorageImpl {
+    /// Runs a function on the mass storage class, with the USB interrupt unable to interfere.
+    fn with_class<T>(&mut self, func: impl FnOnce(&mut MassStorage<'static, UsbBus>) -> T) -> T {
+        cortex_m::interrupt::free(|_| func(unsafe { USB_MASS_STORAGE.as_mut().unwrap() }))
+    }
+}
+
+impl UsbMassStorageInterface for UsbMassStorageImpl {
+    fn insert_medium(&mut self, image: Vec<u8>) -> bool {
+        self.with_class(|class| class.insert_medium(image));
+        true
+    }
+
+    fn is_ejected(&mut self) -> bool {
+        self.with_class(|class| class.is_ejected())
+    }
+
+    fn remove_medium(&mut self) -> Option<Vec<u8>> {
+        self.with_class(|class| class.remove_medium())
+    }
+}
+
+struct SerialImpl;
+
+impl SerialImpl {
+    /// Runs a function on the USB device and serial class, with the USB interrupt unable to
+    /// interfere.
+    fn with_serial<T>(&mut self, func: impl FnOnce(&mut UsbDevice<'static, UsbBus>, &mut SerialPort<'static, UsbBus>) -> T) -> T {
+        cortex_m::interrupt::free(|_| unsafe {
+            func(USB_DEVICE.as_mut().unwrap(), USB_SERIAL.as_mut().unwrap())
+        })
+    }
+}
+
+impl SerialInterface for SerialImpl {
+    fn is_connected(&mut self) -> bool {
+        self.with_serial(|device, serial| device.state() == UsbDeviceState::Configured && serial.dtr())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> usize {
+        self.with_serial(|_, serial| serial.read(buffer).unwrap_or(0))
+    }
+
+    fn write(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            if !self.is_connected() {
+                return;
+            }
+
+            // The interrupt takes care of sending buffered data, so this only needs to wait
+            // until there's space in the buffer. Interrupts are re-enabled between attempts.
+            let written = self.with_serial(|_, serial| serial.write(bytes).unwrap_or(0));
+            bytes = &bytes[written..];
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+#[interrupt]
+fn USBCTRL_IRQ() {
+    let (device, mass_storage, serial) = unsafe {(
+        USB_DEVICE.as_mut().unwrap(),
+        USB_MASS_STORAGE.as_mut().unwrap(),
+        USB_SERIAL.as_mut().unwrap(),
+    )};
+    device.poll(&mut [mass_storage, serial]);
+}
+
 struct FrameworkImpl<
     SpiD: SpiDevice,
     DcPin: PinId,
@@ -276,6 +393,8 @@ struct FrameworkImpl<
     display: DisplayImpl<SpiD, DcPin, RstPin, Delay>,
     buttons: ButtonsImpl,
     storage: StorageImpl<StorageI2CDevice, StorageError, Delay>,
+    usb_mass_storage: UsbMassStorageImpl,
+    serial: SerialImpl,
     timer: Timer,
 }
 
@@ -291,6 +410,8 @@ impl<
     type DisplayI = DisplayImpl<SpiD, DcPin, RstPin, Delay>;
     type ButtonsI = ButtonsImpl;
     type StorageI = StorageImpl<StorageI2CDevice, StorageError, Delay>;
+    type UsbMassStorageI = UsbMassStorageImpl;
+    type SerialI = SerialImpl;
 
     fn display(&self) -> &Self::DisplayI { &self.display }
     fn display_mut(&mut self) -> &mut Self::DisplayI { &mut self.display }
@@ -301,6 +422,12 @@ impl<
     fn storage(&self) -> &Self::StorageI { &self.storage }
     fn storage_mut(&mut self) -> &mut Self::StorageI { &mut self.storage }
 
+    fn usb_mass_storage(&self) -> &Self::UsbMassStorageI { &self.usb_mass_storage }
+    fn usb_mass_storage_mut(&mut self) -> &mut Self::UsbMassStorageI { &mut self.usb_mass_storage }
+
+    fn serial(&self) -> &Self::SerialI { &self.serial }
+    fn serial_mut(&mut self) -> &mut Self::SerialI { &mut self.serial }
+
     fn hardware_revision(&self) -> String { rev::REVISION_NAME.to_string() }
 
     fn reboot_into_bootloader(&mut self) -> ! {
@@ -345,6 +472,12 @@ impl<
         // Not implemented
     }
 
+    fn idle(&mut self) {
+        // Core 1 signals an event whenever it writes a button event to the FIFO, and USB activity
+        // raises an interrupt, either of which wakes this core up again
+        cortex_m::asm::wfe();
+    }
+
     fn should_run_tests(&mut self) -> bool {
         // Hold DEL on boot
         // The OS has never queried for input before this, so if delete was pressed, it's still in
@@ -0,0 +1,438 @@
+//! A USB mass storage class, using the bulk-only transport and a minimal subset of SCSI commands.
+//! It exposes a single medium held in RAM, which can be inserted and removed at any time, like a
+//! memory card in a card reader.
+
+use core::convert::TryInto;
+
+use alloc::{vec, vec::Vec};
+use delta_pico_rust::interface::USB_MASS_STORAGE_BLOCK_SIZE;
+use usb_device::{class_prelude::*, control::{Recipient, RequestType}};
+
+const CLASS_MASS_STORAGE: u8 = 0x08;
+const SUBCLASS_SCSI: u8 = 0x06;
+const PROTOCOL_BULK_ONLY: u8 = 0x50;
+
+const REQUEST_GET_MAX_LUN: u8 = 0xFE;
+const REQUEST_RESET: u8 = 0xFF;
+
+const PACKET_SIZE: usize = 64;
+const BLOCK_SIZE: usize = USB_MASS_STORAGE_BLOCK_SIZE;
+
+const COMMAND_BLOCK_SIGNATURE: u32 = 0x43425355;
+const COMMAND_BLOCK_LENGTH: usize = 31;
+const STATUS_SIGNATURE: u32 = 0x53425355;
+
+const STATUS_PASSED: u8 = 0;
+const STATUS_FAILED: u8 = 1;
+
+mod opcode {
+    pub const TEST_UNIT_READY: u8 = 0x00;
+    pub const REQUEST_SENSE: u8 = 0x03;
+    pub const INQUIRY: u8 = 0x12;
+    pub const MODE_SENSE_6: u8 = 0x1A;
+    pub const START_STOP_UNIT: u8 = 0x1B;
+    pub const PREVENT_ALLOW_MEDIUM_REMOVAL: u8 = 0x1E;
+    pub const READ_FORMAT_CAPACITIES: u8 = 0x23;
+    pub const READ_CAPACITY_10: u8 = 0x25;
+    pub const READ_10: u8 = 0x28;
+    pub const WRITE_10: u8 = 0x2A;
+    pub const VERIFY_10: u8 = 0x2F;
+    pub const SYNCHRONIZE_CACHE_10: u8 = 0x35;
+    pub const MODE_SENSE_10: u8 = 0x5A;
+}
+
+/// The reason the last command failed, reported to the host by REQUEST SENSE.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Sense {
+    None,
+    MediumNotPresent,
+    MediumChanged,
+    InvalidCommand,
+    OutOfRange,
+}
+
+impl Sense {
+    /// The sense key and additional sense code.
+    fn codes(self) -> (u8, u8) {
+        match self {
+            Sense::None => (0x00, 0x00),
+            Sense::MediumNotPresent => (0x02, 0x3A),
+            Sense::MediumChanged => (0x06, 0x28),
+            Sense::InvalidCommand => (0x05, 0x20),
+            Sense::OutOfRange => (0x05, 0x21),
+        }
+    }
+}
+
+/// Where the bytes sent to the host during a data stage come from.
+enum DataSource {
+    Buffer(Vec<u8>),
+
+    /// The medium, starting from the given byte offset.
+    Medium(usize),
+
+    /// Zeroes, sent when a failed command was expecting data.
+    Padding,
+}
+
+/// Where the bytes received from the host during a data stage go.
+enum DataTarget {
+    /// The medium, starting from the given byte offset.
+    Medium(usize),
+
+    /// Nowhere, because the command failed.
+    Discard,
+}
+
+enum Stage {
+    /// Waiting for the host to send a command.
+    Command,
+
+    /// Sending `length` bytes to the host, of which `sent` have been sent so far.
+    DataIn { source: DataSource, sent: usize, length: usize },
+
+    /// Receiving `length` bytes from the host, of which `received` have been received so far.
+    DataOut { target: DataTarget, received: usize, length: usize },
+
+    /// Waiting for the status of the last command to be sent.
+    Status,
+}
+
+pub struct MassStorage<'a, B: UsbBus> {
+    interface: InterfaceNumber,
+    out_ep: EndpointOut<'a, B>,
+    in_ep: EndpointIn<'a, B>,
+
+    medium: Option<Vec<u8>>,
+
+    /// Whether the host has ejected the medium. The medium is kept so that it can be removed
+    /// later, but appears not to be present.
+    ejected: bool,
+
+    /// Whether the host needs to be told that the medium has changed, before it can be used.
+    medium_changed: bool,
+
+    sense: Sense,
+    stage: Stage,
+
+    /// The tag, expected data length, and status of the current command.
+    tag: u32,
+    data_length: u32,
+    status: u8,
+}
+
+impl<'a, B: UsbBus> MassStorage<'a, B> {
+    pub fn new(alloc: &'a UsbBusAllocator<B>) -> Self {
+        Self {
+            interface: alloc.interface(),
+            out_ep: alloc.bulk(PACKET_SIZE as u16),
+            in_ep: alloc.bulk(PACKET_SIZE as u16),
+
+            medium: None,
+            ejected: false,
+            medium_changed: false,
+
+            sense: Sense::None,
+            stage: Stage::Command,
+
+            tag: 0,
+            data_length: 0,
+            status: STATUS_PASSED,
+        }
+    }
+
+    pub fn insert_medium(&mut self, medium: Vec<u8>) {
+        self.medium = Some(medium);
+        self.ejected = false;
+        self.medium_changed = true;
+    }
+
+    pub fn is_ejected(&self) -> bool {
+        self.ejected
+    }
+
+    pub fn remove_medium(&mut self) -> Option<Vec<u8>> {
+        self.ejected = false;
+        self.medium.take()
+    }
+
+    /// Checks that the medium can be read and written, returning the reason if not.
+    fn check_ready(&mut self) -> Result<(), Sense> {
+        if self.medium.is_none() || self.ejected {
+            Err(Sense::MediumNotPresent)
+        } else if self.medium_changed {
+            self.medium_changed = false;
+            Err(Sense::MediumChanged)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn block_count(&self) -> usize {
+        self.medium.as_ref().map(|m| m.len() / BLOCK_SIZE).unwrap_or(0)
+    }
+
+    /// Handles a command block wrapper from the host.
+    fn handle_command(&mut self, packet: &[u8]) {
+        if packet.len() != COMMAND_BLOCK_LENGTH
+            || u32::from_le_bytes(packet[0..4].try_into().unwrap()) != COMMAND_BLOCK_SIGNATURE {
+            return;
+        }
+
+        self.tag = u32::from_le_bytes(packet[4..8].try_into().unwrap());
+        self.data_length = u32::from_le_bytes(packet[8..12].try_into().unwrap());
+        let data_in = packet[12] & 0x80 != 0;
+        let command = &packet[15..31];
+
+        let stage = match self.execute(command) {
+            Ok(stage) => {
+                self.status = STATUS_PASSED;
+                stage
+            }
+            Err(sense) => {
+                self.sense = sense;
+                self.status = STATUS_FAILED;
+
+                // The host still expects its data stage, so fill it with nothing useful
+                let length = self.data_length as usize;
+                if length == 0 {
+                    None
+                } else if data_in {
+                    Some(Stage::DataIn { source: DataSource::Padding, sent: 0, length })
+                } else {
+                    Some(Stage::DataOut { target: DataTarget::Discard, received: 0, length })
+                }
+            }
+        };
+
+        match stage {
+            Some(stage) => {
+                self.stage = stage;
+                self.send();
+            }
+            None => self.send_status(0),
+        }
+    }
+
+    /// Runs a SCSI command, returning the data stage which it needs, if any.
+    fn execute(&mut self, command: &[u8]) -> Result<Option<Stage>, Sense> {
+        match command[0] {
+            opcode::INQUIRY => {
+                let mut data = vec![
+                    0x00, // Direct access device
+                    0x80, // Removable
+                    0x04, // SPC-2
+                    0x02, // Response data format
+                    31,   // Additional length
+                    0x00, 0x00, 0x00,
+                ];
+                data.extend_from_slice(b"Delta   ");
+                data.extend_from_slice(b"Pico            ");
+                data.extend_from_slice(b"1.0 ");
+                Ok(Some(self.respond(data)))
+            }
+
+            opcode::REQUEST_SENSE => {
+                let (key, code) = self.sense.codes();
+                self.sense = Sense::None;
+                Ok(Some(self.respond(vec![
+                    0x70, 0x00, key, 0x00, 0x00, 0x00, 0x00,
+                    10, // Additional length
+                    0x00, 0x00, 0x00, 0x00,
+                    code, 0x00, 0x00, 0x00, 0x00, 0x00,
+                ])))
+            }
+
+            opcode::TEST_UNIT_READY | opcode::VERIFY_10 | opcode::SYNCHRONIZE_CACHE_10 => {
+                self.check_ready()?;
+                Ok(None)
+            }
+
+            opcode::PREVENT_ALLOW_MEDIUM_REMOVAL => Ok(None),
+
+            opcode::START_STOP_UNIT => {
+                let load_eject = command[4] & 0x02 != 0;
+                let start = command[4] & 0x01 != 0;
+                if load_eject && !start {
+                    self.ejected = true;
+                }
+                Ok(None)
+            }
+
+            opcode::READ_CAPACITY_10 => {
+                self.check_ready()?;
+                let mut data = vec![];
+                data.extend_from_slice(&(self.block_count() as u32).saturating_sub(1).to_be_bytes());
+                data.extend_from_slice(&(BLOCK_SIZE as u32).to_be_bytes());
+                Ok(Some(self.respond(data)))
+            }
+
+            opcode::READ_FORMAT_CAPACITIES => {
+                self.check_ready()?;
+                let mut data = vec![0x00, 0x00, 0x00, 0x08];
+                data.extend_from_slice(&(self.block_count() as u32).to_be_bytes());
+                data.push(0x02); // Formatted media
+                data.extend_from_slice(&(BLOCK_SIZE as u32).to_be_bytes()[1..]);
+                Ok(Some(self.respond(data)))
+            }
+
+            opcode::MODE_SENSE_6 => Ok(Some(self.respond(vec![0x03, 0x00, 0x00, 0x00]))),
+            opcode::MODE_SENSE_10 => Ok(Some(self.respond(vec![0x00, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]))),
+
+            opcode::READ_10 | opcode::WRITE_10 => {
+                self.check_ready()?;
+                let block = u32::from_be_bytes(command[2..6].try_into().unwrap()) as usize;
+                let blocks = u16::from_be_bytes(command[7..9].try_into().unwrap()) as usize;
+                if block + blocks > self.block_count() {
+                    return Err(Sense::OutOfRange);
+                }
+
+                let offset = block * BLOCK_SIZE;
+                let length = blocks * BLOCK_SIZE;
+                Ok(Some(if command[0] == opcode::READ_10 {
+                    Stage::DataIn { source: DataSource::Medium(offset), sent: 0, length }
+                } else {
+                    Stage::DataOut { target: DataTarget::Medium(offset), received: 0, length }
+                }))
+            }
+
+            _ => Err(Sense::InvalidCommand),
+        }
+    }
+
+    /// Creates a data stage which sends the given response, truncated to the length the host
+    /// asked for.
+    fn respond(&self, data: Vec<u8>) -> Stage {
+        let length = data.len().min(self.data_length as usize);
+        Stage::DataIn { source: DataSource::Buffer(data), sent: 0, length }
+    }
+
+    /// Sends the next packet of the current data stage, or the status if the data has all been
+    /// sent.
+    fn send(&mut self) {
+        let mut packet = [0; PACKET_SIZE];
+
+        match &mut self.stage {
+            Stage::DataIn { source, sent, length } if *sent < *length => {
+                let size = PACKET_SIZE.min(*length - *sent);
+                match source {
+                    DataSource::Buffer(data) => packet[..size].copy_from_slice(&data[*sent..(*sent + size)]),
+                    DataSource::Medium(offset) => {
+                        // If the medium was removed partway through, send zeroes instead
+                        let start = *offset + *sent;
+                        if let Some(medium) = &self.medium {
+                            packet[..size].copy_from_slice(&medium[start..(start + size)]);
+                        }
+                    }
+                    DataSource::Padding => (),
+                }
+
+                if self.in_ep.write(&packet[..size]).is_ok() {
+                    *sent += size;
+                }
+            }
+
+            Stage::DataIn { sent, .. } => {
+                let sent = *sent as u32;
+                self.send_status(sent);
+            }
+
+            Stage::DataOut { .. } | Stage::Command | Stage::Status => (),
+        }
+    }
+
+    /// Sends the command status wrapper, given how many bytes of data were transferred.
+    fn send_status(&mut self, transferred: u32) {
+        let mut packet = [0; 13];
+        packet[0..4].copy_from_slice(&STATUS_SIGNATURE.to_le_bytes());
+        packet[4..8].copy_from_slice(&self.tag.to_le_bytes());
+        packet[8..12].copy_from_slice(&self.data_length.saturating_sub(transferred).to_le_bytes());
+        packet[12] = self.status;
+
+        if self.in_ep.write(&packet).is_ok() {
+            self.stage = Stage::Status;
+        }
+    }
+
+    /// Receives a packet from the host.
+    fn receive(&mut self) {
+        let mut packet = [0; PACKET_SIZE];
+        let size = match self.out_ep.read(&mut packet) {
+            Ok(size) => size,
+            Err(_) => return,
+        };
+
+        match &mut self.stage {
+            Stage::Command => self.handle_command(&packet[..size]),
+
+            Stage::DataOut { target, received, length } => {
+                let size = size.min(*length - *received);
+                if let (DataTarget::Medium(offset), Some(medium)) = (target, &mut self.medium) {
+                    let start = *offset + *received;
+                    medium[start..(start + size)].copy_from_slice(&packet[..size]);
+                }
+                *received += size;
+
+                if *received == *length {
+                    let received = *received as u32;
+                    self.send_status(received);
+                }
+            }
+
+            Stage::DataIn { .. } | Stage::Status => (),
+        }
+    }
+}
+
+impl<B: UsbBus> UsbClass<B> for MassStorage<'_, B> {
+    fn get_configuration_descriptors(&self, writer: &mut DescriptorWriter) -> usb_device::Result<()> {
+        writer.interface(self.interface, CLASS_MASS_STORAGE, SUBCLASS_SCSI, PROTOCOL_BULK_ONLY)?;
+        writer.endpoint(&self.out_ep)?;
+        writer.endpoint(&self.in_ep)?;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.stage = Stage::Command;
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<B>) {
+        let request = xfer.request();
+        if request.request_type == RequestType::Class
+            && request.recipient == Recipient::Interface
+            && request.index == u8::from(self.interface) as u16
+            && request.request == REQUEST_GET_MAX_LUN {
+            // Only one logical unit
+            xfer.accept_with(&[0]).ok();
+        }
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<B>) {
+        let request = xfer.request();
+        if request.request_type == RequestType::Class
+            && request.recipient == Recipient::Interface
+            && request.index == u8::from(self.interface) as u16
+            && request.request == REQUEST_RESET {
+            self.stage = Stage::Command;
+            xfer.accept().ok();
+        }
+    }
+
+    fn endpoint_out(&mut self, addr: EndpointAddress) {
+        if addr == self.out_ep.address() {
+            self.receive();
+        }
+    }
+
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        if addr != self.in_ep.address() {
+            return;
+        }
+
+        if let Stage::Status = self.stage {
+            self.stage = Stage::Command;
+        } else {
+            self.send();
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use alloc::{format, vec, vec::Vec, string::String};
+
+use crate::{interface::{Colour, ApplicationFramework, ButtonInput, ShapeFill}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, ContextMenu, ContextMenuItem, SelectorMenuCallable}, maths::{Radix, IntegerFormat, IntegerError}, graphics::AsciiFont};
+use super::{Application, ApplicationInfo};
+
+mod test;
+
+/// An application for integer arithmetic in binary, octal, decimal and hexadecimal, with bitwise
+/// operators and a configurable word size.
+pub struct BaseNApplication<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+
+    /// The expression being edited, with literals in `radix`.
+    expression: String,
+
+    /// The index in `expression` before which characters are inserted.
+    cursor: usize,
+
+    /// Whether the character before the cursor was inserted by a multi-tap keypress, and may
+    /// therefore be replaced by a subsequent `TextMultiTapCycle`.
+    cursor_after_multi_tap: bool,
+
+    radix: Radix,
+    format: IntegerFormat,
+
+    /// The result of the last evaluation, if the expression has been evaluated since last edited.
+    result: Option<Result<u64, IntegerError>>,
+}
+
+os_accessor!(BaseNApplication<F>);
+
+impl<F: ApplicationFramework> Application for BaseNApplication<F> {
+    type Framework = F;
+
+    fn info() -> ApplicationInfo {
+        ApplicationInfo {
+            name: "Base N".into(),
+            visible: true,
+        }
+    }
+
+    fn new(os: OperatingSystemPointer<F>) -> Self {
+        Self {
+            os,
+            expression: String::new(),
+            cursor: 0,
+            cursor_after_multi_tap: false,
+            radix: Radix::Decimal,
+            format: IntegerFormat::default(),
+            result: None,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.draw();
+
+        let input = if let Some(input) = self.os_mut().input() { input } else { return };
+        let was_multi_tap = self.cursor_after_multi_tap;
+        self.cursor_after_multi_tap = false;
+
+        match input {
+            OSInput::Button(ButtonInput::Digit(d)) => {
+                let c = core::char::from_digit(d as u32, 10).unwrap();
+                if self.radix.is_digit(c) {
+                    self.insert(&[c]);
+                }
+            }
+            OSInput::TextMultiTapNew(c) => {
+                let c = c.to_ascii_uppercase();
+                if self.radix.is_digit(c) {
+                    self.insert(&[c]);
+                    self.cursor_after_multi_tap = true;
+                }
+            }
+            OSInput::TextMultiTapCycle(c) => {
+                let c = c.to_ascii_uppercase();
+                if was_multi_tap && self.radix.is_digit(c) {
+                    self.delete();
+                    self.insert(&[c]);
+                    self.cursor_after_multi_tap = true;
+                }
+            }
+
+            OSInput::Button(ButtonInput::Add) => self.insert(&['+']),
+            OSInput::Button(ButtonInput::Subtract) => self.insert(&['-']),
+            OSInput::Button(ButtonInput::Multiply) => self.insert(&['*']),
+            OSInput::Button(ButtonInput::Fraction) => self.insert(&['/']),
+            OSInput::Button(ButtonInput::Parentheses) => {
+                self.insert(&['(', ')']);
+                self.cursor -= 1;
+            }
+
+            OSInput::Button(ButtonInput::MoveLeft) => self.cursor = self.cursor.saturating_sub(1),
+            OSInput::Button(ButtonInput::MoveRight) => self.cursor = (self.cursor + 1).min(self.expression.len()),
+            OSInput::Button(ButtonInput::Delete) => self.delete(),
+            OSInput::Button(ButtonInput::Clear) => {
+                self.expression.clear();
+                self.cursor = 0;
+                self.result = None;
+            }
+
+            OSInput::Button(ButtonInput::Exe) => self.evaluate(),
+            OSInput::Button(ButtonInput::List) => self.open_menu(),
+
+            _ => (),
+        }
+    }
+
+    fn test(&mut self) {
+        test::test(self);
+    }
+}
+
+impl<F: ApplicationFramework> BaseNApplication<F> {
+    /// Inserts characters at the cursor, and moves the cursor after them.
+    fn insert(&mut self, chars: &[char]) {
+        for c in chars {
+            self.expression.insert(self.cursor, *c);
+            self.cursor += 1;
+        }
+        self.result = None;
+    }
+
+    /// Deletes the character before the cursor.
+    fn delete(&mut self) {
+        if self.cursor > 0 {
+            self.cursor -= 1;
+            self.expression.remove(self.cursor);
+            self.result = None;
+        }
+    }
+
+    fn evaluate(&mut self) {
+        self.result = Some(self.format.evaluate(&self.expression, self.radix));
+    }
+
+    /// Changes the input radix, converting any literals already in the expression.
+    fn set_radix(&mut self, radix: Radix) {
+        self.expression = self.format.convert_literals(&self.expression, self.radix, radix);
+        self.cursor = self.expression.len();
+        self.radix = radix;
+        if self.result.is_some() {
+            self.evaluate();
+        }
+    }
+
+    fn draw(&self) {
+        let os = self.os_mut();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title("Base N");
+
+        os.display_sprite.print_at(5, 35, &format!(
+            "{}  {}-bit {}",
+            self.radix.short_name(),
+            self.format.bits,
+            if self.format.signed { "signed" } else { "unsigned" },
+        ));
+
+        // Draw expression, with a cursor
+        let (before_cursor, after_cursor) = self.expression.split_at(self.cursor);
+        let (cursor_x, _) = os.display_sprite.font.string_size(before_cursor);
+        os.display_sprite.print_at(5, 65, before_cursor);
+        os.display_sprite.print_at(5 + cursor_x, 65, after_cursor);
+        os.display_sprite.draw_rect(5 + cursor_x, 63, 2, 24, Colour::WHITE, ShapeFill::Filled, 0);
+
+        // Draw result in all bases
+        match self.result {
+            Some(Ok(value)) => {
+                let mut y = 105;
+                for radix in Radix::ALL.iter() {
+                    let mut digits = self.format.format(value, *radix);
+                    if *radix == Radix::Binary {
+                        digits = group_digits(&digits, 4);
+                    }
+
+                    let (lines, line_height, _) = os.display_sprite.wrap_text(&digits, os.display_sprite.width - 60);
+                    os.display_sprite.print_at(5, y, radix.short_name());
+                    for line in lines {
+                        os.display_sprite.print_at(55, y, &line);
+                        y += line_height;
+                    }
+                    y += 5;
+                }
+            }
+            Some(Err(error)) => {
+                os.display_sprite.print_at(5, 105, &format!("{:?}", error));
+            }
+            None => (),
+        }
+
+        os.display_sprite.print_at(5, 290, "[LIST] Options");
+        os.draw();
+    }
+
+    fn open_menu(&mut self) {
+        ContextMenu::new(
+            self.os,
+            vec![
+                ContextMenuItem::new_common("Operators...", |this: &mut Self| {
+                    this.draw();
+                    this.operator_menu();
+                }),
+                ContextMenuItem::new_common("Base...", |this: &mut Self| {
+                    this.draw();
+                    this.radix_menu();
+                }),
+                ContextMenuItem::new_common("Word size...", |this: &mut Self| {
+                    this.draw();
+                    this.word_size_menu();
+                }),
+                ContextMenuItem::new_common(
+                    if self.format.signed { "Make unsigned" } else { "Make signed" },
+                    |this: &mut Self| {
+                        this.format.signed = !this.format.signed;
+                        if this.result.is_some() {
+                            this.evaluate();
+                        }
+                    }
+                ),
+            ],
+            true,
+        ).tick_until_call(self);
+    }
+
+    fn operator_menu(&mut self) {
+        macro_rules! operator {
+            ($label:expr, $chars:expr) => {
+                ContextMenuItem::new_common($label, |this: &mut Self| this.insert($chars))
+            };
+        }
+
+        ContextMenu::new(
+            self.os,
+            vec![
+                operator!("AND  &", &['&']),
+                operator!("OR  |", &['|']),
+                operator!("XOR  ^", &['^']),
+                operator!("NOT  ~", &['~']),
+                operator!("Shift left  <<", &['<', '<']),
+                operator!("Shift right  >>", &['>', '>']),
+                operator!("Modulo  %", &['%']),
+            ],
+            true,
+        ).tick_until_call(self);
+    }
+
+    fn radix_menu(&mut self) {
+        ContextMenu::new(
+            self.os,
+            Radix::ALL.iter().map(|radix| {
+                let radix = *radix;
+                ContextMenuItem::new_common(
+                    format!("{} (base {})", radix.short_name(), radix.value()),
+                    move |this: &mut Self| this.set_radix(radix),
+                )
+            }).collect(),
+            true,
+        ).tick_until_call(self);
+    }
+
+    fn word_size_menu(&mut self) {
+        ContextMenu::new(
+            self.os,
+            IntegerFormat::WORD_SIZES.iter().map(|bits| {
+                let bits = *bits;
+                ContextMenuItem::new_common(format!("{}-bit", bits), move |this: &mut Self| {
+                    this.format.bits = bits;
+                    if this.result.is_some() {
+                        this.evaluate();
+                    }
+                })
+            }).collect(),
+            true,
+        ).tick_until_call(self);
+    }
+}
+
+/// Splits a string of digits into groups of `size`, aligned to the right, separated by spaces.
+fn group_digits(digits: &str, size: usize) -> String {
+    let chars: Vec<char> = digits.chars().collect();
+    let mut result = String::new();
+    for (i, c) in chars.iter().enumerate() {
+        if i > 0 && (chars.len() - i) % size == 0 {
+            result.push(' ');
+        }
+        result.push(*c);
+    }
+    result
+}
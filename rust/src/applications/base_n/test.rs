@@ -0,0 +1,56 @@
+use crate::{interface::{ApplicationFramework, ButtonInput}, tests, operating_system::OSInput, maths::{Radix, IntegerError}};
+
+use super::BaseNApplication;
+
+pub fn test<F: ApplicationFramework>(app: &mut BaseNApplication<F>) {
+    // Bitwise AND, selected from the operator menu
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Digit(0)),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.expression, "12&10");
+    assert_eq!(app.result, Some(Ok(8)));
+
+    // Precedence and signed display
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::Clear),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::Subtract),
+        OSInput::Button(ButtonInput::Digit(3)),
+        OSInput::Button(ButtonInput::Multiply),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    let result = app.result.unwrap().unwrap();
+    assert_eq!(app.format.format(result, Radix::Decimal), "-4");
+    assert_eq!(app.format.format(result, Radix::Hexadecimal), "FFFFFFFC");
+
+    // Switching to hex converts literals, and hex digits can be typed with multi-tap
+    app.set_radix(Radix::Hexadecimal);
+    assert_eq!(app.expression, "2-3*2");
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::Clear),
+        OSInput::TextMultiTapNew('f'),
+        OSInput::TextMultiTapNew('f'),
+        OSInput::Button(ButtonInput::Add),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.result, Some(Ok(0x100)));
+
+    // Division by zero is reported
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::Clear),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Fraction),
+        OSInput::Button(ButtonInput::Digit(0)),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.result, Some(Err(IntegerError::DivisionByZero)));
+}
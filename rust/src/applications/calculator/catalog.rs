@@ -14,6 +14,10 @@ where
     title: String,
     items: Vec<CatalogItem<T>>,
     selected_index: usize,
+
+    /// The index of the first row which is visible. Catalogs with more items than can fit on the
+    /// screen at once scroll to keep the selected item in view.
+    scroll_row: u16,
 }
 
 /// An item in a `Catalog`. Additional metadata can be attached to an item.
@@ -57,6 +61,7 @@ where
     const ITEM_PADDING: u16 = 5;
     const DESCRIPTION_HEIGHT: u16 = 70;
     const COLUMNS: u16 = 3;
+    const VISIBLE_ROWS: u16 = (Self::HEIGHT - Self::DESCRIPTION_HEIGHT) / (Self::ROW_HEIGHT - 1);
 
     pub fn new(os: OperatingSystemPointer<F>, title: impl Into<String>, items: Vec<CatalogItem<T>>) -> Self {
         Self {
@@ -64,6 +69,7 @@ where
             title: title.into(),
             items,
             selected_index: 0,
+            scroll_row: 0,
         }
     }
 }
@@ -97,10 +103,25 @@ where
         );
         self.os.ui_draw_title(&self.title);
 
+        // Scroll so that the selected item is visible
+        let selected_row = self.selected_index as u16 / Self::COLUMNS;
+        if selected_row < self.scroll_row {
+            self.scroll_row = selected_row;
+        } else if selected_row >= self.scroll_row + Self::VISIBLE_ROWS {
+            self.scroll_row = selected_row + 1 - Self::VISIBLE_ROWS;
+        }
+
         // Draw items
-        let mut column = 0;
-        let mut row = 0;
         for (i, item) in self.items.iter().enumerate() {
+            let column = i as u16 % Self::COLUMNS;
+            let row = i as u16 / Self::COLUMNS;
+
+            // Skip items which are scrolled out of view
+            if row < self.scroll_row || row >= self.scroll_row + Self::VISIBLE_ROWS {
+                continue;
+            }
+            let row = row - self.scroll_row;
+
             let item_x = starting_x + (column * (Self::WIDTH / Self::COLUMNS) - if column > 0 { 1 } else { 0 }) as i16;
             let item_y = starting_y + (row * (Self::ROW_HEIGHT - 1)) as i16;
 
@@ -124,16 +145,6 @@ where
                 item_y + Self::ITEM_PADDING as i16,
                 &item.name
             );
-
-            // Advance column and optionally increase row
-            if column == 0 || column == 1 {
-                column += 1;
-            } else if column == 2 {
-                column = 0;
-                row += 1;
-            } else {
-                unreachable!()
-            }
         }
 
         // Draw border around the whole thing - we do this at the end so the item borders don't
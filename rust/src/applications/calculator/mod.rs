@@ -1,8 +1,8 @@
 use core::cmp::{max, min};
 use alloc::{format, vec, vec::Vec};
-use rbop::{Number, StructuredNode, nav::{MoveVerticalDirection, MoveResult}, node::{unstructured::Upgradable, function::Function}, render::{Area, Renderer, Viewport, LayoutComputationProperties}, UnstructuredNode, UnstructuredNodeList, Token};
+use rbop::{Number, StructuredNode, nav::{MoveVerticalDirection, MoveResult}, node::function::Function, render::{Area, Renderer, Viewport, LayoutComputationProperties}, UnstructuredNode, UnstructuredNodeList, Token};
 
-use crate::{filesystem::{Calculation, ChunkIndex, CalculationResult}, interface::{Colour, ApplicationFramework, DisplayInterface, ButtonInput, ShapeFill, DISPLAY_WIDTH}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, SelectorMenu, ContextMenu, ContextMenuItem, SelectorMenuCallable}, rbop_impl::{RbopContext, RbopSpriteRenderer}, graphics::Sprite};
+use crate::{filesystem::{Calculation, ChunkIndex, CalculationResult, UserFunctions, USER_FUNCTION_NAMES, HISTORY_TEXT_FILE, HISTORY_CSV_FILE}, interface::{Colour, ApplicationFramework, DisplayInterface, ButtonInput, ShapeFill, DISPLAY_WIDTH}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, SelectorMenu, ContextMenu, ContextMenuItem, SelectorMenuCallable, SelectorMenuItem}, rbop_impl::{RbopContext, RbopSpriteRenderer}, graphics::Sprite, maths::{constant_catalog_items, ComplexFormat, FailedSolve}};
 use self::catalog::{CatalogItem, Catalog};
 
 use super::{Application, ApplicationInfo};
@@ -14,6 +14,9 @@ mod test;
 
 pub mod catalog;
 
+mod tools;
+pub use tools::CatalogAction;
+
 const PADDING: u64 = 10;
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
@@ -166,13 +169,7 @@ impl<F: ApplicationFramework> Application for CalculatorApplication<F> {
                 if self.selection == Selection::Expression(i) {
                     // If this is the calculation currently being edited, there is a possibly edited
                     // version in the rbop context, so use that instead of the cached sprite and result
-                    result = match self.rbop_ctx.root.upgrade() {
-                        Ok(structured) => match structured.evaluate(&self.os().filesystem.settings.evaluation_settings()) {
-                            Ok(evaluation_result) => CalculationResult::Ok(evaluation_result.simplify()),
-                            Err(err) => CalculationResult::MathsError(err),
-                        },
-                        Err(err) => CalculationResult::NodeError(err),
-                    };
+                    result = self.evaluate_current();
 
                     new_calculation_sprite = SpriteCacheEntryData::Sprite(
                         RbopSpriteRenderer::draw_context_to_sprite(&mut self.rbop_ctx, Colour::BLACK)
@@ -188,7 +185,16 @@ impl<F: ApplicationFramework> Application for CalculatorApplication<F> {
                 } else {
                     Colour::BLACK
                 };
-                result_sprite = Some(Self::draw_result_to_sprite(&result, result_bg_colour));
+                result_sprite = Some(if let Some(solve) = self.failed_solve(i) {
+                    // The solver's message says more than the MathsError it's recorded as
+                    Self::draw_text_to_sprite(&format!("{}", solve), result_bg_colour)
+                } else {
+                    Self::draw_result_to_sprite(
+                        &result,
+                        self.os().filesystem.settings.values.complex_format,
+                        result_bg_colour,
+                    )
+                });
                 result_height = PADDING as u16 * 3 + result_sprite.as_ref().unwrap().height;
             }
 
@@ -313,37 +319,38 @@ impl<F: ApplicationFramework> Application for CalculatorApplication<F> {
         // Poll for input
         if let Some(input) = self.os_mut().input() {
             if input == OSInput::Button(ButtonInput::Exe) {
-                // Save whatever we're editing
-                self.save_current();
-
-                // Add a new calculation to the end of the list
-                self.calculations.push(Calculation::blank());
-
-                // Move to it
-                self.selection = Selection::Expression(self.calculations.len() - 1);
-                self.reset_scroll();
-
-                // Reset the rbop context, and save the new calculation
-                self.load_current();  
-                self.save_current();
-
-                // Clear the sprite cache
-                self.sprite_cache.clear(self.calculations.len());
+                // Save whatever we're editing, and move on to a new calculation
+                if self.save_current() {
+                    self.start_new_calculation();
+                }
             } else if input == OSInput::Button(ButtonInput::List) {
                 ContextMenu::new(
                     self.os,
                     vec![
                         ContextMenuItem::new_common("Catalog...", |this: &mut Self| {
-                            let catalog = Catalog::new(this.os, "Catalog", Self::catalog_items());
+                            let catalog = Catalog::new(this.os, "Catalog", this.catalog_actions());
                             if let Some(item) = catalog.tick_until_complete() {
-                                let node = item.metadata;
-
-                                this.rbop_ctx.root.insert(
-                                    &mut this.rbop_ctx.nav_path,
-                                    &mut RbopSpriteRenderer::new(),
-                                    this.rbop_ctx.viewport.as_mut(),
-                                    node,
-                                );
+                                this.run_catalog_action(item.metadata);
+                            }
+                        }),
+
+                        ContextMenuItem::new_common("Define function...", |this: &mut Self| {
+                            this.define_function();
+                        }),
+
+                        ContextMenuItem::new_common("Export history", |this: &mut Self| {
+                            let message = if this.os_mut().filesystem.export_history_files().is_some() {
+                                format!("Saved {} and {}, which can be viewed in Files.", HISTORY_TEXT_FILE, HISTORY_CSV_FILE)
+                            } else {
+                                "Could not export history, storage may be full".into()
+                            };
+                            this.os_mut().ui_text_dialog(&message);
+                        }),
+
+                        ContextMenuItem::new_common("Import history...", |this: &mut Self| {
+                            if this.import_history() {
+                                // Reload to pick up the new calculations
+                                return this.os_mut().restart_application();
                             }
                         }),
 
@@ -402,16 +409,87 @@ impl<F: ApplicationFramework> Application for CalculatorApplication<F> {
 }
 
 impl<F: ApplicationFramework> CalculatorApplication<F> {
-    fn save_current(&mut self) {
-        // Evaluate
-        let result = match self.rbop_ctx.root.upgrade() {
-            Ok(structured) => match structured.evaluate(&self.os().filesystem.settings.evaluation_settings()) {
-                Ok(evaluation_result) => CalculationResult::Ok(evaluation_result.simplify()),
-                Err(err) => CalculationResult::MathsError(err),
-            },
-            Err(err) => CalculationResult::NodeError(err),
+    /// Evaluates the expression currently in the rbop context. A failed solve can't be evaluated
+    /// like an expression, so it keeps the result it was recorded with until it is edited.
+    fn evaluate_current(&self) -> CalculationResult {
+        if self.failed_solve(self.selection.index()).is_some() {
+            return self.calculations[self.selection.index()].result.clone();
+        }
+        self.os().filesystem.evaluate(&self.rbop_ctx.root)
+    }
+
+    /// The failed solve recorded by the calculation at the given index, if it has one and, when
+    /// it is being edited, its equation hasn't been changed.
+    fn failed_solve(&self, index: usize) -> Option<&FailedSolve> {
+        let calc = &self.calculations[index];
+        if self.selection == Selection::Expression(index) && calc.root != self.rbop_ctx.root {
+            return None;
+        }
+        calc.solve.as_ref()
+    }
+
+    /// Asks for a text file, such as a note, and adds the expressions in it to the history. Returns
+    /// true if any were added, in which case the application needs to be restarted to show them.
+    fn import_history(&mut self) -> bool {
+        let names = self.os_mut().filesystem.files.list_files()
+            .into_iter()
+            .map(|(_, name)| name)
+            .filter(|name| name.ends_with(".txt"))
+            .collect::<Vec<_>>();
+        if names.is_empty() {
+            self.os_mut().ui_text_dialog("There are no text files to import. Write one expression per line in a note, such as sqrt(2)/3+sin(x)^2.");
+            return false;
+        }
+
+        let name = ContextMenu::new(
+            self.os,
+            names.into_iter().map(|name| ContextMenuItem::Text { text: name.clone(), metadata: name }).collect(),
+            true,
+        ).tick_until_complete().map(|item| item.into_inner());
+        let name = if let Some(name) = name { name } else { return false };
+
+        // Keep whatever is being edited, since the history is about to be reloaded
+        self.save_current();
+
+        let files = &mut self.os_mut().filesystem.files;
+        let contents = files.find_file(&name).and_then(|idx| files.read_file(idx)).map(|file| file.contents);
+        let contents = if let Some(contents) = contents { contents } else {
+            self.os_mut().ui_text_dialog("Could not read file.");
+            return false;
         };
 
+        match self.os_mut().filesystem.import_history_text(&contents) {
+            Ok(count) => {
+                self.os_mut().ui_text_dialog(&format!("Imported {} calculation{}.", count, if count == 1 { "" } else { "s" }));
+                count > 0
+            }
+            Err(err) => {
+                self.os_mut().ui_text_dialog(&format!("{}", err));
+                false
+            }
+        }
+    }
+
+    /// Evaluates the expression being edited, and saves it with its result. Evaluation can't be
+    /// interrupted part way through, but if it takes a while and the user presses AC meanwhile, the
+    /// expression is saved without a result and false is returned, so they can carry on editing.
+    fn save_current(&mut self) -> bool {
+        self.os_mut().begin_busy("Calculating");
+        let result = self.evaluate_current();
+        let cancelled = self.os_mut().busy_tick(None);
+        self.os_mut().end_busy();
+
+        self.save_current_with_result(if cancelled { CalculationResult::None } else { result });
+        !cancelled
+    }
+
+    /// Saves the expression being edited with the given result, rather than evaluating it.
+    fn save_current_with_result(&mut self, result: CalculationResult) {
+        // An edited failed solve is just an expression now
+        if self.failed_solve(self.selection.index()).is_none() {
+            self.calculations[self.selection.index()].solve = None;
+        }
+
         // Save into array
         self.calculations[self.selection.index()].root = self.rbop_ctx.root.clone();
         self.calculations[self.selection.index()].result = result;
@@ -423,6 +501,46 @@ impl<F: ApplicationFramework> CalculatorApplication<F> {
         );
     }
     
+    /// Adds a new blank calculation to the end of the history, and starts editing it.
+    fn start_new_calculation(&mut self) {
+        // Add a new calculation to the end of the list
+        self.calculations.push(Calculation::blank());
+
+        // Move to it
+        self.selection = Selection::Expression(self.calculations.len() - 1);
+        self.reset_scroll();
+
+        // Reset the rbop context, and save the new calculation
+        self.load_current();
+        self.save_current();
+
+        // Clear the sprite cache
+        self.sprite_cache.clear(self.calculations.len());
+    }
+
+    /// Adds a calculation to the history just before the last one, which is where new
+    /// calculations are typed, without disturbing the expression being edited.
+    fn insert_before_last(&mut self, calc: Calculation) {
+        let index = self.calculations.len() - 1;
+        self.calculations.insert(index, calc);
+
+        // Everything after the new calculation moves down one place
+        for i in index..self.calculations.len() {
+            self.os_mut().filesystem.calculations.write_calculation_at_index(
+                ChunkIndex(i as u16),
+                self.calculations[i].clone()
+            );
+        }
+        if self.selection.index() >= index {
+            self.selection = match self.selection {
+                Selection::Expression(i) => Selection::Expression(i + 1),
+                Selection::Result(i) => Selection::Result(i + 1),
+            };
+        }
+
+        self.sprite_cache.clear(self.calculations.len());
+    }
+
     fn load_current(&mut self) {
         // Reset rbop context
         self.rbop_ctx = RbopContext {
@@ -460,7 +578,7 @@ impl<F: ApplicationFramework> CalculatorApplication<F> {
         );
     }
 
-    fn draw_result_to_sprite(result: &CalculationResult, background_colour: Colour) -> Sprite {        
+    fn draw_result_to_sprite(result: &CalculationResult, complex_format: ComplexFormat, background_colour: Colour) -> Sprite {        
         let error_string = match result {
             CalculationResult::Ok(number) => {
                 // Convert the result number into a structured node
@@ -475,14 +593,29 @@ impl<F: ApplicationFramework> CalculatorApplication<F> {
                 );
             },
 
+            CalculationResult::Complex(number) => {
+                return RbopSpriteRenderer::draw_to_sprite::<_>(
+                    &mut number.to_display_node(complex_format),
+                    None,
+                    None,
+                    background_colour,
+                );
+            },
+
             CalculationResult::MathsError(err) => format!("{}", err),
             CalculationResult::NodeError(err) => format!("{}", err),
+            CalculationResult::ComplexError(err) => format!("{}", err),
 
             CalculationResult::None => return Sprite::empty(),
         };
 
         // That `match` didn't return, create a sprite with an error string
-        let (width, _) = Sprite::empty().font.string_size(&error_string);
+        Self::draw_text_to_sprite(&error_string, background_colour)
+    }
+
+    /// Draws a line of text, such as an error, in place of a result.
+    fn draw_text_to_sprite(text: &str, background_colour: Colour) -> Sprite {
+        let (width, _) = Sprite::empty().font.string_size(text);
 
         // We'll use the same height as a digit to avoid wobble when the result is flickering
         // between a number and an error
@@ -495,7 +628,7 @@ impl<F: ApplicationFramework> CalculatorApplication<F> {
 
         let mut sprite = Sprite::new(width as u16, height as u16);
         sprite.fill(background_colour);
-        sprite.print_at(0, 0, &error_string);
+        sprite.print_at(0, 0, text);
         sprite
     }
 
@@ -505,13 +638,62 @@ impl<F: ApplicationFramework> CalculatorApplication<F> {
     }
 
     pub fn catalog_items() -> Vec<CatalogItem<UnstructuredNode>> {
-        vec![
+        let mut items = vec![
             CatalogItem::new("x", "Variable for graph plots", UnstructuredNode::Token(Token::Variable('x'))),
             CatalogItem::new("sqrt", "Compute square root of a value", UnstructuredNode::Sqrt(UnstructuredNodeList::new())),
             CatalogItem::new("pow", "Raise a value to a power", UnstructuredNode::Power(UnstructuredNodeList::new())),
             CatalogItem::new("sin", "Trigonometric sine", UnstructuredNode::new_function_call(Function::Sine)),
             CatalogItem::new("cos", "Trigonometric cosine", UnstructuredNode::new_function_call(Function::Cosine)),
             CatalogItem::new("gcd", "Greatest common denominator of two values", UnstructuredNode::new_function_call(Function::GreatestCommonDenominator)),
-        ]
+        ];
+        items.extend(constant_catalog_items());
+        items.push(CatalogItem::new("i", "Imaginary unit", UnstructuredNode::Token(Token::Variable('i'))));
+        items
+    }
+
+    /// Catalog items for each user-defined function which currently has a definition. Selecting
+    /// one inserts the function's name, ready for its argument to be given in parentheses.
+    pub fn user_function_catalog_items(functions: &UserFunctions<F>) -> Vec<CatalogItem<UnstructuredNode>> {
+        USER_FUNCTION_NAMES.iter()
+            .filter(|name| functions.definition(**name).is_some())
+            .map(|name| CatalogItem::new(
+                format!("{}(x)", name),
+                format!("User-defined function {}", name),
+                UnstructuredNode::Token(Token::Variable(*name)),
+            ))
+            .collect()
+    }
+
+    /// Asks which user-defined function to edit, then prompts for its body. Entering an empty body
+    /// deletes the function.
+    fn define_function(&mut self) {
+        let name = ContextMenu::new(
+            self.os,
+            USER_FUNCTION_NAMES.iter().map(|name| ContextMenuItem::Text {
+                text: match self.os().filesystem.user_functions.definition(*name) {
+                    Some(_) => format!("{}(x) (defined)", name),
+                    None => format!("{}(x)", name),
+                },
+                metadata: *name,
+            }).collect(),
+            true,
+        ).tick_until_complete().map(|item| item.into_inner());
+        let name = if let Some(name) = name { name } else { return };
+
+        let existing = self.os().filesystem.user_functions.definition(name).cloned();
+        let body = if let Some(body) = self.os_mut().ui_input_expression(&format!("{}(x) =", name), existing) {
+            body
+        } else {
+            return
+        };
+
+        let body = if body.root.items.is_empty() { None } else { Some(body) };
+        if let Err(e) = self.os_mut().filesystem.user_functions.set_definition(name, body) {
+            self.os_mut().ui_text_dialog(&format!("{}", e));
+        }
+
+        // Results which call this function may have changed
+        self.save_current();
+        self.sprite_cache.clear(self.calculations.len());
     }
 }
@@ -1,9 +1,11 @@
+use alloc::{boxed::Box, vec, vec::Vec};
 use num_traits::One;
-use rbop::{Number, node::structured::AngleUnit};
+use rbop::{Number, StructuredNode, error::MathsError, UnstructuredNode, UnstructuredNodeList, Token, node::{structured::AngleUnit, compiled::CompiledNode, unstructured::Upgradable}};
+use rust_decimal::Decimal;
 
-use crate::{interface::{ApplicationFramework, ButtonInput}, tests, operating_system::OSInput, filesystem::CalculationResult};
+use crate::{interface::{ApplicationFramework, ButtonInput}, tests, operating_system::OSInput, filesystem::{Calculation, CalculationResult, ChunkIndex, DefinitionError, HistoryImportError, calculations_to_text, calculations_to_csv}, maths::{LinearNotation, LinearParseErrorKind, Complex, ComplexError, ComplexFormat, decimal_from_scientific, Equation, SolverError, FailedSolve}};
 
-use super::CalculatorApplication;
+use super::{CalculatorApplication, Selection};
 
 pub fn test<F: ApplicationFramework>(app: &mut CalculatorApplication<F>) {
     // Note: We can assume a cleared history in here, the test setup does that for us
@@ -87,4 +89,309 @@ pub fn test<F: ApplicationFramework>(app: &mut CalculatorApplication<F>) {
         app.calculations[app.calculations.len() - 2].result,
         CalculationResult::Ok(Number::Decimal(d, _)) if d.is_one()
     ));
-}
\ No newline at end of file
+
+    // Constants (atm is the last item, so this also checks that the catalog scrolls)
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert!(matches!(
+        &app.calculations[app.calculations.len() - 2].result,
+        CalculationResult::Ok(n) if n.to_decimal() == Decimal::from(101325)
+    ));
+
+    // Complex numbers
+    tests::press(app, &[
+        // (1+2i)(3-i)
+        OSInput::Button(ButtonInput::Parentheses),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Add),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::TextMultiTapNew('i'),
+        OSInput::Button(ButtonInput::MoveRight),
+        OSInput::Button(ButtonInput::Parentheses),
+        OSInput::Button(ButtonInput::Digit(3)),
+        OSInput::Button(ButtonInput::Subtract),
+        OSInput::TextMultiTapNew('i'),
+        OSInput::Button(ButtonInput::MoveRight),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert!(matches!(
+        &app.calculations[app.calculations.len() - 2].result,
+        CalculationResult::Complex(c) if c.rounded() == Complex::new(Decimal::from(5), Decimal::from(5))
+    ));
+
+    // Complex calculations with real results are stored as reals
+    tests::press(app, &[
+        OSInput::TextMultiTapNew('i'),
+        OSInput::Button(ButtonInput::Power),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::MoveRight),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert!(matches!(
+        &app.calculations[app.calculations.len() - 2].result,
+        CalculationResult::Ok(n) if n.to_decimal() == Decimal::from(-1)
+    ));
+
+    // Extreme arguments are overflows rather than panics
+    let (huge, tiny) = (decimal_from_scientific(1, 20), decimal_from_scientific(1, -20));
+    assert_eq!(Complex::new(tiny, huge).arg(), Decimal::HALF_PI);
+    assert_eq!(Complex::new(-tiny, -huge).arg(), -Decimal::HALF_PI);
+    assert_eq!(Complex::new(Decimal::ZERO, decimal_from_scientific(1, 27)).exp(), Err(ComplexError::Overflow));
+    assert_eq!(Complex::from_real(decimal_from_scientific(1, 27)).sin(), Err(ComplexError::Overflow));
+    assert!(Complex::from_real(Decimal::from(1000)).cos().is_ok());
+
+    // Negative imaginary numbers are shown without a zero real part
+    assert!(matches!(
+        Complex::new(Decimal::ZERO, Decimal::from(-2)).to_display_node(ComplexFormat::Rectangular),
+        StructuredNode::Multiply(coefficient, _) if matches!(*coefficient, StructuredNode::Number(n) if n == Number::from(-2))
+    ));
+
+    // Solve x^2 = 2 from the catalog, with no bounds
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveRight),
+        OSInput::Button(ButtonInput::MoveRight),
+        OSInput::Button(ButtonInput::Exe),
+
+        // f(x) = x^2
+        OSInput::ShiftedButton(ButtonInput::Digit(0)),
+        OSInput::Button(ButtonInput::Power),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::Exe),
+
+        // g(x) = 2
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::Exe),
+
+        // Guess 1, and skip bounds
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert!(matches!(
+        &app.calculations[app.calculations.len() - 2].result,
+        CalculationResult::Ok(n) if n.to_decimal() == Decimal::new(1414213562373, 12)
+    ));
+
+    // Solve x^2 = -1, which has no real solution, part way through typing another expression.
+    // The failure gets its own entry in the history, and what was being typed is left alone
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::Digit(7)),
+
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveRight),
+        OSInput::Button(ButtonInput::MoveRight),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::ShiftedButton(ButtonInput::Digit(0)),
+        OSInput::Button(ButtonInput::Power),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Subtract),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    let failed = app.calculations[app.calculations.len() - 2].clone();
+    assert_eq!(failed.root.to_linear(), "x^2=-1");
+    assert_eq!(failed.result, CalculationResult::MathsError(MathsError::Overflow));
+    assert_eq!(failed.solve, Some(FailedSolve { guess: Number::from(1), bounds: None, error: SolverError::NoConvergence }));
+    assert_eq!(failed.result_text().as_deref(), Some("Solver did not converge, from x=1"));
+    assert_eq!(app.os.filesystem.calculations.read_calculation_at_index(ChunkIndex(app.calculations.len() as u16 - 2)), Some(failed.clone()));
+    assert_eq!(app.selection, Selection::Expression(app.calculations.len() - 1));
+    assert_eq!(app.rbop_ctx.root.to_linear(), "7");
+
+    // Going back to the failed solve keeps its error rather than evaluating the equation
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::MoveUp),
+        OSInput::Button(ButtonInput::MoveUp),
+    ]);
+    assert_eq!(app.selection, Selection::Expression(app.calculations.len() - 2));
+    assert_eq!(app.evaluate_current(), failed.result);
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+    ]);
+    assert_eq!(app.calculations[app.calculations.len() - 2], failed);
+    assert_eq!(app.rbop_ctx.root.to_linear(), "7");
+    tests::press(app, &[OSInput::Button(ButtonInput::Clear)]);
+
+    // Solver edge cases
+    let settings = app.os.filesystem.settings.evaluation_settings();
+    let compile = |node| CompiledNode::from_structured(node, Some('x'), &settings);
+    let x_squared = || StructuredNode::Power(
+        Box::new(StructuredNode::Variable('x')),
+        Box::new(StructuredNode::Number(Number::from(2))),
+    );
+    let equation = |rhs: i64| Equation { left: compile(x_squared()), right: compile(StructuredNode::Number(Number::from(rhs))) };
+
+    // Bisection takes over where Newton's method can't start, at the turning point
+    assert_eq!(equation(2).solve(Decimal::ZERO, Some((Decimal::ZERO, Decimal::TWO))), Ok(Decimal::new(1414213562373, 12)));
+    assert_eq!(equation(2).solve(Decimal::ONE, Some((Decimal::ZERO, Decimal::ONE))), Err(SolverError::OutOfBounds));
+    assert_eq!(equation(2).solve(Decimal::ONE, Some((Decimal::ONE, Decimal::ZERO))), Err(SolverError::InvalidBounds));
+    assert_eq!(equation(-1).solve(Decimal::ONE, None), Err(SolverError::NoConvergence));
+
+    // Define f(x) = x^2 + 1, then evaluate f(3), selecting f from the end of the catalog
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::ShiftedButton(ButtonInput::Digit(0)),
+        OSInput::Button(ButtonInput::Power),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::MoveRight),
+        OSInput::Button(ButtonInput::Add),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::Parentheses),
+        OSInput::Button(ButtonInput::Digit(3)),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert!(app.os.filesystem.user_functions.definition('f').is_some());
+    assert!(matches!(
+        app.calculations[app.calculations.len() - 2].result,
+        CalculationResult::Ok(Number::Rational(10, 1))
+    ));
+
+    // Functions may call each other, and calls are expanded with their arguments in parentheses
+    let functions = &mut app.os.filesystem.user_functions;
+    functions.set_definition('g', Some(tests::linear("2f(x)"))).unwrap();
+    let expanded = functions.expand(&tests::linear("g(1)"));
+    let settings = app.os.filesystem.settings.evaluation_settings();
+    assert_eq!(expanded.upgrade().unwrap().evaluate(&settings).map(|n| n.simplify()), Ok(Number::Rational(4, 1)));
+
+    // Functions can't call themselves, directly or through other functions
+    let functions = &mut app.os.filesystem.user_functions;
+    assert_eq!(functions.set_definition('h', Some(tests::linear("h(x)+1"))), Err(DefinitionError::Recursive));
+    assert_eq!(functions.set_definition('f', Some(tests::linear("g(x)"))), Err(DefinitionError::Recursive));
+    assert!(functions.definition('h').is_none());
+    assert_eq!(functions.definition('f').map(|f| &f.root.items), Some(&tests::linear("x^2+1").root.items));
+
+    // Deleted functions are no longer expanded
+    let functions = &mut app.os.filesystem.user_functions;
+    functions.set_definition('g', None).unwrap();
+    assert!(functions.definition('g').is_none());
+
+    // If storage is full, redefining a function fails and leaves the old definition intact
+    let mut filler = vec![];
+    while let Some(address) = functions.table.allocate_chunks(1) {
+        filler.push(address);
+    }
+    assert_eq!(functions.set_definition('f', Some(tests::linear("x+1"))), Err(DefinitionError::StorageFull));
+    functions.load_into_self();
+    assert_eq!(functions.definition('f').map(|f| &f.root.items), Some(&tests::linear("x^2+1").root.items));
+    for address in filler {
+        functions.table.free_chunks(address, 1);
+    }
+
+    // Linear notation converts to nodes and back again
+    let parsed = tests::linear("(1+2)/3").root;
+    assert_eq!(parsed.items, vec![UnstructuredNode::Fraction(
+        UnstructuredNodeList { items: vec![
+            UnstructuredNode::Token(Token::Digit(1)),
+            UnstructuredNode::Token(Token::Add),
+            UnstructuredNode::Token(Token::Digit(2)),
+        ] },
+        UnstructuredNodeList { items: vec![UnstructuredNode::Token(Token::Digit(3))] },
+    )]);
+    for text in [
+        "1.5*x-3", "sqrt(2)/3+sin(x)^2", "(1+2)/3", "2x/3", "1/2 ^3", "x^(2x)^3", "gcd(12, 18)",
+        "1 2/3", "sin (x)", "((x))/2", "1/(x+1)^2", "a/b/c", "a/(b/c)",
+    ] {
+        assert_eq!(tests::linear(text).to_linear(), text);
+    }
+    assert_eq!(UnstructuredNodeList::from_linear("/2").unwrap_err().kind, LinearParseErrorKind::MissingNumerator);
+    assert_eq!(UnstructuredNodeList::from_linear("(1").unwrap_err().kind, LinearParseErrorKind::UnexpectedEnd);
+    assert_eq!(UnstructuredNodeList::from_linear("gcd(1)").unwrap_err().kind, LinearParseErrorKind::WrongArgumentCount);
+    assert_eq!(UnstructuredNodeList::from_linear("1+2)").unwrap_err().position, 3);
+
+    // Expressions can be entered in linear notation rather than with key presses
+    assert_eq!(calculate(app, "1/2+gcd(12, 18)^2"), CalculationResult::Ok(Number::Rational(73, 2)));
+    assert_eq!(calculate(app, "(1+2i)(3-i)").to_text().as_deref(), Some("5+5i"));
+    assert_eq!(app.calculations[app.calculations.len() - 2].root.to_linear(), "(1+2i)(3-i)");
+
+    // History can be exported as text and CSV
+    let calculation = |text: &str| {
+        let root = tests::linear(text);
+        let result = app.os.filesystem.evaluate(&root);
+        Calculation { root, result, solve: None }
+    };
+    let calculations = vec![calculation("1+2"), Calculation::blank(), calculation("(1+2)/3"), calculation("2+i")];
+    assert_eq!(calculations_to_text(&calculations), "1+2 = 3\n(1+2)/3 = 1\n2+i = 2+i\n");
+    assert_eq!(
+        calculations_to_csv(&calculations),
+        "expression,result\n\"1+2\",\"3\"\n\"(1+2)/3\",\"1\"\n\"2+i\",\"2+i\"\n",
+    );
+
+    // And imported from text, with results recalculated
+    let filesystem = &mut app.os.filesystem;
+    filesystem.calculations.table.clear(false);
+    assert_eq!(filesystem.import_history_text("# Some sums\n1+2 = 99\n\n2*3\n"), Ok(2));
+    let imported = filesystem.calculations.read_calculations().unwrap();
+    assert_eq!(imported.iter().map(|c| c.result.clone()).collect::<Vec<_>>(), vec![
+        CalculationResult::Ok(Number::Rational(3, 1)),
+        CalculationResult::Ok(Number::Rational(6, 1)),
+    ]);
+
+    // Nothing is imported if a line is invalid
+    assert!(matches!(
+        filesystem.import_history_text("4+4\n1+)\n"),
+        Err(HistoryImportError::Parse { line: 2, .. })
+    ));
+    assert_eq!(filesystem.calculations.read_calculations().unwrap().len(), 2);
+    filesystem.calculations.table.clear(false);
+}
+
+/// Enters an expression written in linear notation into the calculator, presses EXE, and returns
+/// the result.
+fn calculate<F: ApplicationFramework>(app: &mut CalculatorApplication<F>, text: &str) -> CalculationResult {
+    app.rbop_ctx.set_root(tests::linear(text));
+    tests::press(app, &[OSInput::Button(ButtonInput::Exe)]);
+    app.calculations[app.calculations.len() - 2].result.clone()
+}
@@ -0,0 +1,124 @@
+use alloc::{format, vec::Vec};
+use rbop::{Number, Token, UnstructuredNode, UnstructuredNodeList, node::{unstructured::{UnstructuredNodeRoot, Upgradable}, compiled::CompiledNode}};
+
+use crate::{interface::ApplicationFramework, operating_system::OsAccessor, filesystem::{Calculation, CalculationResult}, maths::{Equation, FailedSolve, number_to_node}, rbop_impl::RbopSpriteRenderer};
+
+use super::{CalculatorApplication, catalog::CatalogItem};
+
+/// An item in the calculator's catalog. Besides nodes to insert into the expression, the catalog
+/// offers tools, which prompt for their inputs and then insert their numeric result.
+#[derive(Debug)]
+pub enum CatalogAction {
+    Insert(UnstructuredNode),
+    Solve,
+}
+
+impl<F: ApplicationFramework> CalculatorApplication<F> {
+    /// The items shown in the calculator's own catalog. This is every item from `catalog_items`,
+    /// followed by the tools, and then any user-defined functions.
+    pub fn catalog_actions(&self) -> Vec<CatalogItem<CatalogAction>> {
+        let mut items = Self::catalog_items().into_iter()
+            .map(|item| CatalogItem::new(item.name, item.description, CatalogAction::Insert(item.metadata)))
+            .collect::<Vec<_>>();
+        items.push(CatalogItem::new("solve", "Numerically solve an equation for x", CatalogAction::Solve));
+        items.extend(
+            Self::user_function_catalog_items(&self.os().filesystem.user_functions).into_iter()
+                .map(|item| CatalogItem::new(item.name, item.description, CatalogAction::Insert(item.metadata)))
+        );
+        items
+    }
+
+    /// Performs a catalog action, inserting its node or result at the cursor.
+    pub fn run_catalog_action(&mut self, action: CatalogAction) {
+        let result = match action {
+            CatalogAction::Insert(node) => Some(node),
+            CatalogAction::Solve => match self.solve_tool() {
+                Some(Ok(x)) => Some(number_to_node(x)),
+                Some(Err(failed)) => {
+                    // Record the failure as its own calculation, leaving the expression being
+                    // edited alone
+                    self.insert_before_last(failed);
+                    None
+                }
+                None => None,
+            },
+        };
+        let node = if let Some(node) = result { node } else { return };
+
+        self.rbop_ctx.root.insert(
+            &mut self.rbop_ctx.nav_path,
+            &mut RbopSpriteRenderer::new(),
+            self.rbop_ctx.viewport.as_mut(),
+            node,
+        );
+    }
+
+    /// Prompts for an expression in terms of `x`, and compiles it, returning it in both forms. If
+    /// the expression is invalid, shows the error and returns `None`.
+    fn input_compiled(&mut self, title: &str) -> Option<(UnstructuredNodeRoot, CompiledNode)> {
+        let unstructured = self.os_mut().ui_input_expression(title, None)?;
+        match self.os().filesystem.user_functions.expand(&unstructured).upgrade() {
+            Ok(structured) => {
+                let settings = self.os().filesystem.settings.evaluation_settings();
+                Some((unstructured, CompiledNode::from_structured(structured, Some('x'), &settings)))
+            }
+            Err(e) => {
+                self.os_mut().ui_text_dialog(&format!("{:?}", e));
+                None
+            }
+        }
+    }
+
+    /// Prompts for a number which may be left blank, in which case this returns `Some(None)`. If
+    /// the user opens the menu, returns `None`.
+    fn input_optional_number(&mut self, title: &str) -> Option<Option<Number>> {
+        loop {
+            let unstructured = self.os_mut().ui_input_expression(title, None)?;
+            if unstructured.root.items.is_empty() {
+                return Some(None);
+            }
+
+            let settings = self.os().filesystem.settings.evaluation_settings();
+            match self.os().filesystem.user_functions.expand(&unstructured).upgrade().map_err(|e| format!("{:?}", e))
+                .and_then(|s| s.evaluate(&settings).map_err(|e| format!("{:?}", e)))
+            {
+                Ok(number) => return Some(Some(number)),
+                Err(e) => self.os_mut().ui_text_dialog(&e),
+            }
+        }
+    }
+
+    /// Asks for an equation `f(x) = g(x)`, an initial guess, and optional bounds, then solves the
+    /// equation numerically. If it can't be solved, returns a calculation which records the
+    /// failure, with the equation as its expression. Returns `None` if the user leaves a prompt.
+    fn solve_tool(&mut self) -> Option<Result<Number, Calculation>> {
+        let (left_root, left) = self.input_compiled("Solve: left side, f(x)")?;
+        let (right_root, right) = self.input_compiled("Solve: right side, g(x)")?;
+        let (guess, _) = self.os_mut().ui_input_expression_and_evaluate("Initial guess for x", None, || ())?;
+
+        let bounds = match self.input_optional_number("Lower bound (EXE to skip)")? {
+            Some(lower) => {
+                let (upper, _) = self.os_mut().ui_input_expression_and_evaluate("Upper bound", None, || ())?;
+                Some((lower, upper))
+            }
+            None => None,
+        };
+
+        let decimal_bounds = bounds.map(|(lower, upper)| (lower.to_decimal(), upper.to_decimal()));
+        match (Equation { left, right }).solve(guess.to_decimal(), decimal_bounds) {
+            Ok(x) => Some(Ok(Number::from(x).simplify())),
+            Err(error) => {
+                // There's no equals node, so the equation is shown with an `=` variable
+                let mut items = left_root.root.items;
+                items.push(UnstructuredNode::Token(Token::Variable('=')));
+                items.extend(right_root.root.items);
+
+                Some(Err(Calculation {
+                    root: UnstructuredNodeRoot { root: UnstructuredNodeList { items } },
+                    result: CalculationResult::MathsError(error.to_maths_error()),
+                    solve: Some(FailedSolve { guess, bounds, error }),
+                }))
+            }
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use alloc::{format, string::String, vec, vec::Vec};
+use num_traits::Zero;
+use rbop::{Number, StructuredNode, node::unstructured::UnstructuredNodeRoot};
+
+use crate::{interface::{Colour, ApplicationFramework, ButtonInput, ShapeFill, DISPLAY_WIDTH}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer}, maths::{Complex, ComplexFormat, Matrix, MatrixError, polynomial_roots}, rbop_impl::RbopSpriteRenderer};
+use super::{Application, ApplicationInfo};
+
+mod test;
+
+/// The names of unknowns in linear systems, in order.
+const UNKNOWNS: [char; 4] = ['x', 'y', 'z', 'w'];
+
+/// A kind of equation which can be solved.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+enum EquationMode {
+    /// A polynomial in `x` of the given degree, equal to zero.
+    Polynomial(usize),
+
+    /// A system of linear equations with the given number of unknowns, and the same number of
+    /// equations.
+    LinearSystem(usize),
+}
+
+const MODES: [EquationMode; 6] = [
+    EquationMode::Polynomial(2),
+    EquationMode::Polynomial(3),
+    EquationMode::Polynomial(4),
+    EquationMode::LinearSystem(2),
+    EquationMode::LinearSystem(3),
+    EquationMode::LinearSystem(4),
+];
+
+impl EquationMode {
+    fn name(&self) -> String {
+        match self {
+            EquationMode::Polynomial(degree) => format!("Polynomial, degree {}", degree),
+            EquationMode::LinearSystem(n) => format!("Linear system, {} unknowns", n),
+        }
+    }
+
+    fn coefficient_count(&self) -> usize {
+        match self {
+            EquationMode::Polynomial(degree) => degree + 1,
+            EquationMode::LinearSystem(n) => n * (n + 1),
+        }
+    }
+
+    /// The title of the prompt for the coefficient at the given index.
+    fn coefficient_title(&self, index: usize) -> String {
+        match *self {
+            EquationMode::Polynomial(degree) => match degree - index {
+                0 => "Constant term".into(),
+                1 => "Coefficient of x".into(),
+                power => format!("Coefficient of x^{}", power),
+            },
+
+            // Each equation is a row of coefficients, followed by the right-hand side
+            EquationMode::LinearSystem(n) => {
+                let (equation, column) = (index / (n + 1), index % (n + 1));
+                if column == n {
+                    format!("Eq. {}: right-hand side", equation + 1)
+                } else {
+                    format!("Eq. {}: coefficient of {}", equation + 1, UNKNOWNS[column])
+                }
+            }
+        }
+    }
+}
+
+/// One solution to an equation.
+#[derive(PartialEq, Eq, Clone, Debug)]
+enum Solution {
+    /// An exact or real solution.
+    Real(Number),
+
+    /// A complex root of a polynomial, with a non-zero imaginary part.
+    Complex(Complex),
+}
+
+impl Solution {
+    fn to_display_node(&self, complex_format: ComplexFormat) -> StructuredNode {
+        match self {
+            Solution::Real(number) => StructuredNode::Number(*number),
+            Solution::Complex(complex) => complex.to_display_node(complex_format),
+        }
+    }
+}
+
+pub struct EquationApplication<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+
+    /// The index into `MODES` of the selected mode.
+    selected_index: usize,
+
+    /// The most recently entered coefficients for each mode, so that an equation can be tweaked
+    /// and solved again without entering every coefficient.
+    coefficients: Vec<Vec<Number>>,
+
+    /// The labelled solutions to the last equation solved.
+    solutions: Vec<(String, Solution)>,
+}
+
+os_accessor!(EquationApplication<F>);
+
+impl<F: ApplicationFramework> Application for EquationApplication<F> {
+    type Framework = F;
+
+    fn info() -> ApplicationInfo {
+        ApplicationInfo {
+            name: "Equation".into(),
+            visible: true,
+        }
+    }
+
+    fn new(os: OperatingSystemPointer<F>) -> Self {
+        Self {
+            os,
+            selected_index: 0,
+            coefficients: MODES.iter().map(|mode| vec![Number::zero(); mode.coefficient_count()]).collect(),
+            solutions: vec![],
+        }
+    }
+
+    fn tick(&mut self) {
+        self.draw();
+
+        match self.os_mut().input() {
+            Some(OSInput::Button(ButtonInput::MoveUp)) if self.selected_index > 0 =>
+                self.selected_index -= 1,
+            Some(OSInput::Button(ButtonInput::MoveDown)) if self.selected_index < MODES.len() - 1 =>
+                self.selected_index += 1,
+            Some(OSInput::Button(ButtonInput::Exe)) => self.solve(),
+            _ => (),
+        }
+    }
+
+    fn test(&mut self) {
+        test::test(self);
+    }
+}
+
+impl<F: ApplicationFramework> EquationApplication<F> {
+    /// Draws the list of modes.
+    fn draw(&self) {
+        let os = self.os_mut();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title("Equation");
+
+        for (i, mode) in MODES.iter().enumerate() {
+            let y = 40 + i as i16 * 35;
+            if i == self.selected_index {
+                os.display_sprite.draw_rect(5, y, DISPLAY_WIDTH - 10, 32, Colour::BLUE, ShapeFill::Filled, 7);
+            }
+            os.display_sprite.print_at(15, y + 6, &mode.name());
+        }
+
+        os.display_sprite.print_at(5, 290, "[EXE] Solve");
+        os.draw();
+    }
+
+    /// Prompts for the coefficients of the selected mode, then solves and shows the solutions.
+    fn solve(&mut self) {
+        let mode = MODES[self.selected_index];
+        for i in 0..mode.coefficient_count() {
+            let current = self.coefficients[self.selected_index][i];
+            let root = if current.is_zero() { None } else { Some(UnstructuredNodeRoot::from_number(current)) };
+            match self.os_mut().ui_input_expression_and_evaluate(&mode.coefficient_title(i), root, || ()) {
+                Some((value, _)) => self.coefficients[self.selected_index][i] = value,
+                None => return,
+            }
+        }
+
+        let coefficients = &self.coefficients[self.selected_index];
+        let solutions = match mode {
+            EquationMode::Polynomial(_) => {
+                let decimals = coefficients.iter().map(|c| c.to_decimal()).collect::<Vec<_>>();
+                polynomial_roots(&decimals)
+                    .map(|roots| roots.iter().enumerate().map(|(i, root)| (
+                        format!("x{}", i + 1),
+                        if root.is_real() { Solution::Real(Number::from(root.re)) } else { Solution::Complex(*root) },
+                    )).collect())
+                    .map_err(|e| format!("{}", e))
+            }
+
+            EquationMode::LinearSystem(n) => {
+                let rows = coefficients.chunks(n + 1);
+                let system = Matrix::from_rows(rows.clone().map(|row| row[..n].to_vec()).collect());
+                let rhs = Matrix::from_rows(rows.map(|row| vec![row[n]]).collect());
+                system.inverse()
+                    .and_then(|inverse| inverse.multiply(&rhs))
+                    .map(|solution| (0..n).map(|i| (
+                        format!("{}", UNKNOWNS[i]),
+                        Solution::Real(solution[(i, 0)]),
+                    )).collect())
+                    .map_err(|e| match e {
+                        MatrixError::Singular => "No unique solution".into(),
+                        e => format!("{}", e),
+                    })
+            }
+        };
+
+        match solutions {
+            Ok(solutions) => {
+                self.solutions = solutions;
+                self.show_solutions();
+            }
+            Err(message) => self.os_mut().ui_text_dialog(&message),
+        }
+    }
+
+    /// Shows the last set of solutions until EXE is pressed.
+    fn show_solutions(&mut self) {
+        let os = self.os_mut();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title("Solutions");
+
+        let complex_format = os.filesystem.settings.values.complex_format;
+        let mut y = 40;
+        for (label, solution) in &self.solutions {
+            os.display_sprite.print_at(10, y, &format!("{} =", label));
+            let sprite = RbopSpriteRenderer::draw_to_sprite(
+                &mut solution.to_display_node(complex_format),
+                None,
+                None,
+                Colour::BLACK,
+            );
+            os.display_sprite.draw_sprite(60, y, &sprite);
+            y += sprite.height.max(25) as i16 + 10;
+        }
+
+        os.display_sprite.print_at(5, 290, "[EXE] Back");
+        os.draw();
+
+        while os.input() != Some(OSInput::Button(ButtonInput::Exe)) {}
+    }
+}
@@ -0,0 +1,76 @@
+use alloc::vec;
+use rbop::Number;
+use rust_decimal::Decimal;
+
+use crate::{interface::{ApplicationFramework, ButtonInput}, tests, operating_system::OSInput, maths::{Complex, polynomial_roots, PolynomialError}};
+
+use super::{EquationApplication, Solution};
+
+pub fn test<F: ApplicationFramework>(app: &mut EquationApplication<F>) {
+    // x^2 + 3x + 2 = 0
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(3)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::Exe),
+
+        // Dismiss solutions
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.solutions.len(), 2);
+    assert!(matches!(app.solutions[0].1, Solution::Real(n) if n.to_decimal() == Decimal::from(-2)));
+    assert!(matches!(app.solutions[1].1, Solution::Real(n) if n.to_decimal() == Decimal::from(-1)));
+
+    // 2x + y = 5, x + 3y = 10
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(5)),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(3)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Digit(0)),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.solutions, vec![
+        ("x".into(), Solution::Real(Number::Rational(1, 1))),
+        ("y".into(), Solution::Real(Number::Rational(3, 1))),
+    ]);
+
+    // x^2 + 1 has complex roots
+    let one = Decimal::ONE;
+    assert_eq!(
+        polynomial_roots(&[one, Decimal::ZERO, one]),
+        Ok(vec![Complex::new(Decimal::ZERO, one), Complex::new(Decimal::ZERO, -one)]),
+    );
+
+    // (x - 1)^2 (x + 2) = x^3 - 3x + 2, which has a repeated root
+    assert_eq!(
+        polynomial_roots(&[one, Decimal::ZERO, Decimal::from(-3), Decimal::TWO]),
+        Ok(vec![Complex::from_real(Decimal::from(-2)), Complex::from_real(one), Complex::from_real(one)]),
+    );
+
+    // x^4 - 1
+    let roots = polynomial_roots(&[one, Decimal::ZERO, Decimal::ZERO, Decimal::ZERO, -one]).unwrap();
+    assert_eq!(roots.len(), 4);
+    assert_eq!(roots[0], Complex::from_real(-one));
+    assert_eq!(roots[1], Complex::from_real(one));
+
+    assert_eq!(polynomial_roots(&[Decimal::ZERO, one, one]), Err(PolynomialError::LeadingCoefficientZero));
+}
@@ -0,0 +1,199 @@
+use alloc::{string::String, vec, vec::Vec};
+
+use crate::{interface::{Colour, ApplicationFramework, ButtonInput}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, FullPageMenu, FullPageMenuItem, FullPageMenuItemDecorator, ContextMenu, ContextMenuItem, SelectorMenu, SelectorMenuItem}, filesystem::{ChunkIndex, File}};
+use super::{Application, ApplicationInfo};
+
+mod test;
+
+/// A file being viewed, with its contents split into rows which fit the screen.
+struct OpenFile {
+    name: String,
+    rows: Vec<String>,
+    scroll_row: usize,
+}
+
+pub struct FilesApplication<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+    menu: FullPageMenu<F>,
+
+    /// The index of each file in `menu`, in the same order.
+    files: Vec<ChunkIndex>,
+
+    /// The file being viewed, if any. While this is set, its contents are shown instead of the
+    /// list of files.
+    open: Option<OpenFile>,
+}
+
+os_accessor!(FilesApplication<F>);
+
+/// An action chosen from the List menu while the list of files is shown.
+enum ListAction {
+    Rename,
+    Delete,
+    ConnectToComputer,
+}
+
+impl<F: ApplicationFramework> Application for FilesApplication<F> {
+    type Framework = F;
+
+    fn info() -> ApplicationInfo {
+        ApplicationInfo {
+            name: "Files".into(),
+            visible: true,
+        }
+    }
+
+    fn new(os: OperatingSystemPointer<F>) -> Self {
+        let mut app = Self {
+            os,
+            menu: FullPageMenu::new(os, vec![]),
+            files: vec![],
+            open: None,
+        };
+        app.load_files();
+        app
+    }
+
+    fn tick(&mut self) {
+        if self.open.is_some() {
+            self.tick_viewer();
+        } else {
+            self.tick_list();
+        }
+    }
+
+    fn test(&mut self) {
+        test::test(self);
+    }
+}
+
+impl<F: ApplicationFramework> FilesApplication<F> {
+    const VIEWER_PADDING: i16 = 5;
+
+    /// Reloads the list of files from storage, keeping the selection where possible.
+    fn load_files(&mut self) {
+        let files = self.os_mut().filesystem.files.list_files();
+
+        self.files = files.iter().map(|(idx, _)| *idx).collect();
+        let items = files.into_iter()
+            .map(|(_, name)| FullPageMenuItem {
+                icon: Self::icon_for_file_name(&name).into(),
+                title: name,
+                decorator: FullPageMenuItemDecorator::None,
+            })
+            .collect();
+
+        // Recreate the menu, and restore the scroll position by moving down to the previous
+        // selection
+        let selected_index = self.menu.selected_index.min(self.files.len().saturating_sub(1));
+        self.menu = FullPageMenu::new(self.os, items);
+        for _ in 0..selected_index {
+            self.menu.move_down();
+        }
+    }
+
+    /// Picks an icon for a file, based on which application its extension belongs to.
+    fn icon_for_file_name(name: &str) -> &'static str {
+        if name.ends_with(".bas") {
+            "programs_icon"
+        } else if name.ends_with(".txt") {
+            "notes_icon"
+        } else {
+            "files_icon"
+        }
+    }
+
+    fn tick_list(&mut self) {
+        self.os_mut().display_sprite.fill(Colour::BLACK);
+        self.os_mut().ui_draw_title("Files");
+        self.menu.draw();
+        self.os_mut().draw();
+
+        match self.os_mut().input() {
+            Some(OSInput::Button(ButtonInput::MoveUp)) if !self.files.is_empty() => self.menu.move_up(),
+            Some(OSInput::Button(ButtonInput::MoveDown)) if !self.files.is_empty() => self.menu.move_down(),
+            Some(OSInput::Button(ButtonInput::Exe)) if !self.files.is_empty() => self.open_selected(),
+            Some(OSInput::Button(ButtonInput::List)) => match self.list_menu() {
+                Some(ListAction::Rename) => self.rename_selected(),
+                Some(ListAction::Delete) => {
+                    let idx = self.files[self.menu.selected_index];
+                    self.os_mut().filesystem.files.delete_file(idx);
+                    self.load_files();
+                }
+                Some(ListAction::ConnectToComputer) => {
+                    self.os_mut().usb_mass_storage_mode();
+                    self.load_files();
+                }
+                None => (),
+            },
+            _ => (),
+        }
+    }
+
+    fn list_menu(&mut self) -> Option<ListAction> {
+        let mut items = vec![];
+        if !self.files.is_empty() {
+            items.push(ContextMenuItem::Text { text: "Rename...".into(), metadata: ListAction::Rename });
+            items.push(ContextMenuItem::Text { text: "Delete".into(), metadata: ListAction::Delete });
+        }
+        items.push(ContextMenuItem::Text { text: "Connect to computer".into(), metadata: ListAction::ConnectToComputer });
+        ContextMenu::new(self.os, items, true).tick_until_complete().map(|item| item.into_inner())
+    }
+
+    fn rename_selected(&mut self) {
+        let idx = self.files[self.menu.selected_index];
+        let old_name = self.menu.items[self.menu.selected_index].title.clone();
+
+        let name = if let Some(name) = self.os_mut().ui_input_text("File name", &old_name) { name } else { return };
+        let name = name.trim();
+        if name.is_empty() || name == old_name {
+            return;
+        }
+
+        if self.os_mut().filesystem.files.find_file(name).is_some() {
+            self.os_mut().ui_text_dialog("A file with this name already exists");
+        } else if self.os_mut().filesystem.files.rename_file(idx, name).is_none() {
+            self.os_mut().ui_text_dialog("Could not rename file, storage may be full");
+        }
+        self.load_files();
+    }
+
+    fn open_selected(&mut self) {
+        let idx = self.files[self.menu.selected_index];
+        let File { name, contents } = if let Some(file) = self.os_mut().filesystem.files.read_file(idx) { file } else { return };
+
+        // Wrap each line separately, so that line breaks in the file are kept
+        let width = self.os().display_sprite.width - Self::VIEWER_PADDING as u16 * 2;
+        let mut rows = vec![];
+        for line in contents.split('\n') {
+            let (wrapped, _, _) = self.os_mut().display_sprite.wrap_text(line, width);
+            rows.extend(wrapped);
+        }
+
+        self.open = Some(OpenFile { name, rows, scroll_row: 0 });
+    }
+
+    fn tick_viewer(&mut self) {
+        let line_height = self.os().display_sprite.font.string_size("A").1;
+        let top = OperatingSystem::<F>::TITLE_BAR_HEIGHT as i16 + Self::VIEWER_PADDING;
+        let visible_rows = ((self.os().display_sprite.height as i16 - top) / line_height) as usize;
+
+        // Copy the pointer, so that the OS can be borrowed alongside the open file
+        let mut os = self.os;
+        let open = self.open.as_mut().unwrap();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title(&open.name);
+        for (i, row) in open.rows.iter().skip(open.scroll_row).take(visible_rows).enumerate() {
+            os.display_sprite.print_at(Self::VIEWER_PADDING, top + i as i16 * line_height, row);
+        }
+        os.draw();
+
+        let max_scroll_row = open.rows.len().saturating_sub(visible_rows);
+        match os.input() {
+            Some(OSInput::Button(ButtonInput::MoveUp)) => open.scroll_row = open.scroll_row.saturating_sub(1),
+            Some(OSInput::Button(ButtonInput::MoveDown)) => open.scroll_row = (open.scroll_row + 1).min(max_scroll_row),
+            Some(OSInput::Button(ButtonInput::Exe)) | Some(OSInput::Button(ButtonInput::Delete)) => self.open = None,
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::{format, string::String, vec, vec::Vec};
+use rbop::{node::structured::AngleUnit, serialize::Serializable};
+
+use crate::{interface::{ApplicationFramework, ButtonInput}, tests, operating_system::{OSInput, OsAccessor}, filesystem::{File, Calculation, MAX_FILES}};
+
+use super::FilesApplication;
+
+pub fn test<F: ApplicationFramework>(app: &mut FilesApplication<F>) {
+    // Start from a known set of files
+    let mut long_contents = String::new();
+    for _ in 0..30 {
+        long_contents.push_str("line\n");
+    }
+    let files = &mut app.os_mut().filesystem.files;
+    files.table.clear(false);
+    files.save_file(&File { name: "b.bas".into(), contents: "print 1".into() }).unwrap();
+    files.save_file(&File { name: "a.txt".into(), contents: long_contents }).unwrap();
+    app.load_files();
+
+    let titles = |app: &FilesApplication<F>| app.menu.items.iter().map(|i| i.title.clone()).collect::<Vec<_>>();
+    assert_eq!(titles(app), ["a.txt", "b.bas"]);
+    assert_eq!(app.menu.items[0].icon, "notes_icon");
+    assert_eq!(app.menu.items[1].icon, "programs_icon");
+
+    // View the first file, scroll down, and go back
+    tests::press(app, &[OSInput::Button(ButtonInput::Exe)]);
+    assert_eq!(app.open.as_ref().unwrap().name, "a.txt");
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+    ]);
+    assert_eq!(app.open.as_ref().unwrap().scroll_row, 2);
+    tests::press(app, &[OSInput::Button(ButtonInput::Exe)]);
+    assert!(app.open.is_none());
+
+    // Rename the second file
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Clear),
+        OSInput::TextMultiTapNew('c'),
+        OSInput::Button(ButtonInput::Point),
+        OSInput::TextMultiTapNew('b'),
+        OSInput::TextMultiTapNew('a'),
+        OSInput::TextMultiTapNew('s'),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(titles(app), ["a.txt", "c.bas"]);
+    assert_eq!(app.menu.selected_index, 1);
+    let idx = app.os_mut().filesystem.files.find_file("c.bas").unwrap();
+    assert_eq!(app.os_mut().filesystem.files.read_file(idx).unwrap().contents, "print 1");
+
+    // Delete it
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(titles(app), ["a.txt"]);
+    assert_eq!(app.menu.selected_index, 0);
+
+    // Export a disk image, change everything it contains, then import it to put things back
+    let filesystem = &mut app.os_mut().filesystem;
+    filesystem.calculations.replace_calculations(&[Calculation::blank()]).unwrap();
+    let original_settings = filesystem.settings.values.clone();
+    let image = filesystem.export_disk_image().unwrap();
+
+    filesystem.calculations.replace_calculations(&[]).unwrap();
+    filesystem.settings.values.angle_unit = match original_settings.angle_unit {
+        AngleUnit::Degree => AngleUnit::Radian,
+        AngleUnit::Radian => AngleUnit::Degree,
+    };
+    filesystem.settings.save().unwrap();
+    let idx = filesystem.files.find_file("a.txt").unwrap();
+    filesystem.files.delete_file(idx).unwrap();
+    filesystem.files.save_file(&File { name: "new?.txt".into(), contents: "x".into() }).unwrap();
+
+    let import = filesystem.import_disk_image(image).unwrap();
+    assert!(import.history_changed);
+    assert!(import.settings_changed);
+    assert_eq!(import.files_changed, 1);
+    assert_eq!(import.files_deleted, 1);
+    assert!(import.failed.is_empty());
+
+    assert_eq!(filesystem.calculations.read_calculations().unwrap(), vec![Calculation::blank()]);
+    assert_eq!(filesystem.settings.values, original_settings);
+    let names = filesystem.files.list_files().into_iter().map(|(_, name)| name).collect::<Vec<_>>();
+    assert_eq!(names, ["a.txt"]);
+
+    // Importing the same image again changes nothing
+    let image = filesystem.export_disk_image().unwrap();
+    assert_eq!(format!("{}", filesystem.import_disk_image(image).unwrap()), "No changes were made.");
+
+    // Names which would be the same on the disk, which ignores case and replaces some characters,
+    // are still exported as separate files
+    for (name, contents) in [("A.txt", "upper"), ("b?.txt", "question"), ("b_.txt", "underscore")] {
+        filesystem.files.save_file(&File { name: name.into(), contents: contents.into() }).unwrap();
+    }
+    let files = filesystem.files.list_files().into_iter()
+        .map(|(idx, _)| filesystem.files.read_file(idx).unwrap())
+        .collect::<Vec<_>>();
+    let image = filesystem.export_disk_image().unwrap();
+    for file in files.iter() {
+        filesystem.files.save_file(&File { name: file.name.clone(), contents: "changed".into() }).unwrap();
+    }
+
+    let import = filesystem.import_disk_image(image).unwrap();
+    assert_eq!(import.files_changed, 4);
+    assert_eq!(import.files_deleted, 0);
+    assert!(import.failed.is_empty());
+    for file in files.iter() {
+        let idx = filesystem.files.find_file(&file.name).unwrap();
+        assert_eq!(filesystem.files.read_file(idx).as_ref(), Some(file));
+    }
+
+    // The image grows to fit as many files as can be stored, alongside a long history, even
+    // though each small file takes a whole block on the disk
+    filesystem.files.table.clear(false);
+    for i in 0..MAX_FILES {
+        filesystem.files.save_file(&File { name: format!("file{}.txt", i), contents: "x".into() }).unwrap();
+    }
+    let root = tests::linear("123456789+123456789");
+    let calculation = Calculation { result: filesystem.evaluate(&root), root, solve: None };
+    let history = vec![calculation.clone(); 8192 / calculation.serialize().len() + 1];
+    filesystem.calculations.replace_calculations(&history).unwrap();
+    let image = filesystem.export_disk_image().unwrap();
+    assert_eq!(format!("{}", filesystem.import_disk_image(image).unwrap()), "No changes were made.");
+    assert_eq!(filesystem.files.list_files().len(), MAX_FILES as usize);
+
+    filesystem.files.table.clear(false);
+    filesystem.calculations.table.clear(false);
+}
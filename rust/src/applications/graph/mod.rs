@@ -3,7 +3,7 @@ use num_traits::FromPrimitive;
 use rbop::{Number, StructuredNode, node::{unstructured::{Upgradable, UnstructuredNodeRoot}, structured::EvaluationSettings, compiled::CompiledNode}, error::MathsError, render::{Viewport, Area}};
 use rust_decimal::{prelude::{One, ToPrimitive, Zero}, Decimal};
 
-use crate::{interface::{Colour, ApplicationFramework, ButtonInput, DISPLAY_WIDTH, DISPLAY_HEIGHT}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, ContextMenu, ContextMenuItem, SelectorMenuCallable}, rbop_impl::RbopSpriteRenderer};
+use crate::{interface::{Colour, ApplicationFramework, ButtonInput, DISPLAY_WIDTH, DISPLAY_HEIGHT}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, ContextMenu, ContextMenuItem, SelectorMenuCallable}, rbop_impl::RbopSpriteRenderer, maths::{Regression, RegressionModel}};
 use super::{Application, ApplicationInfo};
 
 mod test;
@@ -116,14 +116,35 @@ impl UserViewWindow {
     }
 }
 
+/// How the Y values of a plot are calculated from X values.
+enum PlotFunction {
+    /// The compiled node tree, as upgraded and compiled from the plot's unstructured node tree.
+    Compiled(CompiledNode),
+
+    /// A curve fitted by the Statistics application. This is evaluated directly rather than
+    /// through rbop, since rbop has no logarithm function, and so can't express every model.
+    Regression(Regression),
+}
+
+impl PlotFunction {
+    fn evaluate_raw(&self, x: Number) -> Result<Number, MathsError> {
+        match self {
+            PlotFunction::Compiled(compiled) => compiled.evaluate_raw(x),
+            PlotFunction::Regression(regression) => regression.evaluate(x.to_decimal())
+                .map(Number::from)
+                .map_err(|_| MathsError::Overflow),
+        }
+    }
+}
+
 /// A plot on the graph space, derived from an equation entered as an rbop node tree.
 struct Plot {
     /// The unstructured node tree, as entered by the user to construct the graph.
     unstructured: UnstructuredNodeRoot,
 
-    /// The compiled node tree, as upgraded and compiled from the unstructured node tree. If the
-    /// `unstructured` field is modified, this should be modified too to match.
-    compiled: CompiledNode,
+    /// How to calculate the plot. If the `unstructured` field is modified, this should be modified
+    /// too to match.
+    function: PlotFunction,
 
     /// A calculated list of points on this graph. Each index is an X value on the *screen* (not the
     /// graph space), and the value is the corresponding Y value on both the graph space and the 
@@ -133,15 +154,46 @@ struct Plot {
 
 impl Plot {
     /// Recalculates all of the `y_values` given a viewport and settings to evaluate with.
-    fn recalculate_values(&mut self, view: &CalculatedViewWindow) {
-        self.y_values = view.x_coords_on_screen()
-            .iter().map(|i| Self::calculate_one_value(*i, &self.compiled, view)).collect::<Vec<_>>();
+    ///
+    /// The OS' busy indicator is shown if this takes a while, and AC cancels it, in which case the
+    /// rest of the values are left blank. Returns false if cancelled.
+    fn recalculate_values<F: ApplicationFramework>(&mut self, view: &CalculatedViewWindow, os: OperatingSystemPointer<F>) -> bool {
+        let os = os.get_mut_from_immut();
+        let x_values = view.x_coords_on_screen();
+
+        os.begin_busy("Plotting");
+        let mut cancelled = false;
+        self.y_values = Vec::with_capacity(x_values.len());
+        for (i, x) in x_values.iter().enumerate() {
+            cancelled = cancelled || os.busy_tick(Some((i, x_values.len())));
+            self.y_values.push(if cancelled {
+                Err(MathsError::Overflow)
+            } else {
+                Self::calculate_one_value(*x, &self.function, view)
+            });
+        }
+        os.end_busy();
+
+        !cancelled
     }
 
-    /// Calculates one value for `y_values`, given an X value on the graph space, a node tree to
-    /// evaluate, and settings to evaluate with.
-    fn calculate_one_value(x: Number, node: &CompiledNode, view: &CalculatedViewWindow) -> Result<(Number, i16), MathsError> {
-        let real_value = node.evaluate_raw(x)?;
+    /// Recalculates the `y_values` of several plots with `recalculate_values`. If one is cancelled,
+    /// the plots after it are left blank rather than calculated.
+    fn recalculate_all_values<F: ApplicationFramework>(plots: &mut [Plot], view: &CalculatedViewWindow, os: OperatingSystemPointer<F>) {
+        let mut cancelled = false;
+        for plot in plots {
+            if cancelled {
+                plot.y_values = view.x_coords_on_screen().iter().map(|_| Err(MathsError::Overflow)).collect();
+            } else {
+                cancelled = !plot.recalculate_values(view, os);
+            }
+        }
+    }
+
+    /// Calculates one value for `y_values`, given an X value on the graph space, a function to
+    /// evaluate, and the view window to place it within.
+    fn calculate_one_value(x: Number, function: &PlotFunction, view: &CalculatedViewWindow) -> Result<(Number, i16), MathsError> {
+        let real_value = function.evaluate_raw(x)?;
         let screen_value = view.y_to_screen(real_value).ok_or(MathsError::Overflow)?;
 
         Ok((real_value, screen_value))
@@ -170,7 +222,7 @@ impl Plot {
 
             // Insert new values
             for i in (self.y_values.len() - pan)..self.y_values.len() {
-                self.y_values[i] = Self::calculate_one_value(x_values[i], &self.compiled, view);
+                self.y_values[i] = Self::calculate_one_value(x_values[i], &self.function, view);
             }
         } else if pan < 0 {
             // Moving left - copy values up
@@ -181,7 +233,7 @@ impl Plot {
             
             // Insert new values
             for i in 0..pan {
-                self.y_values[i] = Self::calculate_one_value(x_values[i], &self.compiled, view);
+                self.y_values[i] = Self::calculate_one_value(x_values[i], &self.function, view);
             }
         }
     }
@@ -240,7 +292,7 @@ impl TraceState {
     /// Checks if the tracing cursor is close to the boundary of the screen, and if so, adjusts
     /// the user and calculated view windows (and recalculates plot points accordingly) to pan
     /// the screen.
-    fn pan_for_current_x(&self, user_view: &mut UserViewWindow, calc_view: &mut CalculatedViewWindow, plots: &mut [Plot]) {
+    fn pan_for_current_x<F: ApplicationFramework>(&self, user_view: &mut UserViewWindow, calc_view: &mut CalculatedViewWindow, plots: &mut [Plot], os: OperatingSystemPointer<F>) {
         // TODO: some pans will need to adjust Y too!
 
         let inc = Self::x_increment(user_view);
@@ -260,10 +312,9 @@ impl TraceState {
 
         if need_recalc {
             *calc_view = user_view.to_calculated();
-            for plot in plots {
-                // TODO: can we partially recalculate like with freeform pans?
-                plot.recalculate_values(calc_view);
-            }
+
+            // TODO: can we partially recalculate like with freeform pans?
+            Plot::recalculate_all_values(plots, calc_view, os);
         }
     }
 }
@@ -291,13 +342,26 @@ impl<F: ApplicationFramework> Application for GraphApplication<F> {
     fn new(os: OperatingSystemPointer<F>) -> Self {
         let user_view_window = UserViewWindow::new();
 
-        Self {
+        let mut app = Self {
             os,
             plots: Vec::new(),
             user_view_window,
             calculated_view_window: user_view_window.to_calculated(),
             movement_mode: MovementMode::Freeform,
+        };
+
+        // Add any plots which other applications have sent us
+        for regression in core::mem::take(&mut app.os_mut().pending_plots) {
+            let mut plot = Plot {
+                unstructured: regression.to_unstructured(),
+                function: PlotFunction::Regression(regression),
+                y_values: Vec::new()
+            };
+            plot.recalculate_values(&app.calculated_view_window, app.os);
+            app.plots.push(plot);
         }
+
+        app
     }
 
     fn tick(&mut self) {
@@ -355,6 +419,7 @@ impl<F: ApplicationFramework> Application for GraphApplication<F> {
                             &mut self.user_view_window,
                             &mut self.calculated_view_window,
                             &mut self.plots[..],
+                            self.os,
                         );
                     } else {
                         unreachable!()
@@ -367,6 +432,7 @@ impl<F: ApplicationFramework> Application for GraphApplication<F> {
                             &mut self.user_view_window,
                             &mut self.calculated_view_window,
                             &mut self.plots[..],
+                            self.os,
                         );
                     } else {
                         unreachable!()
@@ -449,7 +515,7 @@ impl<F: ApplicationFramework> GraphApplication<F> {
         if let MovementMode::Trace(state) = self.movement_mode {
             // Work out current Y
             // TODO: don't hardcode to first plot
-            let current_y = self.plots[state.plot_index].compiled.evaluate_raw(state.current_x);
+            let current_y = self.plots[state.plot_index].function.evaluate_raw(state.current_x);
 
             // Print current coordinates
             self.os_mut().display_sprite.print_at(
@@ -507,10 +573,10 @@ impl<F: ApplicationFramework> GraphApplication<F> {
                     let compiled = CompiledNode::from_structured(structured, Some('x'), &this.settings());
                     let mut plot = Plot {
                         unstructured,
-                        compiled,
+                        function: PlotFunction::Compiled(compiled),
                         y_values: Vec::new()
                     };
-                    plot.recalculate_values(&this.calculated_view_window);
+                    plot.recalculate_values(&this.calculated_view_window, this.os);
 
                     // Create and push plot
                     this.plots.push(plot);
@@ -555,31 +621,40 @@ impl<F: ApplicationFramework> GraphApplication<F> {
     fn plot_edit_menu(&mut self, plot_index: usize) {
         self.draw();
 
+        // The expression for a logarithmic fit is only for display, so it can't be edited
+        let editable = !matches!(
+            &self.plots[plot_index].function,
+            PlotFunction::Regression(regression) if regression.model == RegressionModel::Logarithmic
+        );
+
+        let mut menu_items = vec![];
+        if editable {
+            menu_items.push(ContextMenuItem::new_common("Edit", move |this: &mut Self| {
+                if let Some((structured, unstructured)) = this.input_expression_until_upgrade(
+                    Some(this.plots[plot_index].unstructured.clone())
+                )
+                {
+                    let settings = this.settings();
+                    let plot = &mut this.plots[plot_index];
+                    let compiled = CompiledNode::from_structured(structured, Some('x'), &settings);
+                    plot.unstructured = unstructured;
+                    plot.function = PlotFunction::Compiled(compiled);
+                    plot.recalculate_values(&this.calculated_view_window, this.os);    
+                }
+            }));
+        }
+        menu_items.push(ContextMenuItem::new_common("Delete", move |this: &mut Self| {
+            // If tracing, stop - easier than logic to adjust traced plot index
+            if let MovementMode::Trace(_) = this.movement_mode {
+                this.movement_mode = MovementMode::Freeform;
+            }
+
+            this.plots.remove(plot_index);
+        }));
+
         ContextMenu::new(
             self.os,
-            vec![
-                ContextMenuItem::new_common("Edit", move |this: &mut Self| {
-                    if let Some((structured, unstructured)) = this.input_expression_until_upgrade(
-                        Some(this.plots[plot_index].unstructured.clone())
-                    )
-                    {
-                        let settings = this.settings();
-                        let plot = &mut this.plots[plot_index];
-                        let compiled = CompiledNode::from_structured(structured, Some('x'), &settings);
-                        plot.unstructured = unstructured;
-                        plot.compiled = compiled;
-                        plot.recalculate_values(&this.calculated_view_window);    
-                    }
-                }),
-                ContextMenuItem::new_common("Delete", move |this: &mut Self| {
-                    // If tracing, stop - easier than logic to adjust traced plot index
-                    if let MovementMode::Trace(_) = this.movement_mode {
-                        this.movement_mode = MovementMode::Freeform;
-                    }
-
-                    this.plots.remove(plot_index);
-                }),
-            ],
+            menu_items,
             true,
         ).tick_until_call(self);
     }
@@ -649,7 +724,7 @@ impl<F: ApplicationFramework> GraphApplication<F> {
     fn input_expression_until_upgrade(&mut self, start: Option<UnstructuredNodeRoot>) -> Option<(StructuredNode, UnstructuredNodeRoot)> {
         loop {
             if let Some(unstructured) = self.os_mut().ui_input_expression("y =", start.clone()) {
-                match unstructured.upgrade() {
+                match self.os().filesystem.user_functions.expand(&unstructured).upgrade() {
                     Ok(s) => return Some((s, unstructured)),
                     Err(e) => {
                         self.os_mut().ui_text_dialog(&e.to_string());
@@ -682,8 +757,6 @@ impl<F: ApplicationFramework> GraphApplication<F> {
         self.calculated_view_window = self.user_view_window.to_calculated();
 
         // Adjust plots
-        for plot in &mut self.plots {
-            plot.recalculate_values(&self.calculated_view_window);
-        }
+        Plot::recalculate_all_values(&mut self.plots, &self.calculated_view_window, self.os);
     }
 }
@@ -1,4 +1,7 @@
-use crate::{interface::{ApplicationFramework, ButtonInput}, tests, operating_system::OSInput};
+use rbop::Number;
+use rust_decimal::Decimal;
+
+use crate::{interface::{ApplicationFramework, ButtonInput}, tests, applications::Application, operating_system::{OSInput, OsAccessor}, maths::{Regression, RegressionModel}};
 
 use super::GraphApplication;
 
@@ -74,4 +77,19 @@ pub fn test<F: ApplicationFramework>(app: &mut GraphApplication<F>) {
         OSInput::Button(ButtonInput::Exe),
     ]);
     assert_eq!(app.plots.len(), 0);
+
+    // Curves fitted by the Statistics application are plotted when it sends them, including
+    // logarithmic ones, which rbop can't express
+    let xs = [1, 2, 3, 4].map(Decimal::from);
+    let regression = Regression::fit(RegressionModel::Logarithmic, &xs, &xs).unwrap();
+    app.os_mut().pending_plots.push(regression.clone());
+    let sent = GraphApplication::new(app.os);
+    assert_eq!(sent.plots.len(), 1);
+    let x_to_screen_1 = sent.calculated_view_window.x_to_screen(1.into()).unwrap() as usize;
+    assert_eq!(
+        sent.plots[0].y_values[x_to_screen_1].as_ref().map(|y| y.0),
+        Ok(Number::from(regression.coefficients[0])),
+    );
+    let x_to_screen_minus_1 = sent.calculated_view_window.x_to_screen((-1).into()).unwrap() as usize;
+    assert!(sent.plots[0].y_values[x_to_screen_minus_1].is_err());
 }
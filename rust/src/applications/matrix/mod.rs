@@ -0,0 +1,390 @@
+use alloc::{format, vec, vec::Vec};
+use num_traits::Zero;
+use rbop::{Number, StructuredNode, node::unstructured::UnstructuredNodeRoot};
+
+use crate::{interface::{Colour, ApplicationFramework, ButtonInput, ShapeFill, DISPLAY_WIDTH}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, ContextMenu, ContextMenuItem, SelectorMenu, SelectorMenuItem, SelectorMenuCallable}, maths::{Matrix, MatrixError, format_number}, rbop_impl::RbopSpriteRenderer};
+use super::{Application, ApplicationInfo};
+
+mod test;
+
+/// The names of the matrix variables, in order.
+const NAMES: [char; 6] = ['A', 'B', 'C', 'D', 'E', 'F'];
+
+/// The largest number of rows or columns a matrix may have.
+const MAX_SIZE: usize = 8;
+
+const CELL_WIDTH: u16 = 56;
+const CELL_HEIGHT: u16 = 30;
+const VISIBLE_ROWS: usize = 6;
+const VISIBLE_COLUMNS: usize = 4;
+const GRID_X: i16 = 8;
+const GRID_Y: i16 = 40;
+
+pub struct MatrixApplication<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+
+    /// The matrix variables, one for each entry of `NAMES`.
+    matrices: Vec<Matrix>,
+
+    /// The index of the selected matrix variable.
+    selected_index: usize,
+}
+
+os_accessor!(MatrixApplication<F>);
+
+/// An action chosen from the menu in the matrix grid.
+enum GridAction {
+    Back,
+    Resize,
+    Store,
+}
+
+impl<F: ApplicationFramework> Application for MatrixApplication<F> {
+    type Framework = F;
+
+    fn info() -> ApplicationInfo {
+        ApplicationInfo {
+            name: "Matrix".into(),
+            visible: true,
+        }
+    }
+
+    fn new(os: OperatingSystemPointer<F>) -> Self {
+        Self {
+            os,
+            matrices: NAMES.iter().map(|_| Matrix::zero(2, 2)).collect(),
+            selected_index: 0,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.draw();
+
+        match self.os_mut().input() {
+            Some(OSInput::Button(ButtonInput::MoveUp)) if self.selected_index > 0 =>
+                self.selected_index -= 1,
+            Some(OSInput::Button(ButtonInput::MoveDown)) if self.selected_index < NAMES.len() - 1 =>
+                self.selected_index += 1,
+            Some(OSInput::Button(ButtonInput::Exe)) => self.edit_matrix(self.selected_index),
+            Some(OSInput::Button(ButtonInput::List)) => self.open_menu(),
+            _ => (),
+        }
+    }
+
+    fn test(&mut self) {
+        test::test(self);
+    }
+}
+
+impl<F: ApplicationFramework> MatrixApplication<F> {
+    /// Draws the list of matrix variables.
+    fn draw(&self) {
+        let os = self.os_mut();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title("Matrix");
+
+        for (i, (name, matrix)) in NAMES.iter().zip(self.matrices.iter()).enumerate() {
+            let y = GRID_Y + i as i16 * 35;
+            if i == self.selected_index {
+                os.display_sprite.draw_rect(5, y, DISPLAY_WIDTH - 10, 32, Colour::BLUE, ShapeFill::Filled, 7);
+            }
+            os.display_sprite.print_at(15, y + 6, &format!("{}", name));
+            os.display_sprite.print_at(60, y + 6, &format!("{} x {}", matrix.rows(), matrix.columns()));
+        }
+
+        os.display_sprite.print_at(5, 290, "[EXE] Edit  [LIST] Options");
+        os.draw();
+    }
+
+    fn open_menu(&mut self) {
+        let name = NAMES[self.selected_index];
+
+        ContextMenu::new(
+            self.os,
+            vec![
+                ContextMenuItem::new_common(format!("Edit {}", name), |this: &mut Self| {
+                    this.edit_matrix(this.selected_index);
+                }),
+                ContextMenuItem::new_common(format!("Resize {}...", name), |this: &mut Self| {
+                    this.resize_matrix(this.selected_index);
+                }),
+                ContextMenuItem::new_common("Arithmetic...", |this: &mut Self| {
+                    this.draw();
+                    this.arithmetic_menu();
+                }),
+                ContextMenuItem::new_common("Functions...", |this: &mut Self| {
+                    this.draw();
+                    this.functions_menu();
+                }),
+                ContextMenuItem::new_common(format!("Clear {}", name), |this: &mut Self| {
+                    let matrix = &mut this.matrices[this.selected_index];
+                    *matrix = Matrix::zero(matrix.rows(), matrix.columns());
+                }),
+            ],
+            true,
+        ).tick_until_call(self);
+    }
+
+    /// Shows operations which take two matrices. The selected matrix is the left-hand operand.
+    fn arithmetic_menu(&mut self) {
+        let name = NAMES[self.selected_index];
+
+        macro_rules! binary {
+            ($label:literal, $operation:expr) => {
+                ContextMenuItem::new_common(format!($label, name), |this: &mut Self| {
+                    this.draw();
+                    if let Some(other) = this.select_matrix() {
+                        let operation = $operation;
+                        let result = operation(&this.matrices[this.selected_index], &this.matrices[other]);
+                        this.show_result(result);
+                    }
+                })
+            };
+        }
+
+        ContextMenu::new(
+            self.os,
+            vec![
+                binary!("{} + ...", |a: &Matrix, b: &Matrix| a.add(b).map(MatrixResult::Matrix)),
+                binary!("{} - ...", |a: &Matrix, b: &Matrix| a.subtract(b).map(MatrixResult::Matrix)),
+                binary!("{} * ...", |a: &Matrix, b: &Matrix| a.multiply(b).map(MatrixResult::Matrix)),
+                binary!("Dot product {} . ...", |a: &Matrix, b: &Matrix| a.dot(b).map(MatrixResult::Scalar)),
+                binary!("Cross product {} x ...", |a: &Matrix, b: &Matrix| a.cross(b).map(MatrixResult::Matrix)),
+                ContextMenuItem::new_common(format!("Scale {}...", name), |this: &mut Self| {
+                    if let Some((factor, _)) = this.os_mut().ui_input_expression_and_evaluate("Scale factor", None, || ()) {
+                        let result = this.matrices[this.selected_index].scale(factor);
+                        this.show_result(Ok(MatrixResult::Matrix(result)));
+                    }
+                }),
+            ],
+            true,
+        ).tick_until_call(self);
+    }
+
+    /// Shows operations which take only the selected matrix.
+    fn functions_menu(&mut self) {
+        macro_rules! unary {
+            ($label:expr, $operation:expr) => {
+                ContextMenuItem::new_common($label, |this: &mut Self| {
+                    let operation = $operation;
+                    let result = operation(&this.matrices[this.selected_index]);
+                    this.show_result(result);
+                })
+            };
+        }
+
+        ContextMenu::new(
+            self.os,
+            vec![
+                unary!("Transpose", |m: &Matrix| Ok(MatrixResult::Matrix(m.transpose()))),
+                unary!("Determinant", |m: &Matrix| m.determinant().map(MatrixResult::Scalar)),
+                unary!("Inverse", |m: &Matrix| m.inverse().map(MatrixResult::Matrix)),
+                unary!("Row reduce (RREF)", |m: &Matrix| Ok(MatrixResult::Matrix(m.rref()))),
+            ],
+            true,
+        ).tick_until_call(self);
+    }
+
+    /// Displays the result of an operation. Matrix results are shown in a grid, from which they
+    /// can be stored into a variable.
+    fn show_result(&mut self, result: Result<MatrixResult, MatrixError>) {
+        match result {
+            Ok(MatrixResult::Matrix(matrix)) => self.view_result_matrix(matrix),
+            Ok(MatrixResult::Scalar(number)) => {
+                self.draw();
+                self.os_mut().ui_text_dialog(&format!("Result: {}", format_number(&number)));
+            }
+            Err(err) => {
+                self.draw();
+                self.os_mut().ui_text_dialog(&format!("{}", err));
+            }
+        }
+    }
+
+    /// Opens a menu to pick a matrix variable, returning its index.
+    fn select_matrix(&mut self) -> Option<usize> {
+        ContextMenu::new(
+            self.os,
+            NAMES.iter().zip(self.matrices.iter()).enumerate().map(|(i, (name, matrix))| {
+                ContextMenuItem::Text {
+                    text: format!("{}  ({} x {})", name, matrix.rows(), matrix.columns()),
+                    metadata: i,
+                }
+            }).collect(),
+            true,
+        ).tick_until_complete().map(|item| item.into_inner())
+    }
+
+    /// Asks the user for new dimensions for a matrix variable.
+    fn resize_matrix(&mut self, index: usize) {
+        let rows = self.input_dimension("Rows", self.matrices[index].rows());
+        let rows = if let Some(rows) = rows { rows } else { return };
+        let columns = self.input_dimension("Columns", self.matrices[index].columns());
+        let columns = if let Some(columns) = columns { columns } else { return };
+
+        self.matrices[index].resize(rows, columns);
+    }
+
+    /// Asks the user for a matrix dimension, repeating the prompt until it's valid.
+    fn input_dimension(&mut self, title: &str, current: usize) -> Option<usize> {
+        let mut root = Some(UnstructuredNodeRoot::from_number(Number::from(current as i64)));
+        loop {
+            let (number, new_root) = self.os_mut().ui_input_expression_and_evaluate(title, root, || ())?;
+            match number.simplify() {
+                Number::Rational(n, 1) if n >= 1 && n <= MAX_SIZE as i64 => return Some(n as usize),
+                _ => {
+                    self.os_mut().ui_text_dialog(&format!("Must be a whole number from 1 to {}", MAX_SIZE));
+                    root = Some(new_root);
+                }
+            }
+        }
+    }
+
+    /// Opens the grid editor for a matrix variable.
+    fn edit_matrix(&mut self, index: usize) {
+        let mut cursor = (0, 0);
+        loop {
+            let title = format!("Edit {}", NAMES[index]);
+            self.draw_grid(&self.matrices[index], &title, cursor);
+
+            let input = if let Some(input) = self.os_mut().input() { input } else { continue };
+            if self.move_cursor(&input, &mut cursor, index) {
+                continue;
+            }
+
+            match input {
+                OSInput::Button(ButtonInput::Exe) => {
+                    let current = self.matrices[index][cursor];
+                    let root = if current.is_zero() { None } else { Some(UnstructuredNodeRoot::from_number(current)) };
+                    let title = format!("{}[{},{}]", NAMES[index], cursor.0 + 1, cursor.1 + 1);
+                    if let Some((value, _)) = self.os_mut().ui_input_expression_and_evaluate(&title, root, || ()) {
+                        self.matrices[index][cursor] = value;
+                    }
+                }
+                OSInput::Button(ButtonInput::Clear) => self.matrices[index][cursor] = Number::zero(),
+                OSInput::Button(ButtonInput::List) => match self.grid_menu(false) {
+                    Some(GridAction::Back) => return,
+                    Some(GridAction::Resize) => {
+                        self.resize_matrix(index);
+                        cursor = (0, 0);
+                    }
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+    }
+
+    /// Shows a matrix which resulted from an operation, allowing it to be stored to a variable.
+    fn view_result_matrix(&mut self, matrix: Matrix) {
+        let mut cursor = (0, 0);
+        loop {
+            self.draw_grid(&matrix, "Result", cursor);
+
+            let input = if let Some(input) = self.os_mut().input() { input } else { continue };
+            if self.move_cursor_within(&input, &mut cursor, &matrix) {
+                continue;
+            }
+
+            match input {
+                OSInput::Button(ButtonInput::List) => match self.grid_menu(true) {
+                    Some(GridAction::Back) => return,
+                    Some(GridAction::Store) => {
+                        if let Some(index) = self.select_matrix() {
+                            self.selected_index = index;
+                            self.matrices[index] = matrix;
+                            return;
+                        }
+                    }
+                    _ => (),
+                },
+                _ => (),
+            }
+        }
+    }
+
+    fn grid_menu(&mut self, is_result: bool) -> Option<GridAction> {
+        let mut items = vec![
+            ContextMenuItem::Text { text: "Back".into(), metadata: GridAction::Back },
+        ];
+        if is_result {
+            items.push(ContextMenuItem::Text { text: "Store to...".into(), metadata: GridAction::Store });
+        } else {
+            items.push(ContextMenuItem::Text { text: "Resize...".into(), metadata: GridAction::Resize });
+        }
+
+        ContextMenu::new(self.os, items, true)
+            .tick_until_complete()
+            .map(|item| item.into_inner())
+    }
+
+    /// Handles arrow keys in a grid of the given matrix variable. Returns true if the input was
+    /// handled.
+    fn move_cursor(&self, input: &OSInput, cursor: &mut (usize, usize), index: usize) -> bool {
+        self.move_cursor_within(input, cursor, &self.matrices[index])
+    }
+
+    fn move_cursor_within(&self, input: &OSInput, cursor: &mut (usize, usize), matrix: &Matrix) -> bool {
+        match input {
+            OSInput::Button(ButtonInput::MoveUp) => cursor.0 = cursor.0.saturating_sub(1),
+            OSInput::Button(ButtonInput::MoveDown) => cursor.0 = (cursor.0 + 1).min(matrix.rows() - 1),
+            OSInput::Button(ButtonInput::MoveLeft) => cursor.1 = cursor.1.saturating_sub(1),
+            OSInput::Button(ButtonInput::MoveRight) => cursor.1 = (cursor.1 + 1).min(matrix.columns() - 1),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Draws a matrix as a grid of cells, scrolled so that the cell at `cursor` is visible. The
+    /// full value of the selected cell is rendered with rbop beneath the grid, since cells may be
+    /// too narrow to show it.
+    fn draw_grid(&self, matrix: &Matrix, title: &str, cursor: (usize, usize)) {
+        let os = self.os_mut();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title(title);
+
+        // Scroll so the cursor is in view
+        let first_row = cursor.0.saturating_sub(VISIBLE_ROWS - 1);
+        let first_column = cursor.1.saturating_sub(VISIBLE_COLUMNS - 1);
+
+        for r in first_row..matrix.rows().min(first_row + VISIBLE_ROWS) {
+            for c in first_column..matrix.columns().min(first_column + VISIBLE_COLUMNS) {
+                let x = GRID_X + ((c - first_column) as u16 * (CELL_WIDTH - 1)) as i16;
+                let y = GRID_Y + ((r - first_row) as u16 * (CELL_HEIGHT - 1)) as i16;
+
+                if (r, c) == cursor {
+                    os.display_sprite.draw_rect(x, y, CELL_WIDTH, CELL_HEIGHT, Colour::BLUE, ShapeFill::Filled, 0);
+                }
+                os.display_sprite.draw_rect(x, y, CELL_WIDTH, CELL_HEIGHT, Colour::GREY, ShapeFill::Hollow, 0);
+
+                let text = os.display_sprite.fit_text(&format_number(&matrix[(r, c)]), CELL_WIDTH - 6);
+                os.display_sprite.print_at(x + 3, y + 4, &text);
+            }
+        }
+
+        // Indicate if there's more of the matrix than we can see
+        if first_row + VISIBLE_ROWS < matrix.rows() || first_column + VISIBLE_COLUMNS < matrix.columns()
+            || first_row > 0 || first_column > 0
+        {
+            os.display_sprite.print_at(5, 222, &format!("{} x {}", matrix.rows(), matrix.columns()));
+        }
+
+        // Render the selected value in full
+        os.display_sprite.print_at(5, 245, &format!("[{},{}] =", cursor.0 + 1, cursor.1 + 1));
+        let value_sprite = RbopSpriteRenderer::draw_to_sprite(
+            &mut StructuredNode::Number(matrix[cursor]),
+            None,
+            None,
+            Colour::BLACK,
+        );
+        os.display_sprite.draw_sprite(70, 245, &value_sprite);
+
+        os.draw();
+    }
+}
+
+/// The result of a matrix operation.
+enum MatrixResult {
+    Matrix(Matrix),
+    Scalar(Number),
+}
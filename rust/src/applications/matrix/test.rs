@@ -0,0 +1,95 @@
+use alloc::vec;
+use rbop::Number;
+
+use crate::{interface::{ApplicationFramework, ButtonInput}, tests, operating_system::OSInput, maths::Matrix};
+
+use super::MatrixApplication;
+
+pub fn test<F: ApplicationFramework>(app: &mut MatrixApplication<F>) {
+    // Enter A = [1 2; 3 4] with the grid editor
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::MoveRight),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(4)),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::Button(ButtonInput::MoveLeft),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Digit(3)),
+        OSInput::Button(ButtonInput::Exe),
+
+        // Back
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.matrices[0], Matrix::from_rows(vec![
+        vec![Number::from(1), Number::from(2)],
+        vec![Number::from(3), Number::from(4)],
+    ]));
+    assert!(matches!(app.matrices[0].determinant(), Ok(Number::Rational(-2, 1))));
+
+    // Invert A, and store the result in B
+    tests::press(app, &[
+        // Functions > Inverse
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+
+        // Store to B
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.matrices[1], Matrix::from_rows(vec![
+        vec![Number::Rational(-2, 1), Number::Rational(1, 1)],
+        vec![Number::Rational(3, 2), Number::Rational(-1, 2)],
+    ]));
+
+    // A * B should be the identity, stored in C
+    app.selected_index = 0;
+    tests::press(app, &[
+        // Arithmetic > A * ... > B
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+
+        // Store to C
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.matrices[2], Matrix::identity(2));
+
+    // Cross product of row vectors
+    let i = Matrix::from_rows(vec![vec![Number::from(1), Number::from(0), Number::from(0)]]);
+    let j = Matrix::from_rows(vec![vec![Number::from(0), Number::from(1), Number::from(0)]]);
+    assert_eq!(i.cross(&j), Ok(Matrix::from_rows(vec![vec![Number::from(0), Number::from(0), Number::from(1)]])));
+    assert!(matches!(i.dot(&j), Ok(Number::Rational(0, 1))));
+}
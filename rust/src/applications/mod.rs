@@ -75,4 +75,11 @@ pub mod bootloader;
 pub mod storage;
 pub mod numbers_game;
 pub mod settings;
-// pub mod files;
+pub mod unit_converter;
+pub mod base_n;
+pub mod matrix;
+pub mod statistics;
+pub mod equation;
+pub mod programs;
+pub mod notes;
+pub mod files;
@@ -0,0 +1,178 @@
+use alloc::{string::String, vec};
+
+use crate::{interface::{Colour, ApplicationFramework, ButtonInput}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, ContextMenu, ContextMenuItem, SelectorMenu, SelectorMenuItem, TextEditor, FileList}, filesystem::File};
+use super::{Application, ApplicationInfo};
+
+mod test;
+
+/// The extension given to note files, which distinguishes them from other files.
+const EXTENSION: &str = ".txt";
+
+/// A note which is open for editing.
+struct OpenNote {
+    name: String,
+    editor: TextEditor,
+}
+
+pub struct NotesApplication<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+
+    /// The stored notes.
+    list: FileList<F>,
+
+    /// The note being edited, if any. While this is set, the editor is shown instead of the list
+    /// of notes.
+    open: Option<OpenNote>,
+}
+
+os_accessor!(NotesApplication<F>);
+
+/// An action chosen from the List menu while the list of notes is shown.
+enum ListAction {
+    New,
+    Rename,
+    Delete,
+}
+
+/// An action chosen from the List menu while editing.
+enum EditorAction {
+    Close,
+    Discard,
+}
+
+impl<F: ApplicationFramework> Application for NotesApplication<F> {
+    type Framework = F;
+
+    fn info() -> ApplicationInfo {
+        ApplicationInfo {
+            name: "Notes".into(),
+            visible: true,
+        }
+    }
+
+    fn new(os: OperatingSystemPointer<F>) -> Self {
+        Self {
+            os,
+            list: FileList::new(os, EXTENSION, "note"),
+            open: None,
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.open.is_some() {
+            self.tick_editor();
+        } else {
+            self.tick_list();
+        }
+    }
+
+    fn test(&mut self) {
+        test::test(self);
+    }
+}
+
+impl<F: ApplicationFramework> NotesApplication<F> {
+    fn tick_list(&mut self) {
+        self.list.draw("Notes", "[EXE] Open  [LIST] Options");
+
+        match self.os_mut().input() {
+            Some(OSInput::Button(ButtonInput::MoveUp)) => self.list.move_up(),
+            Some(OSInput::Button(ButtonInput::MoveDown)) => self.list.move_down(),
+
+            Some(OSInput::Button(ButtonInput::Exe)) if !self.list.files.is_empty() => self.open_selected(),
+
+            Some(OSInput::Button(ButtonInput::List)) => match self.list_menu() {
+                Some(ListAction::New) => self.new_note(),
+                Some(ListAction::Rename) => self.list.rename_selected(),
+                Some(ListAction::Delete) => self.list.delete_selected(),
+                None => (),
+            },
+
+            _ => (),
+        }
+    }
+
+    fn list_menu(&mut self) -> Option<ListAction> {
+        let mut items = vec![ContextMenuItem::Text { text: "New note...".into(), metadata: ListAction::New }];
+        if !self.list.files.is_empty() {
+            items.push(ContextMenuItem::Text { text: "Rename...".into(), metadata: ListAction::Rename });
+            items.push(ContextMenuItem::Text { text: "Delete".into(), metadata: ListAction::Delete });
+        }
+        ContextMenu::new(self.os, items, true).tick_until_complete().map(|item| item.into_inner())
+    }
+
+    /// Asks for a name, then opens an empty note with it.
+    fn new_note(&mut self) {
+        if let Some(name) = self.list.input_name("") {
+            self.open = Some(OpenNote { name, editor: Self::editor("") });
+            self.save();
+        }
+    }
+
+    fn open_selected(&mut self) {
+        let (idx, name) = if let Some(selected) = self.list.selected() { selected } else { return };
+        if let Some(file) = self.os_mut().filesystem.files.read_file(idx) {
+            self.open = Some(OpenNote { name, editor: Self::editor(&file.contents) });
+        }
+    }
+
+    /// Creates an editor for a note's contents. Unlike programs, notes are prose, so long lines
+    /// are wrapped.
+    fn editor(contents: &str) -> TextEditor {
+        let mut editor = TextEditor::new(contents, true);
+        editor.wrap = true;
+        editor
+    }
+
+    fn tick_editor(&mut self) {
+        self.draw_editor();
+
+        let input = if let Some(input) = self.os_mut().input() { input } else { return };
+        if input == OSInput::Button(ButtonInput::List) {
+            match self.editor_menu() {
+                Some(EditorAction::Close) => {
+                    self.save();
+                    self.open = None;
+                    self.list.load();
+                }
+                Some(EditorAction::Discard) => {
+                    self.open = None;
+                    self.list.load();
+                }
+                None => (),
+            }
+        } else {
+            self.open.as_mut().unwrap().editor.input(&input);
+        }
+    }
+
+    fn editor_menu(&mut self) -> Option<EditorAction> {
+        ContextMenu::new(
+            self.os,
+            vec![
+                ContextMenuItem::Text { text: "Save and close".into(), metadata: EditorAction::Close },
+                ContextMenuItem::Text { text: "Discard changes".into(), metadata: EditorAction::Discard },
+            ],
+            true,
+        ).tick_until_complete().map(|item| item.into_inner())
+    }
+
+    /// Writes the open note to storage.
+    fn save(&mut self) {
+        let open = self.open.as_ref().unwrap();
+        let file = File { name: self.list.file_name(&open.name), contents: open.editor.text() };
+        if self.os_mut().filesystem.files.save_file(&file).is_none() {
+            self.os_mut().ui_text_dialog("Could not save note, storage may be full");
+        }
+    }
+
+    fn draw_editor(&mut self) {
+        // Copy the pointer, so that the OS can be borrowed alongside the editor
+        let mut os = self.os;
+        let open = self.open.as_mut().unwrap();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title(&open.name);
+        open.editor.draw(&mut os.display_sprite, 0, OperatingSystem::<F>::TITLE_BAR_HEIGHT as i16, 240, 290);
+        os.draw();
+    }
+}
@@ -0,0 +1,75 @@
+use alloc::string::String;
+
+use crate::{interface::{ApplicationFramework, ButtonInput}, tests, operating_system::{OSInput, OsAccessor}};
+
+use super::NotesApplication;
+
+pub fn test<F: ApplicationFramework>(app: &mut NotesApplication<F>) {
+    // Create a note named "n", write in it, then save and close
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::TextMultiTapNew('n'),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::TextMultiTapNew('h'),
+        OSInput::TextMultiTapNew('i'),
+        OSInput::ShiftedButton(ButtonInput::Digit(0)),
+        OSInput::Button(ButtonInput::Digit(2)),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::TextMultiTapNew('x'),
+
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert!(app.open.is_none());
+    assert_eq!(app.list.files.len(), 1);
+    assert_eq!(app.list.files[0].1, "n");
+    let file = app.os_mut().filesystem.files.read_file(app.list.files[0].0).unwrap();
+    assert_eq!(file.name, "n.txt");
+    assert_eq!(file.contents, "hi 2\nx");
+
+    // Rename it to "m"
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Clear),
+        OSInput::TextMultiTapNew('m'),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.list.files.len(), 1);
+    assert_eq!(app.list.files[0].1, "m");
+    assert!(app.os_mut().filesystem.files.find_file("n.txt").is_none());
+
+    // Long lines are wrapped, so moving up from the end of one stays within the same line
+    let mut long_line = String::new();
+    for _ in 0..4 {
+        long_line.push_str("the quick brown fox jumps over the lazy dog ");
+    }
+    tests::press(app, &[OSInput::Button(ButtonInput::Exe)]);
+    app.open.as_mut().unwrap().editor = NotesApplication::<F>::editor(&long_line);
+    tests::press(app, &[OSInput::Button(ButtonInput::MoveUp)]);
+    let (line, column) = app.open.as_ref().unwrap().editor.cursor;
+    assert_eq!(line, 0);
+    assert!(column > 0 && column < long_line.len());
+
+    // Discarding leaves the saved contents alone
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert!(app.open.is_none());
+    let file = app.os_mut().filesystem.files.read_file(app.list.files[0].0).unwrap();
+    assert_eq!(file.contents, "hi 2\nx");
+
+    // Delete it
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert!(app.list.files.is_empty());
+}
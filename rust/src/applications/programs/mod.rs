@@ -0,0 +1,255 @@
+use alloc::{format, string::String, vec};
+
+use crate::{interface::{Colour, ApplicationFramework, ButtonInput}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, ContextMenu, ContextMenuItem, SelectorMenu, SelectorMenuItem, TextEditor, FileList}, filesystem::File, scripting::{parse, Interpreter, ScriptErrorKind}};
+use super::{Application, ApplicationInfo, calculator::catalog::{Catalog, CatalogItem}};
+
+mod test;
+
+/// The extension given to program files, which distinguishes them from other files.
+const EXTENSION: &str = ".bas";
+
+/// A program which is open for editing.
+struct OpenProgram {
+    name: String,
+    editor: TextEditor,
+}
+
+pub struct ProgramsApplication<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+
+    /// The stored programs.
+    list: FileList<F>,
+
+    /// The program being edited, if any. While this is set, the editor is shown instead of the
+    /// list of programs.
+    open: Option<OpenProgram>,
+}
+
+os_accessor!(ProgramsApplication<F>);
+
+/// An action chosen from the List menu while the list of programs is shown.
+enum ListAction {
+    New,
+    Edit,
+    Rename,
+    Delete,
+}
+
+/// An action chosen from the List menu while editing.
+enum EditorAction {
+    Run,
+    InsertKeyword,
+    Close,
+}
+
+impl<F: ApplicationFramework> Application for ProgramsApplication<F> {
+    type Framework = F;
+
+    fn info() -> ApplicationInfo {
+        ApplicationInfo {
+            name: "Programs".into(),
+            visible: true,
+        }
+    }
+
+    fn new(os: OperatingSystemPointer<F>) -> Self {
+        Self {
+            os,
+            list: FileList::new(os, EXTENSION, "program"),
+            open: None,
+        }
+    }
+
+    fn tick(&mut self) {
+        if self.open.is_some() {
+            self.tick_editor();
+        } else {
+            self.tick_list();
+        }
+    }
+
+    fn test(&mut self) {
+        test::test(self);
+    }
+}
+
+impl<F: ApplicationFramework> ProgramsApplication<F> {
+    fn tick_list(&mut self) {
+        self.list.draw("Programs", "[EXE] Run  [LIST] Options");
+
+        match self.os_mut().input() {
+            Some(OSInput::Button(ButtonInput::MoveUp)) => self.list.move_up(),
+            Some(OSInput::Button(ButtonInput::MoveDown)) => self.list.move_down(),
+
+            Some(OSInput::Button(ButtonInput::Exe)) => {
+                if let Some((idx, _)) = self.list.selected()
+                    && let Some(file) = self.os_mut().filesystem.files.read_file(idx)
+                {
+                    self.run(&file.contents);
+                }
+            }
+
+            Some(OSInput::Button(ButtonInput::List)) => match self.list_menu() {
+                Some(ListAction::New) => self.new_program(),
+                Some(ListAction::Edit) => self.edit_selected(),
+                Some(ListAction::Rename) => self.list.rename_selected(),
+                Some(ListAction::Delete) => self.list.delete_selected(),
+                None => (),
+            },
+
+            _ => (),
+        }
+    }
+
+    fn list_menu(&mut self) -> Option<ListAction> {
+        let mut items = vec![ContextMenuItem::Text { text: "New program...".into(), metadata: ListAction::New }];
+        if !self.list.files.is_empty() {
+            items.push(ContextMenuItem::Text { text: "Edit".into(), metadata: ListAction::Edit });
+            items.push(ContextMenuItem::Text { text: "Rename...".into(), metadata: ListAction::Rename });
+            items.push(ContextMenuItem::Text { text: "Delete".into(), metadata: ListAction::Delete });
+        }
+        ContextMenu::new(self.os, items, true).tick_until_complete().map(|item| item.into_inner())
+    }
+
+    /// Asks for a name, then opens an empty program with it.
+    fn new_program(&mut self) {
+        if let Some(name) = self.list.input_name("") {
+            self.open = Some(OpenProgram { name, editor: TextEditor::new("", true) });
+            self.save();
+        }
+    }
+
+    fn edit_selected(&mut self) {
+        let (idx, name) = if let Some(selected) = self.list.selected() { selected } else { return };
+        if let Some(file) = self.os_mut().filesystem.files.read_file(idx) {
+            self.open = Some(OpenProgram { name, editor: TextEditor::new(&file.contents, true) });
+        }
+    }
+
+    fn tick_editor(&mut self) {
+        self.draw_editor();
+
+        let input = if let Some(input) = self.os_mut().input() { input } else { return };
+        if input == OSInput::Button(ButtonInput::List) {
+            match self.editor_menu() {
+                Some(EditorAction::Run) => {
+                    self.save();
+                    let source = self.open.as_ref().unwrap().editor.text();
+                    self.run(&source);
+                }
+                Some(EditorAction::InsertKeyword) => self.insert_keyword(),
+                Some(EditorAction::Close) => {
+                    self.save();
+                    self.open = None;
+                    self.list.load();
+                }
+                None => (),
+            }
+        } else {
+            self.open.as_mut().unwrap().editor.input(&input);
+        }
+    }
+
+    fn editor_menu(&mut self) -> Option<EditorAction> {
+        ContextMenu::new(
+            self.os,
+            vec![
+                ContextMenuItem::Text { text: "Run".into(), metadata: EditorAction::Run },
+                ContextMenuItem::Text { text: "Insert keyword...".into(), metadata: EditorAction::InsertKeyword },
+                ContextMenuItem::Text { text: "Save and close".into(), metadata: EditorAction::Close },
+            ],
+            true,
+        ).tick_until_complete().map(|item| item.into_inner())
+    }
+
+    /// Offers a catalog of keywords and functions, which saves typing them with multi-tap.
+    fn insert_keyword(&mut self) {
+        let items = [
+            ("print", "Show values in the console"),
+            ("input", "Ask for a number"),
+            ("if", "Run code if a condition holds"),
+            ("then", "Begins the body of an if"),
+            ("else", "Runs if the condition didn't hold"),
+            ("end", "Ends an if or while"),
+            ("while", "Loop while a condition holds"),
+            ("for", "Loop over a range of numbers"),
+            ("to", "Gives the end of a for loop's range"),
+            ("step", "Gives the step of a for loop"),
+            ("next", "Ends a for loop"),
+            ("stop", "End the program"),
+            ("and", "True if both are true"),
+            ("or", "True if either is true"),
+            ("not", "True if false, and vice versa"),
+            ("mod", "Remainder after division"),
+            ("cls", "Clear the screen"),
+            ("colour", "Set drawing colour: r, g, b"),
+            ("pixel", "Draw a pixel: x, y"),
+            ("line", "Draw a line: x1, y1, x2, y2"),
+            ("rect", "Draw a rectangle: x, y, w, h"),
+            ("fill", "Fill a rectangle: x, y, w, h"),
+            ("text", "Draw text: x, y, value"),
+            ("show", "Display what has been drawn"),
+            ("key()", "Wait for a key and get its code"),
+            ("abs(", "Absolute value"),
+            ("int(", "Round down to a whole number"),
+            ("sqrt(", "Square root"),
+        ];
+
+        let catalog = Catalog::new(
+            self.os,
+            "Keywords",
+            items.iter().map(|(name, description)| CatalogItem::new(*name, *description, *name)).collect(),
+        );
+        if let Some(item) = catalog.tick_until_complete() {
+            let keyword = item.metadata;
+            let editor = &mut self.open.as_mut().unwrap().editor;
+
+            // Keywords are followed by a space, but function calls aren't
+            editor.insert(keyword);
+            if keyword.chars().all(|c| c.is_ascii_alphabetic()) {
+                editor.insert(" ");
+            }
+        }
+    }
+
+    /// Writes the open program to storage.
+    fn save(&mut self) {
+        let open = self.open.as_ref().unwrap();
+        let file = File { name: self.list.file_name(&open.name), contents: open.editor.text() };
+        if self.os_mut().filesystem.files.save_file(&file).is_none() {
+            self.os_mut().ui_text_dialog("Could not save program, storage may be full");
+        }
+    }
+
+    /// Parses and runs a program. Once it finishes, its output stays on the screen until EXE is
+    /// pressed. Errors are shown in a dialog.
+    fn run(&mut self, source: &str) {
+        let program = match parse(source) {
+            Ok(program) => program,
+            Err(e) => {
+                self.os_mut().ui_text_dialog(&format!("{}", e));
+                return;
+            }
+        };
+
+        let mut interpreter = Interpreter::new(self.os);
+        match interpreter.run(&program) {
+            Ok(()) => self.os_mut().ui_text_dialog("Program finished"),
+
+            // The user stopped the program, so there's nothing to report
+            Err(e) if e.kind == ScriptErrorKind::Stopped => (),
+
+            Err(e) => self.os_mut().ui_text_dialog(&format!("{}", e)),
+        }
+    }
+
+    fn draw_editor(&mut self) {
+        // Copy the pointer, so that the OS can be borrowed alongside the editor
+        let mut os = self.os;
+        let open = self.open.as_mut().unwrap();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title(&open.name);
+        open.editor.draw(&mut os.display_sprite, 0, OperatingSystem::<F>::TITLE_BAR_HEIGHT as i16, 240, 290);
+        os.draw();
+    }
+}
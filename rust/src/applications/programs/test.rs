@@ -0,0 +1,135 @@
+use alloc::vec;
+use rust_decimal::Decimal;
+
+use crate::{interface::{ApplicationFramework, ButtonInput}, tests, filesystem::File, operating_system::{OSInput, OsAccessor}, scripting::{parse, Interpreter, Value, ScriptError, ScriptErrorKind}};
+
+use super::ProgramsApplication;
+
+pub fn test<F: ApplicationFramework>(app: &mut ProgramsApplication<F>) {
+    // Create a program named "a" which prints 6*7, then run it from the editor
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::TextMultiTapNew('a'),
+        OSInput::Button(ButtonInput::Exe),
+
+        OSInput::TextMultiTapNew('p'),
+        OSInput::TextMultiTapNew('r'),
+        OSInput::TextMultiTapNew('i'),
+        OSInput::TextMultiTapNew('n'),
+        OSInput::TextMultiTapNew('t'),
+        OSInput::ShiftedButton(ButtonInput::Digit(0)),
+        OSInput::Button(ButtonInput::Digit(6)),
+        OSInput::Button(ButtonInput::Multiply),
+        OSInput::Button(ButtonInput::Digit(7)),
+
+        // Run, and dismiss the "finished" dialog
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Exe),
+
+        // Save and close
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert!(app.open.is_none());
+    assert_eq!(app.list.files.len(), 1);
+    assert_eq!(app.list.files[0].1, "a");
+    let file = app.os_mut().filesystem.files.read_file(app.list.files[0].0).unwrap();
+    assert_eq!(file.name, "a.bas");
+    assert_eq!(file.contents, "print 6*7");
+
+    // Rename it to "b"
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::List),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Exe),
+        OSInput::Button(ButtonInput::Clear),
+        OSInput::TextMultiTapNew('b'),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    assert_eq!(app.list.files.len(), 1);
+    assert_eq!(app.list.files[0].1, "b");
+    assert!(app.os_mut().filesystem.files.find_file("a.bas").is_none());
+    assert!(app.os_mut().filesystem.files.find_file("b.bas").is_some());
+
+    // If storage is full, saving or renaming a program fails and leaves the old one intact
+    let files = &mut app.os_mut().filesystem.files;
+    let mut filler = vec![];
+    while let Some(address) = files.table.allocate_chunks(1) {
+        filler.push(address);
+    }
+    let idx = files.find_file("b.bas").unwrap();
+    assert!(files.save_file(&File { name: "b.bas".into(), contents: "print 1".into() }).is_none());
+    assert!(files.rename_file(idx, "c.bas").is_none());
+    assert_eq!(files.read_file(idx), Some(File { name: "b.bas".into(), contents: "print 6*7".into() }));
+    for address in filler {
+        files.table.free_chunks(address, 1);
+    }
+
+    // Loops, conditionals, and input, checked through the interpreter directly
+    let program = parse(
+        "' Sum the numbers up to n, and their squares\n\
+        input \"n\", n\n\
+        total = 0\n\
+        for i = 1 to n\n\
+          total = total + i\n\
+        next i\n\
+        squares = 0\n\
+        i = n\n\
+        while i > 0\n\
+          squares = squares + i^2\n\
+          i = i - 1\n\
+        end\n\
+        if total = 15 and not squares <> 55 then\n\
+          print \"ok\", total, squares\n\
+        else\n\
+          print \"wrong\"\n\
+        end if\n\
+        if n > 100 then print \"too big\"\n\
+        print \"n = \" + n, 7 mod 3, 2^-1, -2^2\n\
+        stop\n\
+        print \"unreachable\"\n"
+    ).unwrap();
+    app.os_mut().queue_virtual_presses(&[
+        OSInput::Button(ButtonInput::Digit(5)),
+        OSInput::Button(ButtonInput::Exe),
+    ]);
+    let mut interpreter = Interpreter::new(app.os);
+    interpreter.run(&program).unwrap();
+    assert_eq!(interpreter.console, vec!["ok 15 55", "n = 5 1 0.5 -4"]);
+    assert_eq!(interpreter.variables.get("total"), Some(&Value::Number(Decimal::from(15))));
+
+    // Errors report their line
+    assert_eq!(
+        parse("for i = 1 to 3\nprint i\n").unwrap_err().kind,
+        ScriptErrorKind::Expected("next"),
+    );
+    assert_eq!(parse("print \"oops\nprint 1"), Err(ScriptError { line: 1, kind: ScriptErrorKind::UnterminatedString }));
+    let mut interpreter = Interpreter::new(app.os);
+    assert_eq!(
+        interpreter.run(&parse("x = 1\n\ny = x / 0").unwrap()),
+        Err(ScriptError { line: 3, kind: ScriptErrorKind::DivisionByZero }),
+    );
+    assert_eq!(
+        interpreter.run(&parse("print \"a\" * 2").unwrap()).unwrap_err().kind,
+        ScriptErrorKind::TypeMismatch,
+    );
+    assert_eq!(
+        interpreter.run(&parse("print z").unwrap()).unwrap_err().kind,
+        ScriptErrorKind::UndefinedVariable("z".into()),
+    );
+
+    // Loops which never end can be stopped with AC or the menu
+    let forever = parse("i = 0\nwhile 1\n  i = i + 1\nend").unwrap();
+    app.os_mut().queue_virtual_presses(&[OSInput::Button(ButtonInput::Clear)]);
+    assert_eq!(interpreter.run(&forever), Err(ScriptError { line: 2, kind: ScriptErrorKind::Stopped }));
+    app.os_mut().queue_virtual_presses(&[OSInput::Button(ButtonInput::Menu)]);
+    assert_eq!(
+        interpreter.run(&parse("for i = 1 to 2\n  i = 1\nnext").unwrap()).unwrap_err().kind,
+        ScriptErrorKind::Stopped,
+    );
+}
@@ -1,7 +1,6 @@
-use alloc::{vec, format};
-use rbop::node::structured::AngleUnit;
+use alloc::{vec, vec::Vec, format};
 
-use crate::{interface::{Colour, ShapeFill, ApplicationFramework, ButtonInput, DisplayInterface}, operating_system::{OSInput, FullPageMenu, FullPageMenuItem, os_accessor, OperatingSystem, OperatingSystemPointer, FullPageMenuItemDecorator}, timer::Timer};
+use crate::{interface::{Colour, ShapeFill, ApplicationFramework, ButtonInput, DisplayInterface}, operating_system::{OSInput, FullPageMenu, FullPageMenuItem, os_accessor, OperatingSystem, OperatingSystemPointer, FullPageMenuItemDecorator}, timer::Timer, filesystem::{SettingDefinition, SettingValue, SETTINGS}};
 use super::{Application, ApplicationInfo};
 
 // TODO: mostly unimplemented
@@ -43,7 +42,11 @@ impl<F: ApplicationFramework> Application for SettingsApplication<F> {
             match btn {
                 OSInput::Button(ButtonInput::MoveUp) => self.menu.move_up(),
                 OSInput::Button(ButtonInput::MoveDown) => self.menu.move_down(),
-                OSInput::Button(ButtonInput::Exe) => self.change_selected_setting(),
+                OSInput::Button(ButtonInput::Exe) => self.change_selected_setting(true),
+                OSInput::Button(ButtonInput::MoveRight) if self.menu.selected_index < Self::visible_settings().count()
+                    => self.change_selected_setting(true),
+                OSInput::Button(ButtonInput::MoveLeft) if self.menu.selected_index < Self::visible_settings().count()
+                    => self.change_selected_setting(false),
                 _ => (),
             }
         }
@@ -51,67 +54,54 @@ impl<F: ApplicationFramework> Application for SettingsApplication<F> {
 }
 
 impl<F: ApplicationFramework> SettingsApplication<F> {
-    fn build_menu(&mut self) {
-        self.menu.items = vec![
-            FullPageMenuItem {
-                title: format!("Angle unit: {}", self.os().filesystem.settings.values.angle_unit),
-                icon: "settings_angle_unit".into(),
-                decorator: FullPageMenuItemDecorator::None,
-            },
-            FullPageMenuItem {
-                title: "Show frame time".into(),
-                icon: "settings_show_frame_time".into(),
-                decorator: FullPageMenuItemDecorator::Toggle(self.os().filesystem.settings.values.show_frame_time),
-            },
-            FullPageMenuItem {
-                title: "Show heap usage".into(),
-                icon: "settings_show_memory_usage".into(),
-                decorator: FullPageMenuItemDecorator::Toggle(self.os().filesystem.settings.values.show_heap_usage),
-            },
-            FullPageMenuItem {
-                title: "Graphics benchmark".into(),
-                icon: "settings_graphics_benchmark".into(),
-                decorator: FullPageMenuItemDecorator::None,
-            },
-        ];
+    /// The settings shown in the menu, in order. The menu has an item for each, followed by the
+    /// graphics benchmark.
+    fn visible_settings() -> impl Iterator<Item = &'static SettingDefinition> {
+        SETTINGS.iter().filter(|setting| setting.visible)
     }
 
-    fn change_selected_setting(&mut self) {
-        let setting_value: &mut bool;
-        let index: usize;
+    fn build_menu(&mut self) {
+        let mut items = Self::visible_settings()
+            .map(|setting| self.setting_menu_item(setting))
+            .collect::<Vec<_>>();
+        items.push(FullPageMenuItem {
+            title: "Graphics benchmark".into(),
+            icon: "settings_graphics_benchmark".into(),
+            decorator: FullPageMenuItemDecorator::None,
+        });
+        self.menu.items = items;
+    }
 
-        match self.menu.selected_index {
-            0 => {
-                let new_setting_value = match self.os().filesystem.settings.values.angle_unit {
-                    AngleUnit::Degree => AngleUnit::Radian,
-                    AngleUnit::Radian => AngleUnit::Degree,
-                };
-                self.os_mut().filesystem.settings.values.angle_unit = new_setting_value;
-                self.os_mut().filesystem.settings.save();
+    fn setting_menu_item(&self, setting: &SettingDefinition) -> FullPageMenuItem {
+        let value = self.os().filesystem.settings.values.get(setting);
+        FullPageMenuItem {
+            title: setting.title(value),
+            icon: setting.icon.into(),
+            decorator: match value {
+                SettingValue::Bool(b) => FullPageMenuItemDecorator::Toggle(b),
+                _ => FullPageMenuItemDecorator::None,
+            },
+        }
+    }
 
-                self.menu.items[0].title = format!("Angle unit: {}", new_setting_value);
-                return;
-            }
-            1 => {
-                setting_value = &mut self.os_mut().filesystem.settings.values.show_frame_time;
-                index = 1;
-            }
-            2 => {
-                setting_value = &mut self.os_mut().filesystem.settings.values.show_heap_usage;
-                index = 2;
-            }
-            3 => {
+    /// Changes the selected setting to its next value, or its previous one if `forwards` is false,
+    /// and saves it. If the graphics benchmark is selected, runs it instead.
+    fn change_selected_setting(&mut self, forwards: bool) {
+        let index = self.menu.selected_index;
+        let setting = match Self::visible_settings().nth(index) {
+            Some(setting) => setting,
+            None => {
                 self.graphics_benchmark();
                 return
             }
-            
-            _ => unreachable!()
-        }
-
-        *setting_value = !*setting_value;
-        self.menu.items[index].decorator = FullPageMenuItemDecorator::Toggle(*setting_value);
+        };
 
+        let values = &mut self.os_mut().filesystem.settings.values;
+        let value = setting.kind.step(values.get(setting), forwards);
+        values.set(setting, value);
         self.os_mut().filesystem.settings.save();
+
+        self.menu.items[index] = self.setting_menu_item(setting);
     }
 
     fn graphics_benchmark(&self) {
@@ -122,8 +112,13 @@ impl<F: ApplicationFramework> SettingsApplication<F> {
         let mut text_timer = Timer::new(self.os, "Text");
         let mut draw_timer = Timer::new(self.os, "Draw");
 
-        // Run a simple drawing test many times
+        // Run a simple drawing test many times, stopping early if AC is pressed. The busy indicator
+        // isn't shown, since drawing it would skew the timings
         for _ in 0..50 {
+            if self.os_mut().cancel_requested() {
+                return;
+            }
+
             // Clear the screen
             fill_timer.start();
             self.os_mut().display_sprite.fill(Colour::BLACK);
@@ -0,0 +1,318 @@
+use alloc::{format, string::String, vec, vec::Vec};
+use rbop::{Number, node::unstructured::UnstructuredNodeRoot};
+use rust_decimal::Decimal;
+
+use crate::{interface::{Colour, ApplicationFramework, ButtonInput, ShapeFill}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, ContextMenu, ContextMenuItem, SelectorMenu, SelectorMenuItem}, filesystem::ChunkIndex, maths::{OneVariableSummary, TwoVariableSummary, Regression, RegressionModel, format_number}};
+use super::{Application, ApplicationInfo};
+
+mod test;
+
+/// The number of data lists, which are named L1, L2, and so on.
+const LIST_COUNT: usize = 4;
+
+const LABEL_WIDTH: u16 = 32;
+const CELL_WIDTH: u16 = 52;
+const CELL_HEIGHT: u16 = 26;
+const VISIBLE_ROWS: usize = 8;
+const GRID_X: i16 = 2;
+const GRID_Y: i16 = 36;
+
+pub struct StatisticsApplication<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+
+    /// The data lists, as loaded from storage. Changes are written back immediately.
+    lists: Vec<Vec<Number>>,
+
+    /// The selected cell, as (row, list index). The row may be one past the end of the list, which
+    /// is where new values are appended.
+    cursor: (usize, usize),
+}
+
+os_accessor!(StatisticsApplication<F>);
+
+/// An action chosen from the List menu.
+enum StatisticsAction {
+    OneVariable,
+    TwoVariable,
+    Regression,
+    Clear,
+}
+
+impl<F: ApplicationFramework> Application for StatisticsApplication<F> {
+    type Framework = F;
+
+    fn info() -> ApplicationInfo {
+        ApplicationInfo {
+            name: "Statistics".into(),
+            visible: true,
+        }
+    }
+
+    fn new(os: OperatingSystemPointer<F>) -> Self {
+        let mut app = Self {
+            os,
+            lists: vec![],
+            cursor: (0, 0),
+        };
+        app.lists = (0..LIST_COUNT)
+            .map(|i| app.os_mut().filesystem.data_lists.read_list(ChunkIndex(i as u16)))
+            .collect();
+        app
+    }
+
+    fn tick(&mut self) {
+        self.draw();
+
+        let (row, column) = self.cursor;
+        match self.os_mut().input() {
+            Some(OSInput::Button(ButtonInput::MoveUp)) => self.cursor.0 = row.saturating_sub(1),
+            Some(OSInput::Button(ButtonInput::MoveDown)) =>
+                self.cursor.0 = (row + 1).min(self.lists[column].len()),
+            Some(OSInput::Button(ButtonInput::MoveLeft)) if column > 0 => self.move_to_list(column - 1),
+            Some(OSInput::Button(ButtonInput::MoveRight)) if column < LIST_COUNT - 1 => self.move_to_list(column + 1),
+
+            Some(OSInput::Button(ButtonInput::Exe)) => self.edit_cell(),
+            Some(OSInput::Button(ButtonInput::Delete)) if row < self.lists[column].len() => {
+                self.lists[column].remove(row);
+                self.save_list(column);
+            }
+
+            Some(OSInput::Button(ButtonInput::List)) => match self.menu() {
+                Some(StatisticsAction::OneVariable) => self.one_variable(),
+                Some(StatisticsAction::TwoVariable) => self.two_variable(),
+                Some(StatisticsAction::Regression) => {
+                    if let Some(regression) = self.regression() {
+                        // Launching the Graph application drops this one, so return immediately
+                        self.os_mut().pending_plots.push(regression);
+                        return self.os_mut().launch_application_by_name("Graph");
+                    }
+                }
+                Some(StatisticsAction::Clear) => {
+                    self.lists[column].clear();
+                    self.save_list(column);
+                    self.cursor.0 = 0;
+                }
+                None => (),
+            },
+            _ => (),
+        }
+    }
+
+    fn test(&mut self) {
+        test::test(self);
+    }
+}
+
+impl<F: ApplicationFramework> StatisticsApplication<F> {
+    /// Moves the cursor to another list, keeping it within that list's bounds.
+    fn move_to_list(&mut self, column: usize) {
+        self.cursor = (self.cursor.0.min(self.lists[column].len()), column);
+    }
+
+    /// Writes a list back to storage.
+    fn save_list(&mut self, index: usize) {
+        let list = self.lists[index].clone();
+        if self.os_mut().filesystem.data_lists.write_list(ChunkIndex(index as u16), &list).is_none() {
+            self.os_mut().ui_text_dialog("Could not save list, storage may be full");
+        }
+    }
+
+    /// Edits the value under the cursor, or appends a new one if the cursor is past the end of the
+    /// list. The cursor then moves down, so values can be entered in quick succession.
+    fn edit_cell(&mut self) {
+        let (row, column) = self.cursor;
+        let list = &self.lists[column];
+        let root = list.get(row).map(|n| UnstructuredNodeRoot::from_number(*n));
+        let title = format!("L{}({})", column + 1, row + 1);
+
+        if let Some((value, _)) = self.os_mut().ui_input_expression_and_evaluate(&title, root, || ()) {
+            let list = &mut self.lists[column];
+            if row < list.len() {
+                list[row] = value;
+            } else {
+                list.push(value);
+            }
+            self.save_list(column);
+            self.cursor.0 += 1;
+        }
+    }
+
+    fn menu(&mut self) -> Option<StatisticsAction> {
+        ContextMenu::new(
+            self.os,
+            vec![
+                ContextMenuItem::Text { text: "1-var stats".into(), metadata: StatisticsAction::OneVariable },
+                ContextMenuItem::Text { text: "2-var stats".into(), metadata: StatisticsAction::TwoVariable },
+                ContextMenuItem::Text { text: "Regression...".into(), metadata: StatisticsAction::Regression },
+                ContextMenuItem::Text {
+                    text: format!("Clear L{}", self.cursor.1 + 1),
+                    metadata: StatisticsAction::Clear,
+                },
+            ],
+            true,
+        ).tick_until_complete().map(|item| item.into_inner())
+    }
+
+    /// Returns a list converted to decimals, for statistical calculations.
+    fn decimals(&self, index: usize) -> Vec<Decimal> {
+        self.lists[index].iter().map(|n| n.to_decimal()).collect()
+    }
+
+    /// Shows a summary of the list under the cursor.
+    fn one_variable(&mut self) {
+        let column = self.cursor.1;
+        match OneVariableSummary::calculate(&self.decimals(column)) {
+            Ok(summary) => {
+                let rows = Self::format_rows(summary.rows());
+                self.show_results(&format!("1-var stats: L{}", column + 1), rows, false);
+            }
+            Err(e) => self.os_mut().ui_text_dialog(&format!("{}", e)),
+        }
+    }
+
+    /// Asks for a pair of lists, then shows a summary of them.
+    fn two_variable(&mut self) {
+        let (x, y) = if let Some(pair) = self.select_pair() { pair } else { return };
+        match TwoVariableSummary::calculate(&self.decimals(x), &self.decimals(y)) {
+            Ok(summary) => {
+                let rows = Self::format_rows(summary.rows());
+                self.show_results(&format!("2-var stats: L{}, L{}", x + 1, y + 1), rows, false);
+            }
+            Err(e) => self.os_mut().ui_text_dialog(&format!("{}", e)),
+        }
+    }
+
+    /// Asks for a pair of lists and a model, then fits and shows a regression.
+    ///
+    /// If the user chooses to send the fitted curve to the Graph application, returns it. Launching
+    /// Graph is left to the caller, since it drops this application.
+    fn regression(&mut self) -> Option<Regression> {
+        self.draw();
+        let model = ContextMenu::new(
+            self.os,
+            RegressionModel::ALL.iter().map(|model| ContextMenuItem::Text {
+                text: format!("{}  {}", model, model.equation()),
+                metadata: *model,
+            }).collect(),
+            true,
+        ).tick_until_complete()?.into_inner();
+
+        let (x, y) = self.select_pair()?;
+        let regression = match Regression::fit(model, &self.decimals(x), &self.decimals(y)) {
+            Ok(regression) => regression,
+            Err(e) => {
+                self.os_mut().ui_text_dialog(&format!("{}", e));
+                return None;
+            }
+        };
+
+        let mut rows = vec![(String::from("Model"), String::from(model.equation()))];
+        for (name, value) in model.coefficient_names().iter().zip(regression.coefficients.iter()) {
+            rows.push((String::from(*name), format_number(&Number::from(*value))));
+        }
+        rows.extend(Self::format_rows(vec![("r^2", regression.r_squared)]));
+
+        if self.show_results(&format!("{} fit", model), rows, true) {
+            Some(regression)
+        } else {
+            None
+        }
+    }
+
+    /// Asks for the lists to use as X and Y values.
+    fn select_pair(&mut self) -> Option<(usize, usize)> {
+        let x = self.select_list("X")?;
+        let y = self.select_list("Y")?;
+        Some((x, y))
+    }
+
+    fn select_list(&mut self, role: &str) -> Option<usize> {
+        self.draw();
+        ContextMenu::new(
+            self.os,
+            (0..LIST_COUNT).map(|i| ContextMenuItem::Text {
+                text: format!("{} = L{}  ({} values)", role, i + 1, self.lists[i].len()),
+                metadata: i,
+            }).collect(),
+            true,
+        ).tick_until_complete().map(|item| item.into_inner())
+    }
+
+    fn format_rows(rows: Vec<(&'static str, Option<Decimal>)>) -> Vec<(String, String)> {
+        rows.into_iter().map(|(name, value)| (
+            String::from(name),
+            value.map(|v| format_number(&Number::from(v))).unwrap_or_else(|| "undefined".into()),
+        )).collect()
+    }
+
+    /// Shows a page of labelled results until EXE is pressed. If `can_plot` is set, LIST may be
+    /// pressed instead to request that the results are plotted, in which case this returns true.
+    fn show_results(&mut self, title: &str, rows: Vec<(String, String)>, can_plot: bool) -> bool {
+        let os = self.os_mut();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title(title);
+
+        for (i, (name, value)) in rows.iter().enumerate() {
+            let y = GRID_Y + i as i16 * 22;
+            os.display_sprite.print_at(10, y, name);
+            let value = os.display_sprite.fit_text(value, 130);
+            os.display_sprite.print_at(100, y, &value);
+        }
+
+        os.display_sprite.print_at(5, 290, if can_plot { "[EXE] Back  [LIST] Graph" } else { "[EXE] Back" });
+        os.draw();
+
+        loop {
+            match os.input() {
+                Some(OSInput::Button(ButtonInput::Exe)) => return false,
+                Some(OSInput::Button(ButtonInput::List)) if can_plot => return true,
+                _ => (),
+            }
+        }
+    }
+
+    /// Draws the table of data lists, scrolled so that the cursor is visible.
+    fn draw(&self) {
+        let os = self.os_mut();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title("Statistics");
+
+        let (cursor_row, cursor_column) = self.cursor;
+        let first_row = cursor_row.saturating_sub(VISIBLE_ROWS - 1);
+        let cell_x = |column: usize| GRID_X + (LABEL_WIDTH + column as u16 * (CELL_WIDTH - 1)) as i16;
+
+        // Headers
+        for column in 0..LIST_COUNT {
+            os.display_sprite.print_at(cell_x(column) + 12, GRID_Y, &format!("L{}", column + 1));
+        }
+
+        for (i, row) in (first_row..(first_row + VISIBLE_ROWS)).enumerate() {
+            let y = GRID_Y + CELL_HEIGHT as i16 + (i as u16 * (CELL_HEIGHT - 1)) as i16;
+            os.display_sprite.print_at(GRID_X, y + 2, &format!("{}", row + 1));
+
+            for (column, list) in self.lists.iter().enumerate() {
+                let x = cell_x(column);
+                if (row, column) == self.cursor {
+                    os.display_sprite.draw_rect(x, y, CELL_WIDTH, CELL_HEIGHT, Colour::BLUE, ShapeFill::Filled, 0);
+                }
+                os.display_sprite.draw_rect(x, y, CELL_WIDTH, CELL_HEIGHT, Colour::GREY, ShapeFill::Hollow, 0);
+
+                if let Some(value) = list.get(row) {
+                    let text = os.display_sprite.fit_text(&format_number(value), CELL_WIDTH - 6);
+                    os.display_sprite.print_at(x + 3, y + 2, &text);
+                }
+            }
+        }
+
+        // Show the selected value in full
+        let value = self.lists[cursor_column].get(cursor_row).map(format_number).unwrap_or_default();
+        let text = os.display_sprite.fit_text(
+            &format!("L{}({}) = {}", cursor_column + 1, cursor_row + 1, value),
+            230,
+        );
+        os.display_sprite.print_at(5, 265, &text);
+
+        os.display_sprite.print_at(5, 290, "[EXE] Edit  [LIST] Options");
+        os.draw();
+    }
+}
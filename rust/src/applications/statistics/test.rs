@@ -0,0 +1,96 @@
+use alloc::{vec, vec::Vec};
+use rbop::Number;
+use rust_decimal::Decimal;
+
+use crate::{interface::{ApplicationFramework, ButtonInput}, tests, applications::Application, operating_system::{OSInput, OsAccessor}, filesystem::ChunkIndex, maths::{OneVariableSummary, TwoVariableSummary, Regression, RegressionModel, StatisticsError}};
+
+use super::StatisticsApplication;
+
+pub fn test<F: ApplicationFramework>(app: &mut StatisticsApplication<F>) {
+    // Enter L1 = {1, 2, 3, 4}, then L2 = {2, 4, 6, 8}
+    let mut inputs = vec![];
+    for column in 0..2 {
+        for value in 1..=4 {
+            inputs.push(OSInput::Button(ButtonInput::Exe));
+            inputs.push(OSInput::Button(ButtonInput::Digit(value * (column + 1))));
+            inputs.push(OSInput::Button(ButtonInput::Exe));
+        }
+        inputs.push(OSInput::Button(ButtonInput::MoveRight));
+    }
+    tests::press(app, &inputs);
+
+    let l1 = vec![Number::from(1), Number::from(2), Number::from(3), Number::from(4)];
+    let l2 = vec![Number::from(2), Number::from(4), Number::from(6), Number::from(8)];
+    assert_eq!(app.lists[0], l1);
+    assert_eq!(app.lists[1], l2);
+
+    // Lists should have been persisted
+    assert_eq!(app.os_mut().filesystem.data_lists.read_list(ChunkIndex(0)), l1);
+    assert_eq!(app.os_mut().filesystem.data_lists.read_list(ChunkIndex(1)), l2);
+
+    // Delete the 4 from L2, then put it back
+    tests::press(app, &[
+        OSInput::Button(ButtonInput::MoveLeft),
+        OSInput::Button(ButtonInput::MoveDown),
+        OSInput::Button(ButtonInput::Delete),
+    ]);
+    assert_eq!(app.lists[1], vec![Number::from(2), Number::from(6), Number::from(8)]);
+    assert_eq!(app.os_mut().filesystem.data_lists.read_list(ChunkIndex(1)), app.lists[1]);
+    app.lists[1] = l2;
+    app.save_list(1);
+
+    // If storage is full, saving a list fails and leaves the old one intact
+    let data_lists = &mut app.os_mut().filesystem.data_lists;
+    let mut filler = vec![];
+    while let Some(address) = data_lists.table.allocate_chunks(1) {
+        filler.push(address);
+    }
+    assert!(data_lists.write_list(ChunkIndex(0), &[Number::from(5); 40]).is_none());
+    assert_eq!(data_lists.read_list(ChunkIndex(0)), l1);
+    for address in filler {
+        data_lists.table.free_chunks(address, 1);
+    }
+
+    // Reloading gives the same lists
+    let reloaded = StatisticsApplication::new(app.os);
+    assert_eq!(reloaded.lists, app.lists);
+
+    // Summary statistics
+    let xs = app.decimals(0);
+    let ys = app.decimals(1);
+    let summary = OneVariableSummary::calculate(&xs).unwrap();
+    assert_eq!(summary.mean, Decimal::new(25, 1));
+    assert_eq!(summary.sum_of_squares, Decimal::from(30));
+    assert_eq!(summary.q1, Decimal::new(15, 1));
+    assert_eq!(summary.median, Decimal::new(25, 1));
+    assert_eq!(summary.q3, Decimal::new(35, 1));
+
+    let odd = OneVariableSummary::calculate(&[1, 2, 3, 4, 5].map(Decimal::from)).unwrap();
+    assert_eq!(odd.q1, Decimal::new(15, 1));
+    assert_eq!(odd.median, Decimal::from(3));
+    assert_eq!(odd.q3, Decimal::new(45, 1));
+
+    let paired = TwoVariableSummary::calculate(&xs, &ys).unwrap();
+    assert_eq!(paired.sum_xy, Decimal::from(60));
+    assert_eq!(paired.r.map(|r| r.round_dp(10)), Some(Decimal::ONE));
+
+    // Regressions
+    let linear = Regression::fit(RegressionModel::Linear, &xs, &ys).unwrap();
+    assert_eq!(linear.coefficients, vec![Decimal::TWO, Decimal::ZERO]);
+    assert_eq!(linear.r_squared, Some(Decimal::ONE));
+
+    let squares = [1, 4, 9, 16].map(Decimal::from);
+    let quadratic = Regression::fit(RegressionModel::Quadratic, &xs, &squares).unwrap();
+    assert_eq!(
+        quadratic.coefficients.iter().map(|c| c.round_dp(10).normalize()).collect::<Vec<_>>(),
+        vec![Decimal::ONE, Decimal::ZERO, Decimal::ZERO],
+    );
+
+    let power = Regression::fit(RegressionModel::Power, &xs, &ys).unwrap();
+    assert_eq!(power.coefficients[0].round_dp(6), Decimal::TWO);
+    assert_eq!(power.coefficients[1].round_dp(6), Decimal::ONE);
+
+    let negative = [-1, 2, 3, 4].map(Decimal::from);
+    assert_eq!(Regression::fit(RegressionModel::Exponential, &xs, &negative), Err(StatisticsError::NonPositiveData));
+    assert_eq!(Regression::fit(RegressionModel::Linear, &xs, &ys[..2]), Err(StatisticsError::MismatchedLengths));
+}
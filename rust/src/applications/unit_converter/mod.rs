@@ -0,0 +1,180 @@
+use alloc::{format, vec, vec::Vec, string::String};
+use rbop::{Number, node::unstructured::UnstructuredNodeRoot};
+
+use crate::{interface::{Colour, ApplicationFramework, ButtonInput}, operating_system::{OSInput, OperatingSystem, os_accessor, OperatingSystemPointer, ContextMenu, ContextMenuItem, SelectorMenu, SelectorMenuCallable}, maths::{UNIT_CATEGORIES, UnitCategory, Unit, format_decimal}};
+use super::{Application, ApplicationInfo, calculator::catalog::{Catalog, CatalogItem}};
+
+mod test;
+
+pub struct UnitConverterApplication<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+
+    /// Index into `UNIT_CATEGORIES`.
+    category: usize,
+
+    /// Index of the unit being converted from, in the current category.
+    from_unit: usize,
+
+    /// Index of the unit being converted to, in the current category.
+    to_unit: usize,
+
+    /// The value being converted, and the expression which the user entered to produce it.
+    input: Option<(Number, UnstructuredNodeRoot)>,
+}
+
+os_accessor!(UnitConverterApplication<F>);
+
+impl<F: ApplicationFramework> Application for UnitConverterApplication<F> {
+    type Framework = F;
+
+    fn info() -> ApplicationInfo {
+        ApplicationInfo {
+            name: "Unit Converter".into(),
+            visible: true,
+        }
+    }
+
+    fn new(os: OperatingSystemPointer<F>) -> Self {
+        Self {
+            os,
+            category: 0,
+            from_unit: 0,
+            to_unit: 1,
+            input: None,
+        }
+    }
+
+    fn tick(&mut self) {
+        self.draw();
+
+        match self.os_mut().input() {
+            Some(OSInput::Button(ButtonInput::Exe)) => self.input_value(),
+            Some(OSInput::Button(ButtonInput::List)) => self.open_menu(),
+            _ => (),
+        }
+    }
+
+    fn test(&mut self) {
+        test::test(self);
+    }
+}
+
+impl<F: ApplicationFramework> UnitConverterApplication<F> {
+    fn category(&self) -> &'static UnitCategory {
+        &UNIT_CATEGORIES[self.category]
+    }
+
+    fn from_unit(&self) -> &'static Unit {
+        &self.category().units[self.from_unit]
+    }
+
+    fn to_unit(&self) -> &'static Unit {
+        &self.category().units[self.to_unit]
+    }
+
+    /// The number of significant figures which values are shown to.
+    const SIGNIFICANT_FIGURES: u32 = 10;
+
+    /// Converts the input value, returning a string to display as the result.
+    fn result(&self) -> Option<String> {
+        let (value, _) = self.input.as_ref()?;
+        Some(match self.from_unit().convert(value.to_decimal(), self.to_unit()) {
+            Some(result) => format!("{} {}", format_decimal(result, Self::SIGNIFICANT_FIGURES), self.to_unit().symbol),
+            None => "Overflow".into(),
+        })
+    }
+
+    fn draw(&self) {
+        let os = self.os_mut();
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title("Unit Converter");
+
+        os.display_sprite.print_at(5, 40, &format!("Category: {}", self.category().name));
+        os.display_sprite.print_at(5, 70, &format!("From: {} ({})", self.from_unit().name, self.from_unit().symbol));
+        os.display_sprite.print_at(5, 95, &format!("To: {} ({})", self.to_unit().name, self.to_unit().symbol));
+
+        if let Some((value, _)) = &self.input {
+            os.display_sprite.print_at(5, 135, &format!("{} {}", format_decimal(value.to_decimal(), Self::SIGNIFICANT_FIGURES), self.from_unit().symbol));
+            os.display_sprite.print_at(5, 160, "=");
+
+            let result = self.result().unwrap();
+            let (lines, line_height, _) = os.display_sprite.wrap_text(&result, os.display_sprite.width - 10);
+            for (i, line) in lines.iter().enumerate() {
+                os.display_sprite.print_at(5, 185 + line_height * i as i16, line);
+            }
+        } else {
+            os.display_sprite.print_at(5, 135, "[EXE] Enter value");
+        }
+
+        os.display_sprite.print_at(5, 290, "[LIST] Options");
+        os.draw();
+    }
+
+    /// Prompts the user to enter the value to convert.
+    fn input_value(&mut self) {
+        let root = self.input.as_ref().map(|(_, root)| root.clone());
+        if let Some(input) = self.os_mut().ui_input_expression_and_evaluate(
+            &format!("Value ({})", self.from_unit().symbol),
+            root,
+            || self.draw(),
+        ) {
+            self.input = Some(input);
+        }
+    }
+
+    fn open_menu(&mut self) {
+        ContextMenu::new(
+            self.os,
+            vec![
+                ContextMenuItem::new_common("Category...", |this: &mut Self| {
+                    this.draw();
+                    this.category_menu();
+                }),
+                ContextMenuItem::new_common("From unit...", |this: &mut Self| {
+                    this.draw();
+                    if let Some(i) = this.select_unit("From") {
+                        this.from_unit = i;
+                    }
+                }),
+                ContextMenuItem::new_common("To unit...", |this: &mut Self| {
+                    this.draw();
+                    if let Some(i) = this.select_unit("To") {
+                        this.to_unit = i;
+                    }
+                }),
+                ContextMenuItem::new_common("Swap units", |this: &mut Self| {
+                    core::mem::swap(&mut this.from_unit, &mut this.to_unit);
+                }),
+            ],
+            true,
+        ).tick_until_call(self);
+    }
+
+    fn category_menu(&mut self) {
+        ContextMenu::new(
+            self.os,
+            UNIT_CATEGORIES.iter().enumerate().map(|(i, category)|
+                ContextMenuItem::new_common(category.name, move |this: &mut Self| {
+                    if this.category != i {
+                        this.category = i;
+                        this.from_unit = 0;
+                        this.to_unit = 1;
+                    }
+                })
+            ).collect(),
+            true,
+        ).tick_until_call(self);
+    }
+
+    /// Opens a catalog of the units in the current category, and returns the index of the selected
+    /// one.
+    fn select_unit(&mut self, title: &str) -> Option<usize> {
+        let items = self.category().units.iter().enumerate()
+            .map(|(i, unit)| CatalogItem::new(unit.symbol, unit.name, i))
+            .collect::<Vec<_>>();
+
+        Catalog::new(self.os, title, items)
+            .tick_until_complete()
+            .map(|item| item.metadata)
+    }
+}
@@ -0,0 +1,35 @@
+use alloc::string::ToString;
+use rbop::Number;
+
+use crate::{interface::ApplicationFramework, tests, maths::UNIT_CATEGORIES};
+
+use super::UnitConverterApplication;
+
+pub fn test<F: ApplicationFramework>(app: &mut UnitConverterApplication<F>) {
+    // Linear conversions
+    convert(app, "Length", "mi", "km", 3);
+    assert_eq!(app.result().as_deref(), Some("4.828032 km"));
+
+    // Affine conversions, which have an offset
+    convert(app, "Temperature", "C", "F", 100);
+    assert_eq!(app.result().as_deref(), Some("212 F"));
+    convert(app, "Temperature", "F", "K", 32);
+    assert_eq!(app.result().as_deref(), Some("273.15 K"));
+
+    // Tiny results are shown in scientific notation rather than rounded to 0
+    convert(app, "Energy", "eV", "J", 1);
+    assert_eq!(app.result().as_deref(), Some("1.602176634E-19 J"));
+    convert(app, "Length", "nm", "km", 1);
+    assert_eq!(app.result().as_deref(), Some("1E-12 km"));
+
+    app.input = None;
+}
+
+/// Selects a category and units by their names, and enters an integer value to convert.
+fn convert<F: ApplicationFramework>(app: &mut UnitConverterApplication<F>, category: &str, from: &str, to: &str, value: i64) {
+    app.category = UNIT_CATEGORIES.iter().position(|c| c.name == category).unwrap();
+    let units = app.category().units;
+    app.from_unit = units.iter().position(|u| u.symbol == from).unwrap();
+    app.to_unit = units.iter().position(|u| u.symbol == to).unwrap();
+    app.input = Some((Number::from(value), tests::linear(&value.to_string())));
+}
@@ -1,9 +1,9 @@
-use alloc::{vec, vec::Vec};
-use rbop::{Number, UnstructuredNodeList, node::unstructured::UnstructuredNodeRoot, error::{NodeError, MathsError}, serialize::Serializable};
+use alloc::{format, string::String, vec, vec::Vec};
+use rbop::{Number, UnstructuredNodeList, node::unstructured::{UnstructuredNodeRoot, Upgradable}, error::{NodeError, MathsError}, serialize::Serializable};
 
-use crate::{filesystem::chunk_table::ChunkIndex, interface::ApplicationFramework};
+use crate::{filesystem::chunk_table::ChunkIndex, interface::ApplicationFramework, maths::{Complex, ComplexError, FailedSolve, evaluate_complex, format_number, uses_imaginary_unit}};
 
-use super::chunk_table::{ChunkAddress, ChunkTable};
+use super::{Filesystem, chunk_table::{ChunkAddress, ChunkTable}};
 
 pub struct CalculationHistory<F: ApplicationFramework + 'static> {
     pub table: ChunkTable<F>,
@@ -13,6 +13,10 @@ pub struct CalculationHistory<F: ApplicationFramework + 'static> {
 pub struct Calculation {
     pub root: UnstructuredNodeRoot,
     pub result: CalculationResult,
+
+    /// If this calculation records a solve which failed, the inputs to it. The expression is the
+    /// equation which was being solved, and the result is the solver's error as a `MathsError`.
+    pub solve: Option<FailedSolve>,
 }
     
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -21,6 +25,25 @@ pub enum CalculationResult {
     NodeError(NodeError),
     MathsError(MathsError),
     None,
+
+    /// A result with a non-zero imaginary part. Calculations which use `i` but evaluate to a real
+    /// number produce `Ok` instead.
+    Complex(Complex),
+    ComplexError(ComplexError),
+}
+
+impl CalculationResult {
+    /// Formats this result as plain text, or returns `None` if there is no result.
+    pub fn to_text(&self) -> Option<String> {
+        match self {
+            CalculationResult::Ok(number) => Some(format_number(number)),
+            CalculationResult::Complex(number) => Some(format!("{}", number)),
+            CalculationResult::NodeError(err) => Some(format!("{}", err)),
+            CalculationResult::MathsError(err) => Some(format!("{}", err)),
+            CalculationResult::ComplexError(err) => Some(format!("{}", err)),
+            CalculationResult::None => None,
+        }
+    }
 }
 
 impl Calculation {
@@ -28,6 +51,16 @@ impl Calculation {
         Self {
             root: UnstructuredNodeRoot { root: UnstructuredNodeList { items: vec![] } },
             result: CalculationResult::None,
+            solve: None,
+        }
+    }
+
+    /// Formats this calculation's result as plain text, like `CalculationResult::to_text`, except
+    /// that a failed solve is described by the solver's own message.
+    pub fn result_text(&self) -> Option<String> {
+        match &self.solve {
+            Some(solve) => Some(format!("{}", solve)),
+            None => self.result.to_text(),
         }
     }
 }
@@ -35,6 +68,14 @@ impl Calculation {
 impl Serializable for Calculation {
     fn serialize(&self) -> Vec<u8> {
         let mut bytes = self.root.serialize();
+
+        // A failed solve stores its record instead, since the result can be recreated from it
+        if let Some(solve) = &self.solve {
+            bytes.push(6);
+            bytes.append(&mut solve.serialize());
+            return bytes;
+        }
+
         match &self.result {
             CalculationResult::None => bytes.push(0),
             CalculationResult::Ok(result) => {
@@ -49,22 +90,54 @@ impl Serializable for Calculation {
                 bytes.push(3);
                 bytes.append(&mut err.serialize().to_vec());
             },
+            CalculationResult::Complex(result) => {
+                bytes.push(4);
+                bytes.append(&mut result.serialize());
+            },
+            CalculationResult::ComplexError(err) => {
+                bytes.push(5);
+                bytes.append(&mut err.serialize());
+            },
         }
         bytes
     }
 
     fn deserialize(bytes: &mut dyn Iterator<Item = u8>) -> Option<Self> {
         let root = UnstructuredNodeRoot::deserialize(bytes)?;
+        let mut solve = None;
         let result = match bytes.next() {
             Some(0) => CalculationResult::None,
             Some(1) => CalculationResult::Ok(Number::deserialize(bytes)?),
             Some(2) => CalculationResult::NodeError(NodeError::deserialize(bytes)?),
             Some(3) => CalculationResult::MathsError(MathsError::deserialize(bytes)?),
+            Some(4) => CalculationResult::Complex(Complex::deserialize(bytes)?),
+            Some(5) => CalculationResult::ComplexError(ComplexError::deserialize(bytes)?),
+            Some(6) => {
+                let failed = FailedSolve::deserialize(bytes)?;
+                let result = CalculationResult::MathsError(failed.error.to_maths_error());
+                solve = Some(failed);
+                result
+            }
             _ => return None
         };
 
-        Some(Calculation { root, result })
+        Some(Calculation { root, result, solve })
+    }
+}
+
+/// Serializes calculations one after the other, as used when transferring history to a computer.
+pub fn serialize_calculations(calculations: &[Calculation]) -> Vec<u8> {
+    calculations.iter().flat_map(|calc| calc.serialize()).collect()
+}
+
+/// Reads calculations which were serialized by `serialize_calculations`.
+pub fn deserialize_calculations(bytes: &[u8]) -> Option<Vec<Calculation>> {
+    let mut iterator = bytes.iter().copied().peekable();
+    let mut calculations = vec![];
+    while iterator.peek().is_some() {
+        calculations.push(Calculation::deserialize(&mut iterator)?);
     }
+    Some(calculations)
 }
 
 // TODO: all of these operations used priority in old framework
@@ -121,4 +194,40 @@ impl<F: ApplicationFramework> CalculationHistory<F> {
 
         Some(())
     }
+
+    /// Replaces the entire history with the given calculations.
+    pub fn replace_calculations(&mut self, calculations: &[Calculation]) -> Option<()> {
+        self.table.clear(false)?;
+        for (i, calc) in calculations.iter().enumerate() {
+            self.write_calculation_at_index(ChunkIndex(i as u16), calc.clone())?;
+        }
+
+        Some(())
+    }
+}
+
+impl<F: ApplicationFramework> Filesystem<F> {
+    /// Evaluates an expression as the calculator does, after expanding any calls to user-defined
+    /// functions. Expressions which use the imaginary unit `i` are evaluated as complex numbers.
+    pub fn evaluate(&self, root: &UnstructuredNodeRoot) -> CalculationResult {
+        let settings = self.settings.evaluation_settings();
+        let expanded = self.user_functions.expand(root);
+        let structured = match expanded.upgrade() {
+            Ok(structured) => structured,
+            Err(err) => return CalculationResult::NodeError(err),
+        };
+
+        if uses_imaginary_unit(&expanded.root) {
+            match evaluate_complex(&structured, &settings) {
+                Ok(result) if result.rounded().is_real() => CalculationResult::Ok(Number::from(result.rounded().re).simplify()),
+                Ok(result) => CalculationResult::Complex(result),
+                Err(err) => CalculationResult::ComplexError(err),
+            }
+        } else {
+            match structured.evaluate(&settings) {
+                Ok(evaluation_result) => CalculationResult::Ok(evaluation_result.simplify()),
+                Err(err) => CalculationResult::MathsError(err),
+            }
+        }
+    }
 }
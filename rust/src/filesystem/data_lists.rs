@@ -0,0 +1,77 @@
+use alloc::{vec, vec::Vec};
+use rbop::{Number, serialize::Serializable};
+
+use crate::{filesystem::chunk_table::ChunkIndex, interface::ApplicationFramework};
+
+use super::chunk_table::{ChunkAddress, ChunkTable};
+
+/// Persistent lists of numbers, used as data sets by the Statistics application. Each list is
+/// stored at its own index in a chunk table.
+pub struct DataLists<F: ApplicationFramework + 'static> {
+    pub table: ChunkTable<F>,
+}
+
+/// A list of numbers, serialized as a 16-bit big-endian length followed by each number.
+struct DataList(Vec<Number>);
+
+impl Serializable for DataList {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = (self.0.len() as u16).to_be_bytes().to_vec();
+        for number in &self.0 {
+            bytes.append(&mut number.serialize());
+        }
+        bytes
+    }
+
+    fn deserialize(bytes: &mut dyn Iterator<Item = u8>) -> Option<Self> {
+        let length = u16::from_be_bytes([bytes.next()?, bytes.next()?]);
+        let mut result = vec![];
+        for _ in 0..length {
+            result.push(Number::deserialize(bytes)?);
+        }
+        Some(DataList(result))
+    }
+}
+
+impl<F: ApplicationFramework> DataLists<F> {
+    /// Reads the list at the given index. Lists which have never been written are empty.
+    pub fn read_list(&mut self, idx: ChunkIndex) -> Vec<Number> {
+        self.table.chunk_for_index(idx)
+            .and_then(|chunk| DataList::deserialize(&mut self.table.iter_bytes(chunk)))
+            .map(|list| list.0)
+            .unwrap_or_default()
+    }
+
+    fn list_area_at_index(&mut self, idx: ChunkIndex) -> Option<(ChunkAddress, u16)> {
+        let chunk = self.table.chunk_for_index(idx)?;
+        let mut iterator = self.table.iter_bytes(chunk);
+        DataList::deserialize(&mut iterator)?;
+
+        let chunks = (iterator.chunk.0 - chunk.0) + 1;
+
+        Some((chunk, chunks))
+    }
+
+    /// Replaces the list at the given index. Returns `None`, leaving the old list intact, if
+    /// storage is full.
+    pub fn write_list(&mut self, idx: ChunkIndex, list: &[Number]) -> Option<()> {
+        // Write the new list before freeing the old one, so that if storage is full, the old list
+        // is left intact
+        let old_area = self.list_area_at_index(idx);
+
+        let bytes = DataList(list.to_vec()).serialize();
+        let length = self.table.chunks_required_for_bytes(bytes.len());
+        let address = self.table.allocate_chunks(length)?;
+        if self.table.write_bytes(address, bytes).is_none() || self.table.set_chunk_for_index(idx, address).is_none() {
+            self.table.free_chunks(address, length);
+            return None;
+        }
+
+        // The index now points at the new list, so the old heap space can be freed
+        if let Some((address, length)) = old_area {
+            self.table.free_chunks(address, length);
+        }
+
+        Some(())
+    }
+}
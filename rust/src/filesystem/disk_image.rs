@@ -0,0 +1,350 @@
+use core::fmt::Display;
+
+use alloc::{format, string::String, vec, vec::Vec};
+use fatfs::{Dir, FatType, FileSystem, FormatVolumeOptions, FsOptions, IoBase, OemCpConverter, Read, ReadWriteSeek, Seek, SeekFrom, TimeProvider, Write};
+
+use crate::interface::{ApplicationFramework, USB_MASS_STORAGE_BLOCK_SIZE};
+use super::{File, Filesystem, serialize_calculations, deserialize_calculations};
+
+/// The fewest blocks in a disk image. The whole image is held in RAM while it's in use, so images
+/// are sized to fit their contents, but small ones still leave room to add files.
+pub const DISK_IMAGE_MIN_BLOCKS: usize = 96;
+
+/// Blocks left free in a disk image beyond what its contents need, so that files can be edited
+/// and added on the computer.
+pub const DISK_IMAGE_SPARE_BLOCKS: usize = 32;
+
+/// The number of entries in the root directory, which has a fixed size on FAT12.
+const ROOT_DIRECTORY_ENTRIES: usize = 32;
+
+/// The size of an entry in a FAT directory.
+const DIRECTORY_ENTRY_SIZE: usize = 32;
+
+const README_FILE: &str = "readme.txt";
+const HISTORY_FILE: &str = "history.bin";
+const SETTINGS_FILE: &str = "settings.txt";
+const FILES_DIRECTORY: &str = "files";
+
+const README: &str = "\
+This drive contains the data stored on your Delta Pico.
+
+  - history.bin is the calculator history.
+  - settings.txt contains your settings, which can be edited.
+  - The files folder contains your notes and programs. Files can be edited, added, and deleted.
+
+Eject the drive on your computer once you're done, then press EXE on the calculator to copy any
+changes back to it.
+";
+
+/// A disk image held in memory, which `fatfs` can read and write.
+struct RamDisk<'a> {
+    bytes: &'a mut Vec<u8>,
+    position: usize,
+}
+
+impl<'a> RamDisk<'a> {
+    fn new(bytes: &'a mut Vec<u8>) -> Self {
+        Self { bytes, position: 0 }
+    }
+}
+
+impl IoBase for RamDisk<'_> {
+    type Error = ();
+}
+
+impl Read for RamDisk<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        let start = self.position.min(self.bytes.len());
+        let length = buf.len().min(self.bytes.len() - start);
+        buf[..length].copy_from_slice(&self.bytes[start..(start + length)]);
+
+        self.position = start + length;
+        Ok(length)
+    }
+}
+
+impl Write for RamDisk<'_> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        // The image never grows, so writes past the end are truncated
+        let start = self.position.min(self.bytes.len());
+        let length = buf.len().min(self.bytes.len() - start);
+        self.bytes[start..(start + length)].copy_from_slice(&buf[..length]);
+
+        self.position = start + length;
+        Ok(length)
+    }
+
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+impl Seek for RamDisk<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64, Self::Error> {
+        let position = match pos {
+            SeekFrom::Start(x) => x as i64,
+            SeekFrom::End(x) => self.bytes.len() as i64 + x,
+            SeekFrom::Current(x) => self.position as i64 + x,
+        };
+        if position < 0 {
+            return Err(());
+        }
+
+        self.position = position as usize;
+        Ok(position as u64)
+    }
+}
+
+/// A summary of what changed when a disk image was imported.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct DiskImageImport {
+    pub history_changed: bool,
+    pub settings_changed: bool,
+
+    /// The number of files which were created or modified.
+    pub files_changed: usize,
+    pub files_deleted: usize,
+
+    /// The names of any files on the disk which could not be imported, because they were invalid
+    /// or there wasn't enough space to store them.
+    pub failed: Vec<String>,
+}
+
+impl Display for DiskImageImport {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let mut changes: Vec<String> = vec![];
+        if self.history_changed {
+            changes.push("history".into());
+        }
+        if self.settings_changed {
+            changes.push("settings".into());
+        }
+        if self.files_changed > 0 {
+            changes.push(format!("{} file(s)", self.files_changed));
+        }
+        if self.files_deleted > 0 {
+            changes.push(format!("{} deletion(s)", self.files_deleted));
+        }
+
+        if changes.is_empty() {
+            write!(f, "No changes were made.")?;
+        } else {
+            write!(f, "Imported {}.", changes.join(", "))?;
+        }
+
+        if !self.failed.is_empty() {
+            write!(f, " Could not import {}.", self.failed.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<F: ApplicationFramework> Filesystem<F> {
+    /// Builds a FAT12 disk image containing the calculation history, settings, and stored files.
+    /// Returns `None` if storage is inaccessible, or the data is too large for the image.
+    pub fn export_disk_image(&mut self) -> Option<Vec<u8>> {
+        let history = serialize_calculations(&self.calculations.read_calculations()?);
+        let settings = self.settings.values.to_text();
+        let stored_files = self.files.list_files();
+        let names = stored_files.iter().map(|(_, name)| name.clone()).collect::<Vec<_>>();
+        let files = stored_files.iter()
+            .zip(disk_file_names(&names))
+            .filter_map(|((idx, _), disk_name)| Some((disk_name, self.files.read_file(*idx)?)))
+            .collect::<Vec<_>>();
+
+        // Each file takes whole clusters, and the files directory needs an entry for each one, as
+        // well as its `.` and `..` entries
+        let directory_entries = 2 + files.iter().map(|(disk_name, _)| directory_entries(disk_name)).sum::<usize>();
+        let data_clusters =
+            clusters(README.len()) + clusters(history.len()) + clusters(settings.len())
+            + clusters(directory_entries * DIRECTORY_ENTRY_SIZE)
+            + files.iter().map(|(_, file)| clusters(file.contents.len())).sum::<usize>()
+            + DISK_IMAGE_SPARE_BLOCKS;
+        let blocks = disk_image_blocks(data_clusters).max(DISK_IMAGE_MIN_BLOCKS);
+
+        let mut image = vec![0; blocks * USB_MASS_STORAGE_BLOCK_SIZE];
+        fatfs::format_volume(
+            &mut RamDisk::new(&mut image),
+            FormatVolumeOptions::new()
+                .fat_type(FatType::Fat12)
+                .bytes_per_sector(USB_MASS_STORAGE_BLOCK_SIZE as u16)
+                .bytes_per_cluster(USB_MASS_STORAGE_BLOCK_SIZE as u32)
+                .total_sectors(blocks as u32)
+                .max_root_dir_entries(ROOT_DIRECTORY_ENTRIES as u16)
+                .volume_label(*b"DELTA PICO "),
+        ).ok()?;
+
+        {
+            let fs = FileSystem::new(RamDisk::new(&mut image), FsOptions::new()).ok()?;
+            let root = fs.root_dir();
+            write_file(&root, README_FILE, README.as_bytes())?;
+            write_file(&root, HISTORY_FILE, &history)?;
+            write_file(&root, SETTINGS_FILE, settings.as_bytes())?;
+
+            let directory = root.create_dir(FILES_DIRECTORY).ok()?;
+            for (disk_name, file) in files {
+                write_file(&directory, &disk_name, file.contents.as_bytes())?;
+            }
+        }
+
+        Some(image)
+    }
+
+    /// Reads a disk image created by `export_disk_image`, which may since have been modified, and
+    /// copies any changes back into storage. Files which were deleted from the image are deleted
+    /// from storage too. Returns `None` if the image can't be read, or storage is inaccessible.
+    pub fn import_disk_image(&mut self, mut image: Vec<u8>) -> Option<DiskImageImport> {
+        let fs = FileSystem::new(RamDisk::new(&mut image), FsOptions::new()).ok()?;
+        let root = fs.root_dir();
+        let mut import = DiskImageImport::default();
+
+        if let Some(bytes) = read_file(&root, HISTORY_FILE) {
+            match deserialize_calculations(&bytes) {
+                Some(calculations) => if Some(&calculations) != self.calculations.read_calculations().as_ref() {
+                    self.calculations.replace_calculations(&calculations)?;
+                    import.history_changed = true;
+                }
+                None => import.failed.push(HISTORY_FILE.into()),
+            }
+        }
+
+        if let Some(bytes) = read_file(&root, SETTINGS_FILE) {
+            match String::from_utf8(bytes).ok().and_then(|text| self.settings.values.with_text(&text)) {
+                Some(values) => if values != self.settings.values {
+                    self.settings.values = values;
+                    self.settings.save()?;
+                    import.settings_changed = true;
+                }
+                None => import.failed.push(SETTINGS_FILE.into()),
+            }
+        }
+
+        // If the directory is missing entirely, the drive has probably been reformatted, so it
+        // isn't taken as a request to delete everything
+        let directory = if let Ok(directory) = root.open_dir(FILES_DIRECTORY) { directory } else { return Some(import) };
+        let stored_files = self.files.list_files();
+        let stored_names = stored_files.iter().map(|(_, name)| name.clone()).collect::<Vec<_>>();
+        let stored_disk_names = disk_file_names(&stored_names);
+        let mut disk_names = vec![];
+
+        for entry in directory.iter() {
+            let entry = if let Ok(entry) = entry { entry } else { continue };
+            if !entry.is_file() {
+                continue;
+            }
+
+            let disk_name = entry.file_name();
+            disk_names.push(disk_name.clone());
+
+            // Names are compared as they appear on the disk, so that a file keeps its original
+            // name even if it contains characters which the disk doesn't allow
+            let name = stored_names.iter()
+                .zip(stored_disk_names.iter())
+                .find(|(_, stored_disk_name)| **stored_disk_name == disk_name)
+                .map(|(name, _)| name.clone())
+                .unwrap_or_else(|| disk_name.clone());
+
+            // Editors on some computers use CRLF line endings, which the calculator doesn't
+            let contents = read_to_end(&mut entry.to_file())
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .map(|contents| contents.replace("\r\n", "\n"));
+            let contents = if let Some(contents) = contents { contents } else {
+                import.failed.push(disk_name);
+                continue;
+            };
+
+            let existing = self.files.find_file(&name).and_then(|idx| self.files.read_file(idx));
+            if existing.map(|file| file.contents != contents).unwrap_or(true) {
+                if self.files.save_file(&File { name, contents }).is_some() {
+                    import.files_changed += 1;
+                } else {
+                    import.failed.push(disk_name);
+                }
+            }
+        }
+
+        for ((idx, _), stored_disk_name) in stored_files.into_iter().zip(stored_disk_names) {
+            if !disk_names.contains(&stored_disk_name) {
+                self.files.delete_file(idx)?;
+                import.files_deleted += 1;
+            }
+        }
+
+        Some(import)
+    }
+}
+
+/// The number of clusters needed to hold some bytes. Clusters are one block each.
+fn clusters(bytes: usize) -> usize {
+    (bytes + USB_MASS_STORAGE_BLOCK_SIZE - 1) / USB_MASS_STORAGE_BLOCK_SIZE
+}
+
+/// The number of directory entries used by a file with the given name: one for its short name,
+/// and one for each 13 characters of its long name. Any name could need a long name, since short
+/// names are only upper-case.
+fn directory_entries(name: &str) -> usize {
+    1 + (name.encode_utf16().count() + 12) / 13
+}
+
+/// The total number of blocks in a FAT12 image with the given number of data clusters, including
+/// the boot sector, both copies of the FAT, and the root directory.
+fn disk_image_blocks(data_clusters: usize) -> usize {
+    // FAT12 uses one and a half bytes per cluster, and the first two entries are reserved
+    let fat_blocks = clusters((data_clusters + 2) * 3 / 2 + 1);
+    let root_directory_blocks = clusters(ROOT_DIRECTORY_ENTRIES * DIRECTORY_ENTRY_SIZE);
+    1 + 2 * fat_blocks + root_directory_blocks + data_clusters
+}
+
+/// Converts a file name into one which can be used on a FAT filesystem, by replacing any
+/// characters which aren't allowed.
+fn disk_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if "\\/:*?\"<>|".contains(c) || c.is_control() { '_' } else { c })
+        .collect()
+}
+
+/// Chooses a name on the disk for each of the given file names, in the same order. FAT names are
+/// case-insensitive and some characters are replaced by `disk_file_name`, so names which would
+/// collide on the disk are made unique by adding `~1`, `~2` and so on before the extension.
+fn disk_file_names(names: &[String]) -> Vec<String> {
+    let mut disk_names: Vec<String> = vec![];
+    for name in names {
+        let base = disk_file_name(name);
+        let (stem, extension) = match base.rfind('.') {
+            Some(i) if i > 0 => base.split_at(i),
+            _ => (base.as_str(), ""),
+        };
+
+        let mut disk_name = base.clone();
+        let mut suffix = 1;
+        while disk_names.iter().any(|taken| taken.to_lowercase() == disk_name.to_lowercase()) {
+            disk_name = format!("{}~{}{}", stem, suffix, extension);
+            suffix += 1;
+        }
+        disk_names.push(disk_name);
+    }
+    disk_names
+}
+
+fn write_file<IO: ReadWriteSeek, TP: TimeProvider, OCC: OemCpConverter>(dir: &Dir<IO, TP, OCC>, name: &str, bytes: &[u8]) -> Option<()> {
+    let mut file = dir.create_file(name).ok()?;
+    file.truncate().ok()?;
+    file.write_all(bytes).ok()
+}
+
+fn read_file<IO: ReadWriteSeek, TP: TimeProvider, OCC: OemCpConverter>(dir: &Dir<IO, TP, OCC>, name: &str) -> Option<Vec<u8>> {
+    read_to_end(&mut dir.open_file(name).ok()?)
+}
+
+fn read_to_end<R: Read>(reader: &mut R) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut buffer = [0; USB_MASS_STORAGE_BLOCK_SIZE];
+    loop {
+        let length = reader.read(&mut buffer).ok()?;
+        if length == 0 {
+            return Some(bytes);
+        }
+        bytes.extend_from_slice(&buffer[..length]);
+    }
+}
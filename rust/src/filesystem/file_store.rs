@@ -0,0 +1,147 @@
+use alloc::{string::String, vec::Vec};
+use rbop::serialize::Serializable;
+
+use crate::{filesystem::chunk_table::ChunkIndex, interface::ApplicationFramework};
+
+use super::chunk_table::{ChunkAddress, ChunkTable};
+
+/// The maximum number of files which can be stored. Each file occupies the chunk index matching
+/// its slot, so this bounds how many indices are scanned when listing files.
+pub const MAX_FILES: u16 = 64;
+
+/// A named text file, such as a program or a note.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct File {
+    pub name: String,
+    pub contents: String,
+}
+
+/// Serializes a string as a 16-bit big-endian byte length followed by its UTF-8 bytes.
+pub(crate) fn serialize_string(string: &str, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&(string.len() as u16).to_be_bytes());
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+pub(crate) fn deserialize_string(bytes: &mut dyn Iterator<Item = u8>) -> Option<String> {
+    let length = u16::from_be_bytes([bytes.next()?, bytes.next()?]);
+    let mut result = Vec::with_capacity(length as usize);
+    for _ in 0..length {
+        result.push(bytes.next()?);
+    }
+    String::from_utf8(result).ok()
+}
+
+impl Serializable for File {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        serialize_string(&self.name, &mut bytes);
+        serialize_string(&self.contents, &mut bytes);
+        bytes
+    }
+
+    fn deserialize(bytes: &mut dyn Iterator<Item = u8>) -> Option<Self> {
+        Some(File {
+            name: deserialize_string(bytes)?,
+            contents: deserialize_string(bytes)?,
+        })
+    }
+}
+
+/// Persistent named text files, stored one per index in a chunk table. Applications tell their
+/// own files apart by extension, like `.bas` for programs.
+pub struct FileStore<F: ApplicationFramework + 'static> {
+    pub table: ChunkTable<F>,
+}
+
+impl<F: ApplicationFramework> FileStore<F> {
+    /// Reads the file at the given index, if there is one.
+    pub fn read_file(&mut self, idx: ChunkIndex) -> Option<File> {
+        let chunk = self.table.chunk_for_index(idx)?;
+        File::deserialize(&mut self.table.iter_bytes(chunk))
+    }
+
+    /// Reads just the name of the file at the given index, without reading its contents.
+    fn read_name(&mut self, idx: ChunkIndex) -> Option<String> {
+        let chunk = self.table.chunk_for_index(idx)?;
+        deserialize_string(&mut self.table.iter_bytes(chunk))
+    }
+
+    /// Returns the index and name of every stored file, sorted by name.
+    pub fn list_files(&mut self) -> Vec<(ChunkIndex, String)> {
+        let mut files = (0..MAX_FILES)
+            .filter_map(|i| self.read_name(ChunkIndex(i)).map(|name| (ChunkIndex(i), name)))
+            .collect::<Vec<_>>();
+        files.sort_by(|(_, a), (_, b)| a.cmp(b));
+        files
+    }
+
+    /// Finds the index of the file with the given name.
+    pub fn find_file(&mut self, name: &str) -> Option<ChunkIndex> {
+        (0..MAX_FILES).map(ChunkIndex).find(|idx| self.read_name(*idx).as_deref() == Some(name))
+    }
+
+    fn file_area_at_index(&mut self, idx: ChunkIndex) -> Option<(ChunkAddress, u16)> {
+        let chunk = self.table.chunk_for_index(idx)?;
+        let mut iterator = self.table.iter_bytes(chunk);
+        File::deserialize(&mut iterator)?;
+
+        let chunks = (iterator.chunk.0 - chunk.0) + 1;
+
+        Some((chunk, chunks))
+    }
+
+    /// Saves a file, replacing any existing file with the same name. Returns `None` if there are
+    /// already `MAX_FILES` files, or the storage is full.
+    pub fn save_file(&mut self, file: &File) -> Option<ChunkIndex> {
+        let idx = match self.find_file(&file.name) {
+            Some(idx) => idx,
+            None => (0..MAX_FILES).map(ChunkIndex).find(|idx| self.table.chunk_for_index(*idx).is_none())?,
+        };
+
+        self.write_file_at_index(idx, file)?;
+        Some(idx)
+    }
+
+    /// Renames the file at the given index. Returns `None` if another file already has the new
+    /// name, or the storage is full.
+    pub fn rename_file(&mut self, idx: ChunkIndex, name: &str) -> Option<()> {
+        if self.find_file(name).is_some() {
+            return None;
+        }
+
+        let mut file = self.read_file(idx)?;
+        file.name = name.into();
+        self.write_file_at_index(idx, &file)
+    }
+
+    /// Writes a file to the given index, replacing whatever was there.
+    fn write_file_at_index(&mut self, idx: ChunkIndex, file: &File) -> Option<()> {
+        // Write the new file before freeing the old one, so that if storage is full, the old file
+        // is left intact
+        let old_area = self.file_area_at_index(idx);
+
+        let bytes = file.serialize();
+        let length = self.table.chunks_required_for_bytes(bytes.len());
+        let address = self.table.allocate_chunks(length)?;
+        if self.table.write_bytes(address, bytes).is_none() || self.table.set_chunk_for_index(idx, address).is_none() {
+            self.table.free_chunks(address, length);
+            return None;
+        }
+
+        // The index now points at the new file, so the old heap space can be freed
+        if let Some((address, length)) = old_area {
+            self.table.free_chunks(address, length);
+        }
+
+        Some(())
+    }
+
+    /// Deletes the file at the given index.
+    pub fn delete_file(&mut self, idx: ChunkIndex) -> Option<()> {
+        let (address, length) = self.file_area_at_index(idx)?;
+        self.table.free_chunks(address, length)?;
+
+        // Chunk 0 is never allocated, so pointing the index there marks it as unassigned
+        self.table.set_chunk_for_index(idx, ChunkAddress(0))
+    }
+}
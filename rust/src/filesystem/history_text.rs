@@ -0,0 +1,112 @@
+use core::fmt::Display;
+
+use alloc::{format, string::String, vec::Vec};
+use rbop::node::unstructured::UnstructuredNodeRoot;
+
+use crate::{interface::ApplicationFramework, maths::{LinearNotation, LinearParseError}};
+use super::{Calculation, File, Filesystem};
+
+/// The name of the file which the history is exported to as text.
+pub const HISTORY_TEXT_FILE: &str = "history.txt";
+
+/// The name of the file which the history is exported to as CSV.
+pub const HISTORY_CSV_FILE: &str = "history.csv";
+
+/// Formats calculations as text, one per line, like `1+2 = 3`. Blank calculations are skipped.
+pub fn calculations_to_text(calculations: &[Calculation]) -> String {
+    let mut text = String::new();
+    for calc in calculations.iter().filter(|calc| !calc.root.root.items.is_empty()) {
+        text.push_str(&calc.root.to_linear());
+        if let Some(result) = calc.result_text() {
+            text.push_str(" = ");
+            text.push_str(&result);
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Formats calculations as CSV, with a header row and then an `expression,result` row for each
+/// calculation. Blank calculations are skipped.
+pub fn calculations_to_csv(calculations: &[Calculation]) -> String {
+    // Quote every field, since expressions can contain commas
+    let field = |value: &str| format!("\"{}\"", value.replace('"', "\"\""));
+
+    let mut csv = String::from("expression,result\n");
+    for calc in calculations.iter().filter(|calc| !calc.root.root.items.is_empty()) {
+        csv.push_str(&field(&calc.root.to_linear()));
+        csv.push(',');
+        csv.push_str(&field(&calc.result_text().unwrap_or_default()));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// An error encountered while importing history from text.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum HistoryImportError {
+    /// A line could not be parsed. Lines are numbered from 1.
+    Parse { line: usize, error: LinearParseError },
+
+    /// The imported calculations could not be saved.
+    Storage,
+}
+
+impl Display for HistoryImportError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            HistoryImportError::Parse { line, error } => write!(f, "Line {}: {}", line, error),
+            HistoryImportError::Storage => write!(f, "Could not save history, storage may be full"),
+        }
+    }
+}
+
+/// Parses expressions from text, one per line. This accepts the output of `calculations_to_text`:
+/// anything after an `=` is ignored, since results are recalculated. Blank lines, and lines
+/// starting with `#`, are skipped.
+pub fn expressions_from_text(text: &str) -> Result<Vec<UnstructuredNodeRoot>, HistoryImportError> {
+    let mut expressions = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let expression = line.split('=').next().unwrap();
+        if expression.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
+        let root = UnstructuredNodeRoot::from_linear(expression)
+            .map_err(|error| HistoryImportError::Parse { line: i + 1, error })?;
+        expressions.push(root);
+    }
+    Ok(expressions)
+}
+
+impl<F: ApplicationFramework> Filesystem<F> {
+    /// Saves the calculation history into the file store as text and CSV files, replacing any
+    /// previous export.
+    pub fn export_history_files(&mut self) -> Option<()> {
+        let calculations = self.calculations.read_calculations()?;
+        self.files.save_file(&File { name: HISTORY_TEXT_FILE.into(), contents: calculations_to_text(&calculations) })?;
+        self.files.save_file(&File { name: HISTORY_CSV_FILE.into(), contents: calculations_to_csv(&calculations) })?;
+        Some(())
+    }
+
+    /// Parses expressions from text with `expressions_from_text`, evaluates them, and adds them to
+    /// the end of the calculation history. Returns how many were added. Nothing is added if any
+    /// line is invalid.
+    pub fn import_history_text(&mut self, text: &str) -> Result<usize, HistoryImportError> {
+        let expressions = expressions_from_text(text)?;
+        let mut calculations = self.calculations.read_calculations().ok_or(HistoryImportError::Storage)?;
+
+        // The calculator keeps a blank calculation at the end to type into
+        while calculations.last().map(|calc| calc.root.root.items.is_empty()).unwrap_or(false) {
+            calculations.pop();
+        }
+
+        for root in expressions.iter() {
+            let result = self.evaluate(root);
+            calculations.push(Calculation { root: root.clone(), result, solve: None });
+        }
+        self.calculations.replace_calculations(&calculations).ok_or(HistoryImportError::Storage)?;
+
+        Ok(expressions.len())
+    }
+}
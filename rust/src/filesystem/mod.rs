@@ -2,20 +2,35 @@ pub mod chunk_table;
 pub mod raw_storage;
 pub mod calculation_history;
 pub mod settings;
-// pub mod fat_interface;
+pub mod settings_registry;
+pub mod data_lists;
+pub mod user_functions;
+pub mod file_store;
+pub mod disk_image;
+pub mod history_text;
 
 pub use chunk_table::*;
 pub use raw_storage::*;
 pub use calculation_history::*;
 pub use settings::*;
+pub use settings_registry::*;
+pub use data_lists::*;
+pub use user_functions::*;
+pub use file_store::*;
+pub use disk_image::*;
+pub use history_text::*;
+
+mod test;
+pub use test::test;
 
 use crate::interface::ApplicationFramework;
-// pub use fat_interface::*;
 
 pub struct Filesystem<F: ApplicationFramework + 'static> {
     pub settings: Settings<F>,
     pub calculations: CalculationHistory<F>,
-    // pub fat: FatInterface<'a>,
+    pub data_lists: DataLists<F>,
+    pub user_functions: UserFunctions<F>,
+    pub files: FileStore<F>,
 }
 
 impl<F: ApplicationFramework> Filesystem<F> {
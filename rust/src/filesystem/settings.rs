@@ -1,45 +1,45 @@
+use alloc::vec::Vec;
 use rbop::node::structured::{EvaluationSettings, AngleUnit};
 
-use crate::interface::ApplicationFramework;
-use super::{RawStorage, RawStorageAddress};
+use crate::{interface::ApplicationFramework, maths::ComplexFormat, host_link::crc16};
+use super::{RawStorage, RawStorageAddress, SettingsValues};
 
 pub struct Settings<F: ApplicationFramework + 'static> {
     pub storage: RawStorage<F>,
     pub values: SettingsValues,
 }
 
-#[derive(PartialEq, Eq, Clone, Debug)]
-pub struct SettingsValues {
-    pub show_heap_usage: bool,
-    pub show_frame_time: bool,
-    pub fire_button_press_only: bool,
-
-    pub angle_unit: AngleUnit,
-}
-
-impl Default for SettingsValues {
-    fn default() -> Self {
-        SettingsValues {
-            show_heap_usage: false,
-            show_frame_time: false,
-            fire_button_press_only: true,
-            
-            angle_unit: AngleUnit::default(),
-        }
-    }
-}
-
 impl<F: ApplicationFramework> Settings<F> {
-    // These random values were chosen so that a fully 0xFF'd or 0x00'd memory can be detected, and
-    // settings can be left unset.
+    // Settings are stored as a record, laid out as:
+    //   - `RECORD_MAGIC`
+    //   - The record version
+    //   - The length of the body, 16-bit big-endian
+    //   - The CRC-16/CCITT-FALSE of the body, 16-bit big-endian
+    //   - The body, from `SettingsValues::to_record`, which has an entry for each setting in the
+    //     registry
+    //
+    // Before records, each setting was a boolean byte at a fixed address, with nothing at address
+    // 0. Those settings are still read if there's no record, and replaced by one on the next save.
 
     // The minimum size that the storage area used for this should be. Future-proofed!
     pub const MINIMUM_STORAGE_SIZE: u16 = 1028;
 
-    /// The value of a true boolean when stored as a byte.
+    /// Marks the start of a settings record. This was chosen so that a fully 0xFF'd or 0x00'd
+    /// memory, or settings from before records, aren't mistaken for a record.
+    const RECORD_MAGIC: [u8; 2] = [0xD5, 0x7E];
+
+    /// The version of the record layout. This only needs incrementing if the header changes, or
+    /// the body stops being a list of entries, since new settings can just use new IDs. Records
+    /// with a newer version than this are ignored rather than risk misreading them.
+    pub const RECORD_VERSION: u8 = 1;
+
+    /// The length of the record header, before the body.
+    const RECORD_HEADER_LENGTH: u16 = 7;
+
+    /// The value of a true boolean when stored as a byte, before records.
     const TRUE_BYTE: u8 = 0x39;
 
-    /// The value of a false boolean when stored as a byte.
+    /// The value of a false boolean when stored as a byte, before records.
     const FALSE_BYTE: u8 = 0xB5;
 
     /// Returns a new `Settings` instance with default settings.
@@ -50,13 +50,35 @@ impl<F: ApplicationFramework> Settings<F> {
         }
     }
 
-    /// Loads settings values from storage, using their defaults if any are not set. Returns None
-    /// if storage is inaccessible. Despite taking `mut self` due to use of the I2C bus, this does
-    /// not mutate any values.
+    /// Loads settings values from storage, using their defaults if any are not set, or if the
+    /// stored record is corrupt. Returns None if storage is inaccessible. Despite taking
+    /// `mut self` due to use of the I2C bus, this does not mutate any values.
     pub fn load(&mut self) -> Option<SettingsValues> {
         let default = SettingsValues::default();
+
+        let header = self.storage.read_bytes(RawStorageAddress(0), Self::RECORD_HEADER_LENGTH)?;
+        if header[..2] != Self::RECORD_MAGIC {
+            return self.load_unversioned();
+        }
+        let version = header[2];
+        let length = u16::from_be_bytes([header[3], header[4]]);
+        let checksum = u16::from_be_bytes([header[5], header[6]]);
+        if version > Self::RECORD_VERSION || length > Self::MINIMUM_STORAGE_SIZE - Self::RECORD_HEADER_LENGTH {
+            return Some(default);
+        }
+
+        let record = self.storage.read_bytes(RawStorageAddress(Self::RECORD_HEADER_LENGTH), length)?;
+        if crc16(&record) != checksum {
+            return Some(default);
+        }
+        Some(default.with_record(&record).unwrap_or(default))
+    }
+
+    /// Loads settings stored before records were introduced, where each setting is a boolean at a
+    /// fixed address. Returns None if storage is inaccessible.
+    fn load_unversioned(&mut self) -> Option<SettingsValues> {
+        let default = SettingsValues::default();
         Some(SettingsValues {
-            // TODO: index 0 should be a storage version
             show_heap_usage: self.read_bool(RawStorageAddress(1), default.show_heap_usage)?,
             show_frame_time: self.read_bool(RawStorageAddress(2), default.show_frame_time)?,
             fire_button_press_only: self.read_bool(RawStorageAddress(3), default.fire_button_press_only)?,
@@ -67,6 +89,16 @@ impl<F: ApplicationFramework> Settings<F> {
             } else {
                 AngleUnit::Radian
             },
+
+            // Same hack - true is Polar, false is Rectangular
+            complex_format: if self.read_bool(RawStorageAddress(5), default.complex_format == ComplexFormat::Polar)? {
+                ComplexFormat::Polar
+            } else {
+                ComplexFormat::Rectangular
+            },
+
+            // Settings added since records were introduced were never stored this way
+            ..default
         })
     }
 
@@ -78,18 +110,22 @@ impl<F: ApplicationFramework> Settings<F> {
         }
     }
 
-    /// Saves the settings values in this instance to storage. Returns None if storage is
-    /// inaccessible.
+    /// Saves the settings values in this instance to storage as a record. Returns None if storage
+    /// is inaccessible.
     pub fn save(&mut self) -> Option<()> {
-        self.write_bool(RawStorageAddress(1), self.values.show_heap_usage)?;
-        self.write_bool(RawStorageAddress(2), self.values.show_frame_time)?;
-        self.write_bool(RawStorageAddress(3), self.values.fire_button_press_only)?;
-        self.write_bool(RawStorageAddress(4), self.values.angle_unit == AngleUnit::Degree)?; // Hack again (see `load`)
-        Some(())
+        let record = self.values.to_record();
+
+        let mut bytes = Vec::with_capacity(Self::RECORD_HEADER_LENGTH as usize + record.len());
+        bytes.extend_from_slice(&Self::RECORD_MAGIC);
+        bytes.push(Self::RECORD_VERSION);
+        bytes.extend_from_slice(&(record.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&crc16(&record).to_be_bytes());
+        bytes.extend_from_slice(&record);
+        self.storage.write_bytes(RawStorageAddress(0), &bytes)
     }
 
-    /// Loads a boolean from storage, or falls back to a given default if no valid boolean is
-    /// stored. Returns None if storage is inaccessible.
+    /// Loads a boolean stored before records, or falls back to a given default if no valid
+    /// boolean is stored. Returns None if storage is inaccessible.
     fn read_bool(&mut self, address: RawStorageAddress, default: bool) -> Option<bool> {
         let byte = self.storage.read_byte(address)?;
         match byte {
@@ -99,12 +135,6 @@ impl<F: ApplicationFramework> Settings<F> {
         }
     }
 
-    /// Writes a boolean to storage. Returns None if storage is inaccessible.
-    fn write_bool(&mut self, address: RawStorageAddress, value: bool) -> Option<()> {
-        let byte = if value { Self::TRUE_BYTE } else { Self::FALSE_BYTE };
-        self.storage.write_byte(address, byte)
-    }
-
     /// Creates an `EvaluationSettings` object from these settings.
     pub fn evaluation_settings(&self) -> EvaluationSettings {
         EvaluationSettings { angle_unit: self.values.angle_unit, ..Default::default() }
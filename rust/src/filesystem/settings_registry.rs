@@ -0,0 +1,428 @@
+//! The registry of settings. Each setting is declared once in the `settings!` invocation below,
+//! with its type, default, label and icon, and everything else - the `SettingsValues` struct, the
+//! stored record, the text form, and the Settings app's menu - is generated from that.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use rbop::node::structured::AngleUnit;
+
+use crate::maths::ComplexFormat;
+
+/// The value of a setting, independent of its Rust type.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum SettingValue {
+    Bool(bool),
+
+    /// The index of the chosen option of a `SettingKind::Choice`.
+    Choice(u8),
+
+    Number(u16),
+}
+
+/// How a setting's value is chosen and displayed.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum SettingKind {
+    /// Turned on or off.
+    Bool,
+
+    /// One of a list of options, by name.
+    Choice(&'static [&'static str]),
+
+    /// A whole number between `min` and `max` inclusive, changed in increments of `step`, and
+    /// displayed followed by `unit`.
+    Number { min: u16, max: u16, step: u16, unit: &'static str },
+}
+
+impl SettingKind {
+    /// Returns whether a value is valid for a setting of this kind.
+    pub fn accepts(&self, value: SettingValue) -> bool {
+        match (self, value) {
+            (SettingKind::Bool, SettingValue::Bool(_)) => true,
+            (SettingKind::Choice(options), SettingValue::Choice(i)) => (i as usize) < options.len(),
+            (SettingKind::Number { min, max, .. }, SettingValue::Number(n)) => (*min..=*max).contains(&n),
+            _ => false,
+        }
+    }
+
+    /// Returns the value after `value`, or before it if `forwards` is false, wrapping around at the
+    /// ends. Booleans are toggled.
+    pub fn step(&self, value: SettingValue, forwards: bool) -> SettingValue {
+        match (self, value) {
+            (SettingKind::Choice(options), SettingValue::Choice(i)) => {
+                let count = options.len() as u8;
+                SettingValue::Choice(if forwards { (i + 1) % count } else { (i + count - 1) % count })
+            }
+            (SettingKind::Number { min, max, step, .. }, SettingValue::Number(n)) => SettingValue::Number(
+                if forwards {
+                    if n.saturating_add(*step) > *max { *min } else { n + step }
+                } else if n < min.saturating_add(*step) {
+                    *max
+                } else {
+                    n - step
+                }
+            ),
+            (_, SettingValue::Bool(b)) => SettingValue::Bool(!b),
+            _ => value,
+        }
+    }
+
+    /// Formats a value of this kind for the `key = value` text form.
+    fn value_to_text(&self, value: SettingValue) -> String {
+        match (self, value) {
+            (SettingKind::Choice(options), SettingValue::Choice(i)) => options[i as usize].to_ascii_lowercase(),
+            (_, SettingValue::Bool(b)) => format!("{}", b),
+            (_, SettingValue::Number(n)) => format!("{}", n),
+            (_, SettingValue::Choice(i)) => format!("{}", i),
+        }
+    }
+
+    /// Parses a value of this kind from the `key = value` text form, case-insensitively.
+    fn value_from_text(&self, text: &str) -> Option<SettingValue> {
+        let value = match self {
+            SettingKind::Bool => match text.to_ascii_lowercase().as_str() {
+                "true" => SettingValue::Bool(true),
+                "false" => SettingValue::Bool(false),
+                _ => return None,
+            },
+            SettingKind::Choice(options) => SettingValue::Choice(
+                options.iter().position(|option| option.eq_ignore_ascii_case(text))? as u8
+            ),
+            SettingKind::Number { .. } => SettingValue::Number(text.parse().ok()?),
+        };
+        if self.accepts(value) { Some(value) } else { None }
+    }
+
+    /// Encodes a value of this kind for a stored settings record.
+    fn encode(&self, value: SettingValue) -> Vec<u8> {
+        match value {
+            SettingValue::Bool(b) => vec![b as u8],
+            SettingValue::Choice(i) => vec![i],
+            SettingValue::Number(n) => n.to_be_bytes().to_vec(),
+        }
+    }
+
+    /// Decodes a value of this kind from a stored settings record.
+    fn decode(&self, bytes: &[u8]) -> Option<SettingValue> {
+        let value = match (self, bytes) {
+            (SettingKind::Bool, [0]) => SettingValue::Bool(false),
+            (SettingKind::Bool, [1]) => SettingValue::Bool(true),
+            (SettingKind::Choice(_), [i]) => SettingValue::Choice(*i),
+            (SettingKind::Number { .. }, [a, b]) => SettingValue::Number(u16::from_be_bytes([*a, *b])),
+            _ => return None,
+        };
+        if self.accepts(value) { Some(value) } else { None }
+    }
+}
+
+/// A type which a setting can be stored as in `SettingsValues`.
+pub trait SettingType: Sized {
+    fn to_setting_value(&self) -> SettingValue;
+    fn from_setting_value(value: SettingValue) -> Option<Self>;
+}
+
+impl SettingType for bool {
+    fn to_setting_value(&self) -> SettingValue { SettingValue::Bool(*self) }
+
+    fn from_setting_value(value: SettingValue) -> Option<Self> {
+        match value {
+            SettingValue::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+}
+
+impl SettingType for u16 {
+    fn to_setting_value(&self) -> SettingValue { SettingValue::Number(*self) }
+
+    fn from_setting_value(value: SettingValue) -> Option<Self> {
+        match value {
+            SettingValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+}
+
+impl SettingType for AngleUnit {
+    fn to_setting_value(&self) -> SettingValue {
+        SettingValue::Choice(match self {
+            AngleUnit::Degree => 0,
+            AngleUnit::Radian => 1,
+        })
+    }
+
+    fn from_setting_value(value: SettingValue) -> Option<Self> {
+        match value {
+            SettingValue::Choice(0) => Some(AngleUnit::Degree),
+            SettingValue::Choice(1) => Some(AngleUnit::Radian),
+            _ => None,
+        }
+    }
+}
+
+impl SettingType for ComplexFormat {
+    fn to_setting_value(&self) -> SettingValue {
+        SettingValue::Choice(match self {
+            ComplexFormat::Rectangular => 0,
+            ComplexFormat::Polar => 1,
+        })
+    }
+
+    fn from_setting_value(value: SettingValue) -> Option<Self> {
+        match value {
+            SettingValue::Choice(0) => Some(ComplexFormat::Rectangular),
+            SettingValue::Choice(1) => Some(ComplexFormat::Polar),
+            _ => None,
+        }
+    }
+}
+
+/// A setting in the registry.
+pub struct SettingDefinition {
+    /// Identifies the setting within a stored settings record. These must never be renumbered or
+    /// reused, and a setting whose encoding changes incompatibly should get a new ID, so that
+    /// older records are never misread.
+    pub id: u8,
+
+    /// Identifies the setting in the `key = value` text form.
+    pub key: &'static str,
+
+    pub label: &'static str,
+    pub icon: &'static str,
+    pub kind: SettingKind,
+
+    /// Whether the setting is shown in the Settings app.
+    pub visible: bool,
+
+    pub get: fn(&SettingsValues) -> SettingValue,
+    pub set: fn(&mut SettingsValues, SettingValue),
+}
+
+impl SettingDefinition {
+    /// Finds a setting by its ID.
+    pub fn with_id(id: u8) -> Option<&'static SettingDefinition> {
+        SETTINGS.iter().find(|setting| setting.id == id)
+    }
+
+    /// Finds a setting by its key.
+    pub fn with_key(key: &str) -> Option<&'static SettingDefinition> {
+        SETTINGS.iter().find(|setting| setting.key == key)
+    }
+
+    /// Formats a value of this setting for display, like `Angle unit: Degree`. Booleans are just
+    /// their label, since they're shown with a toggle.
+    pub fn title(&self, value: SettingValue) -> String {
+        match (self.kind, value) {
+            (SettingKind::Choice(options), SettingValue::Choice(i)) => format!("{}: {}", self.label, options[i as usize]),
+            (SettingKind::Number { unit, .. }, SettingValue::Number(n)) => format!("{}: {}{}", self.label, n, unit),
+            _ => self.label.into(),
+        }
+    }
+}
+
+macro_rules! settings {
+    ($(
+        $(#[doc = $doc:literal])*
+        $field:ident: $ty:ty = $default:expr => {
+            id: $id:literal,
+            label: $label:literal,
+            icon: $icon:literal,
+            kind: $kind:expr,
+            visible: $visible:literal $(,)?
+        }
+    ),* $(,)?) => {
+        #[derive(PartialEq, Eq, Clone, Debug)]
+        pub struct SettingsValues {
+            $(
+                $(#[doc = $doc])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl Default for SettingsValues {
+            fn default() -> Self {
+                SettingsValues { $($field: $default,)* }
+            }
+        }
+
+        /// Every setting, in the order they're shown in the Settings app.
+        pub static SETTINGS: &[SettingDefinition] = &[$(
+            SettingDefinition {
+                id: $id,
+                key: stringify!($field),
+                label: $label,
+                icon: $icon,
+                kind: $kind,
+                visible: $visible,
+                get: |values| values.$field.to_setting_value(),
+                set: |values, value| if let Some(value) = <$ty>::from_setting_value(value) {
+                    values.$field = value;
+                },
+            },
+        )*];
+    };
+}
+
+settings! {
+    angle_unit: AngleUnit = AngleUnit::Degree => {
+        id: 4,
+        label: "Angle unit",
+        icon: "settings_angle_unit",
+        kind: SettingKind::Choice(&["Degree", "Radian"]),
+        visible: true,
+    },
+
+    complex_format: ComplexFormat = ComplexFormat::Rectangular => {
+        id: 5,
+        label: "Complex format",
+        icon: "settings_complex_format",
+        kind: SettingKind::Choice(&["Rectangular", "Polar"]),
+        visible: true,
+    },
+
+    show_frame_time: bool = false => {
+        id: 2,
+        label: "Show frame time",
+        icon: "settings_show_frame_time",
+        kind: SettingKind::Bool,
+        visible: true,
+    },
+
+    show_heap_usage: bool = false => {
+        id: 1,
+        label: "Show heap usage",
+        icon: "settings_show_memory_usage",
+        kind: SettingKind::Bool,
+        visible: true,
+    },
+
+    /// Whether buttons fire when they're pressed, rather than when they're released.
+    fire_button_press_only: bool = true => {
+        id: 3,
+        label: "Fire on press",
+        icon: "settings_fire_button_press_only",
+        kind: SettingKind::Bool,
+        visible: true,
+    },
+
+    /// How long after a button is released that another press of it is ignored, in milliseconds.
+    /// This filters out switch bounce which gets past the keypad's own debouncing.
+    debounce_time: u16 = 0 => {
+        id: 6,
+        label: "Debounce",
+        icon: "settings_debounce",
+        kind: SettingKind::Number { min: 0, max: 200, step: 20, unit: "ms" },
+        visible: true,
+    },
+
+    /// Whether the arrow keys and DEL fire repeatedly while held down.
+    key_repeat: bool = true => {
+        id: 7,
+        label: "Key repeat",
+        icon: "settings_key_repeat",
+        kind: SettingKind::Bool,
+        visible: true,
+    },
+
+    /// How long a key must be held before it starts repeating, in milliseconds.
+    key_repeat_delay: u16 = 500 => {
+        id: 8,
+        label: "Repeat delay",
+        icon: "settings_key_repeat_delay",
+        kind: SettingKind::Number { min: 200, max: 1000, step: 100, unit: "ms" },
+        visible: true,
+    },
+
+    /// How long between each repeat of a held key, in milliseconds.
+    key_repeat_interval: u16 = 100 => {
+        id: 9,
+        label: "Repeat interval",
+        icon: "settings_key_repeat_interval",
+        kind: SettingKind::Number { min: 50, max: 500, step: 50, unit: "ms" },
+        visible: true,
+    },
+
+    /// How long a key which doesn't repeat must be held to fire a long press, in milliseconds.
+    long_press_time: u16 = 800 => {
+        id: 10,
+        label: "Long press",
+        icon: "settings_long_press",
+        kind: SettingKind::Number { min: 300, max: 2000, step: 100, unit: "ms" },
+        visible: true,
+    },
+}
+
+impl SettingsValues {
+    /// Returns the value of a setting.
+    pub fn get(&self, setting: &SettingDefinition) -> SettingValue {
+        (setting.get)(self)
+    }
+
+    /// Changes the value of a setting. Values which aren't valid for the setting are ignored.
+    pub fn set(&mut self, setting: &SettingDefinition, value: SettingValue) {
+        if setting.kind.accepts(value) {
+            (setting.set)(self, value);
+        }
+    }
+
+    /// Describes these settings as lines of `key = value`, which can be edited by hand and read
+    /// back with `with_text`.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for setting in SETTINGS {
+            text.push_str(&format!("{} = {}\n", setting.key, setting.kind.value_to_text(self.get(setting))));
+        }
+        text
+    }
+
+    /// Parses settings written by `to_text`. Settings which aren't mentioned keep their values from
+    /// `self`. Returns `None` if any line is invalid.
+    pub fn with_text(&self, text: &str) -> Option<SettingsValues> {
+        let mut values = self.clone();
+
+        for line in text.lines().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+            let (key, value) = line.split_once('=')?;
+            let setting = SettingDefinition::with_key(key.trim())?;
+            values.set(setting, setting.kind.value_from_text(value.trim())?);
+        }
+
+        Some(values)
+    }
+
+    /// Encodes these settings as the body of a settings record. Each setting is an entry of its
+    /// ID, the length of its value, and then the value, so that readers can skip entries they
+    /// don't recognise.
+    pub fn to_record(&self) -> Vec<u8> {
+        let mut record = vec![];
+        for setting in SETTINGS {
+            let value = setting.kind.encode(self.get(setting));
+            record.push(setting.id);
+            record.push(value.len() as u8);
+            record.extend_from_slice(&value);
+        }
+        record
+    }
+
+    /// Decodes the body of a settings record written by `to_record`. Settings which aren't in the
+    /// record, or whose values aren't recognised, keep their values from `self`. Returns `None` if
+    /// the record is truncated.
+    pub fn with_record(&self, record: &[u8]) -> Option<SettingsValues> {
+        let mut values = self.clone();
+
+        let mut rest = record;
+        while let [id, length, tail @ ..] = rest {
+            if tail.len() < *length as usize {
+                return None;
+            }
+            let (value, tail) = tail.split_at(*length as usize);
+            rest = tail;
+
+            if let Some(setting) = SettingDefinition::with_id(*id)
+                && let Some(value) = setting.kind.decode(value)
+            {
+                values.set(setting, value);
+            }
+        }
+
+        // A single byte left over is a truncated entry
+        if rest.is_empty() { Some(values) } else { None }
+    }
+}
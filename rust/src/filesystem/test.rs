@@ -0,0 +1,80 @@
+use alloc::vec;
+use rbop::node::structured::AngleUnit;
+
+use crate::{interface::ApplicationFramework, operating_system::OperatingSystem, maths::ComplexFormat};
+
+use super::{RawStorageAddress, SettingKind, SettingValue, SettingsValues, SETTINGS};
+
+pub fn test<F: ApplicationFramework>(os: &mut OperatingSystem<F>) {
+    let settings = &mut os.filesystem.settings;
+    let original_values = settings.values.clone();
+
+    // Settings survive being saved and loaded
+    let values = SettingsValues {
+        show_heap_usage: true,
+        angle_unit: AngleUnit::Radian,
+        complex_format: ComplexFormat::Polar,
+        ..SettingsValues::default()
+    };
+    settings.values = values.clone();
+    settings.save().unwrap();
+    assert_eq!(settings.load(), Some(values.clone()));
+
+    // Every setting in the registry has a unique ID and key, and a valid default
+    for (i, setting) in SETTINGS.iter().enumerate() {
+        assert!(SETTINGS[..i].iter().all(|other| other.id != setting.id && other.key != setting.key));
+        assert!(setting.kind.accepts(SettingsValues::default().get(setting)));
+    }
+
+    // The text form converts back again, and rejects invalid values
+    assert_eq!(SettingsValues::default().with_text(&values.to_text()), Some(values.clone()));
+    assert_eq!(values.with_text("angle_unit = Gradian"), None);
+    assert_eq!(values.with_text("no_such_setting = true"), None);
+
+    // Stepping through values wraps around
+    let choice = SettingKind::Choice(&["A", "B", "C"]);
+    assert_eq!(choice.step(SettingValue::Choice(2), true), SettingValue::Choice(0));
+    assert_eq!(choice.step(SettingValue::Choice(0), false), SettingValue::Choice(2));
+    let number = SettingKind::Number { min: 10, max: 30, step: 10, unit: "" };
+    assert_eq!(number.step(SettingValue::Number(20), true), SettingValue::Number(30));
+    assert_eq!(number.step(SettingValue::Number(30), true), SettingValue::Number(10));
+    assert_eq!(number.step(SettingValue::Number(10), false), SettingValue::Number(30));
+    assert!(!number.accepts(SettingValue::Number(40)));
+
+    // Records skip entries they don't recognise, and ignore invalid values
+    let mut record = vec![0x70, 2, 0xAA, 0xBB];
+    record.extend_from_slice(&values.to_record());
+    record.extend_from_slice(&[1, 1, 0xCC]);
+    assert_eq!(SettingsValues::default().with_record(&record), Some(values.clone()));
+    assert_eq!(SettingsValues::default().with_record(&record[..record.len() - 1]), None);
+
+    // A corrupted record is detected, and defaults are used instead
+    let last = RawStorageAddress(6 + values.to_record().len() as u16);
+    let byte = settings.storage.read_byte(last).unwrap();
+    settings.storage.write_byte(last, byte ^ 0x01).unwrap();
+    assert_eq!(settings.load(), Some(SettingsValues::default()));
+
+    // Settings from before records are migrated, with 0x39 as true and 0xB5 as false, and any
+    // which weren't set keep their defaults, even if a newer record follows the old bytes
+    settings.values = SettingsValues {
+        debounce_time: 100,
+        key_repeat: false,
+        key_repeat_delay: 1000,
+        key_repeat_interval: 500,
+        long_press_time: 2000,
+        ..SettingsValues::default()
+    };
+    settings.save().unwrap();
+    settings.storage.write_bytes(RawStorageAddress(0), &[0x00, 0x39, 0xFF, 0xB5, 0xB5, 0x39, 0xFF]).unwrap();
+    assert_eq!(settings.load(), Some(SettingsValues {
+        show_heap_usage: true,
+        fire_button_press_only: false,
+        angle_unit: AngleUnit::Radian,
+        complex_format: ComplexFormat::Polar,
+        ..SettingsValues::default()
+    }));
+
+    // Put things back as they were
+    settings.values = original_values;
+    settings.save().unwrap();
+}
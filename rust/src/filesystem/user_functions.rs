@@ -0,0 +1,234 @@
+use core::fmt::Display;
+
+use alloc::{vec, vec::Vec};
+use rbop::{UnstructuredNode, UnstructuredNodeList, Token, node::unstructured::UnstructuredNodeRoot, serialize::Serializable};
+
+use crate::{filesystem::chunk_table::ChunkIndex, interface::ApplicationFramework};
+
+use super::chunk_table::{ChunkAddress, ChunkTable};
+
+/// The names which user-defined functions may have. Each is stored at the chunk index matching its
+/// position in this list.
+pub const USER_FUNCTION_NAMES: [char; 3] = ['f', 'g', 'h'];
+
+/// Why a function couldn't be defined.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum DefinitionError {
+    /// The definition calls itself, directly or through other functions, so expanding it would
+    /// never end.
+    Recursive,
+
+    /// The definition couldn't be written, because storage is full.
+    StorageFull,
+}
+
+impl Display for DefinitionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DefinitionError::Recursive => write!(f, "A function can't call itself"),
+            DefinitionError::StorageFull => write!(f, "Could not save function, storage may be full"),
+        }
+    }
+}
+
+/// User-defined functions of `x`, such as `f(x) = x^2 + 1`.
+///
+/// Definitions are kept in memory once loaded, since they're needed every time an expression is
+/// evaluated, and are written through to a chunk table whenever they change.
+pub struct UserFunctions<F: ApplicationFramework + 'static> {
+    pub table: ChunkTable<F>,
+
+    /// The body of each function in `USER_FUNCTION_NAMES`, or `None` if it is not defined.
+    pub definitions: Vec<Option<UnstructuredNodeRoot>>,
+}
+
+impl<F: ApplicationFramework> UserFunctions<F> {
+    /// Loads every definition from storage.
+    pub fn load_into_self(&mut self) {
+        self.definitions = (0..USER_FUNCTION_NAMES.len())
+            .map(|i| self.read_definition(ChunkIndex(i as u16)))
+            .collect();
+    }
+
+    fn read_definition(&mut self, idx: ChunkIndex) -> Option<UnstructuredNodeRoot> {
+        let chunk = self.table.chunk_for_index(idx)?;
+        let root = UnstructuredNodeRoot::deserialize(&mut self.table.iter_bytes(chunk))?;
+
+        // An empty body is how a deleted definition is stored
+        if root.root.items.is_empty() { None } else { Some(root) }
+    }
+
+    fn definition_area_at_index(&mut self, idx: ChunkIndex) -> Option<(ChunkAddress, u16)> {
+        let chunk = self.table.chunk_for_index(idx)?;
+        let mut iterator = self.table.iter_bytes(chunk);
+        UnstructuredNodeRoot::deserialize(&mut iterator)?;
+
+        let chunks = (iterator.chunk.0 - chunk.0) + 1;
+
+        Some((chunk, chunks))
+    }
+
+    /// Defines, redefines, or (given `None`) deletes the function with the given name.
+    ///
+    /// Panics if `name` isn't one of `USER_FUNCTION_NAMES`.
+    pub fn set_definition(&mut self, name: char, body: Option<UnstructuredNodeRoot>) -> Result<(), DefinitionError> {
+        let index = USER_FUNCTION_NAMES.iter().position(|n| *n == name).unwrap();
+        let idx = ChunkIndex(index as u16);
+
+        if let Some(body) = &body && self.calls_function(&body.root, name) {
+            return Err(DefinitionError::Recursive);
+        }
+
+        // Write the new definition before freeing the old one, so that if storage is full, the
+        // old definition is left intact
+        let old_area = self.definition_area_at_index(idx);
+
+        let empty = UnstructuredNodeRoot { root: UnstructuredNodeList { items: vec![] } };
+        let bytes = body.as_ref().unwrap_or(&empty).serialize();
+        let length = self.table.chunks_required_for_bytes(bytes.len());
+        let address = self.table.allocate_chunks(length).ok_or(DefinitionError::StorageFull)?;
+        if self.table.write_bytes(address, bytes).is_none() || self.table.set_chunk_for_index(idx, address).is_none() {
+            self.table.free_chunks(address, length);
+            return Err(DefinitionError::StorageFull);
+        }
+
+        // The index now points at the new definition, so the old heap space can be freed
+        if let Some((address, length)) = old_area {
+            self.table.free_chunks(address, length);
+        }
+
+        self.definitions[index] = body;
+        Ok(())
+    }
+
+    /// Returns whether an expression calls the function with the given name, either directly or
+    /// through the definitions of the functions which it calls.
+    fn calls_function(&self, list: &UnstructuredNodeList, name: char) -> bool {
+        let mut to_visit = called_functions(list);
+        let mut visited = vec![];
+        while let Some(called) = to_visit.pop() {
+            if called == name {
+                return true;
+            }
+            if visited.contains(&called) {
+                continue;
+            }
+            visited.push(called);
+
+            if let Some(body) = self.definition(called) {
+                to_visit.extend(called_functions(&body.root));
+            }
+        }
+
+        false
+    }
+
+    /// The body of the function with the given name, if it is defined.
+    pub fn definition(&self, name: char) -> Option<&UnstructuredNodeRoot> {
+        let index = USER_FUNCTION_NAMES.iter().position(|n| *n == name)?;
+        self.definitions.get(index)?.as_ref()
+    }
+
+    /// Replaces calls to user-defined functions in an expression with their bodies, so that the
+    /// result can be upgraded and evaluated by rbop.
+    ///
+    /// A call is a function's name followed immediately by parentheses, like `f(3)`. The call is
+    /// replaced by the function's body in parentheses, with each `x` replaced by the parenthesised
+    /// argument.
+    ///
+    /// `set_definition` doesn't allow functions to call themselves, but in case a recursive
+    /// definition was stored anyway, a call to a function within its own expansion is left
+    /// unexpanded, and fails to evaluate.
+    pub fn expand(&self, root: &UnstructuredNodeRoot) -> UnstructuredNodeRoot {
+        UnstructuredNodeRoot { root: self.expand_list(&root.root, &mut vec![]) }
+    }
+
+    /// Expands calls in a list. `expanding` holds the names of the functions whose bodies the list
+    /// is within.
+    fn expand_list(&self, list: &UnstructuredNodeList, expanding: &mut Vec<char>) -> UnstructuredNodeList {
+        let mut items = Vec::with_capacity(list.items.len());
+        let mut iter = list.items.iter().peekable();
+        while let Some(node) = iter.next() {
+            if let UnstructuredNode::Token(Token::Variable(name)) = node
+                && !expanding.contains(name)
+                && let Some(body) = self.definition(*name)
+                && let Some(UnstructuredNode::Parentheses(argument)) = iter.peek()
+            {
+                let argument = self.expand_list(argument, expanding);
+
+                // Expand calls within the body, but not in the argument, which has been expanded
+                // already
+                expanding.push(*name);
+                let body = self.expand_list(&body.root, expanding);
+                expanding.pop();
+
+                items.push(UnstructuredNode::Parentheses(substitute_x(&body, &argument)));
+                iter.next();
+                continue;
+            }
+
+            items.push(self.expand_node(node, expanding));
+        }
+
+        UnstructuredNodeList { items }
+    }
+
+    fn expand_node(&self, node: &UnstructuredNode, expanding: &mut Vec<char>) -> UnstructuredNode {
+        match node {
+            UnstructuredNode::Token(_) => node.clone(),
+            UnstructuredNode::Sqrt(inner) => UnstructuredNode::Sqrt(self.expand_list(inner, expanding)),
+            UnstructuredNode::Power(inner) => UnstructuredNode::Power(self.expand_list(inner, expanding)),
+            UnstructuredNode::Parentheses(inner) => UnstructuredNode::Parentheses(self.expand_list(inner, expanding)),
+            UnstructuredNode::Fraction(top, bottom) =>
+                UnstructuredNode::Fraction(self.expand_list(top, expanding), self.expand_list(bottom, expanding)),
+            UnstructuredNode::FunctionCall(function, args) => UnstructuredNode::FunctionCall(
+                function.clone(),
+                args.iter().map(|arg| self.expand_list(arg, expanding)).collect(),
+            ),
+        }
+    }
+}
+
+/// Replaces each `x` in a list with the given argument, in parentheses.
+fn substitute_x(list: &UnstructuredNodeList, argument: &UnstructuredNodeList) -> UnstructuredNodeList {
+    let substitute = |inner: &UnstructuredNodeList| substitute_x(inner, argument);
+    UnstructuredNodeList {
+        items: list.items.iter().map(|node| match node {
+            UnstructuredNode::Token(Token::Variable('x')) => UnstructuredNode::Parentheses(argument.clone()),
+            UnstructuredNode::Token(_) => node.clone(),
+            UnstructuredNode::Sqrt(inner) => UnstructuredNode::Sqrt(substitute(inner)),
+            UnstructuredNode::Power(inner) => UnstructuredNode::Power(substitute(inner)),
+            UnstructuredNode::Parentheses(inner) => UnstructuredNode::Parentheses(substitute(inner)),
+            UnstructuredNode::Fraction(top, bottom) => UnstructuredNode::Fraction(substitute(top), substitute(bottom)),
+            UnstructuredNode::FunctionCall(function, args) =>
+                UnstructuredNode::FunctionCall(function.clone(), args.iter().map(substitute).collect()),
+        }).collect(),
+    }
+}
+
+/// Returns the names of the user-defined functions which a list calls, at any level of nesting. A
+/// name may appear more than once.
+fn called_functions(list: &UnstructuredNodeList) -> Vec<char> {
+    let mut names = vec![];
+    let mut iter = list.items.iter().peekable();
+    while let Some(node) = iter.next() {
+        match node {
+            UnstructuredNode::Token(Token::Variable(name)) => {
+                if USER_FUNCTION_NAMES.contains(name) && matches!(iter.peek(), Some(UnstructuredNode::Parentheses(_))) {
+                    names.push(*name);
+                }
+            }
+            UnstructuredNode::Token(_) => (),
+            UnstructuredNode::Sqrt(inner) | UnstructuredNode::Power(inner) | UnstructuredNode::Parentheses(inner) =>
+                names.extend(called_functions(inner)),
+            UnstructuredNode::Fraction(top, bottom) => {
+                names.extend(called_functions(top));
+                names.extend(called_functions(bottom));
+            }
+            UnstructuredNode::FunctionCall(_, args) => for arg in args {
+                names.extend(called_functions(arg));
+            },
+        }
+    }
+    names
+}
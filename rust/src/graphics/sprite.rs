@@ -350,6 +350,24 @@ impl Sprite {
         self.print_at(x + x_offset, y, s);
     }
 
+    /// Truncates a string with an ellipsis so that it fits in a given width, returning it unchanged
+    /// if it already fits.
+    pub fn fit_text(&self, string: &str, width: u16) -> String {
+        if self.font.string_size(string).0 <= width as i16 {
+            return string.into();
+        }
+
+        let mut chars: Vec<char> = string.chars().collect();
+        while !chars.is_empty() {
+            chars.pop();
+            let candidate: String = chars.iter().chain(['.', '.'].iter()).collect();
+            if self.font.string_size(&candidate).0 <= width as i16 {
+                return candidate;
+            }
+        }
+        String::new()
+    }
+
     /// Wraps a string by breaking it into lines on whitespace, so that it fits in a given width.
     /// Returns a tuple in the form:
     /// 
@@ -0,0 +1,168 @@
+use core::{convert::TryInto, fmt::Display};
+
+use alloc::vec::Vec;
+
+/// The byte which begins every frame.
+pub const FRAME_START: u8 = 0x7E;
+
+/// The number of bytes before a frame's payload: the start byte, kind, and 32-bit length.
+pub const FRAME_HEADER_LENGTH: usize = 6;
+
+/// The number of bytes after a frame's payload, which is its checksum.
+pub const FRAME_CHECKSUM_LENGTH: usize = 2;
+
+/// The longest payload which will be accepted in a received frame. Frames claiming to be longer
+/// are discarded, so a corrupted length can't make us wait for (or allocate) an enormous frame.
+pub const MAX_RECEIVED_PAYLOAD_LENGTH: usize = 0x8000;
+
+/// The value a CRC-16/CCITT-FALSE checksum starts from, before any bytes.
+pub const CRC16_INITIAL: u16 = 0xFFFF;
+
+/// Calculates the CRC-16/CCITT-FALSE checksum of some bytes.
+pub fn crc16(bytes: &[u8]) -> u16 {
+    crc16_update(CRC16_INITIAL, bytes)
+}
+
+/// Continues a CRC-16/CCITT-FALSE checksum with more bytes, so that something can be checksummed
+/// in pieces.
+pub fn crc16_update(mut crc: u16, bytes: &[u8]) -> u16 {
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// A single message sent over the host link.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Frame {
+    pub kind: u8,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    /// Encodes this frame into bytes ready to be sent.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(FRAME_HEADER_LENGTH + self.payload.len() + FRAME_CHECKSUM_LENGTH);
+        bytes.push(FRAME_START);
+        bytes.push(self.kind);
+        bytes.extend_from_slice(&(self.payload.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.payload);
+
+        let checksum = crc16(&bytes[1..]);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+}
+
+/// Writes a frame in pieces, for payloads too large to build in memory all at once. The bytes
+/// written are the same as `Frame::encode` would produce.
+pub struct FrameWriter<W: FnMut(&[u8])> {
+    write: W,
+    crc: u16,
+}
+
+impl<W: FnMut(&[u8])> FrameWriter<W> {
+    /// Writes a frame's header. Exactly `length` bytes of payload must then be written.
+    pub fn begin(kind: u8, length: usize, mut write: W) -> Self {
+        let mut header = [FRAME_START, kind, 0, 0, 0, 0];
+        header[2..].copy_from_slice(&(length as u32).to_le_bytes());
+        write(&header);
+        FrameWriter { write, crc: crc16_update(CRC16_INITIAL, &header[1..]) }
+    }
+
+    /// Writes the next part of the payload.
+    pub fn write_payload(&mut self, bytes: &[u8]) {
+        self.crc = crc16_update(self.crc, bytes);
+        (self.write)(bytes);
+    }
+
+    /// Writes the checksum, completing the frame.
+    pub fn finish(mut self) {
+        (self.write)(&self.crc.to_le_bytes());
+    }
+}
+
+/// A reason why a received frame was discarded.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum FrameError {
+    /// The frame's checksum did not match its contents.
+    Checksum,
+
+    /// The frame's payload was longer than `MAX_RECEIVED_PAYLOAD_LENGTH`.
+    TooLong,
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FrameError::Checksum => write!(f, "Checksum mismatch"),
+            FrameError::TooLong => write!(f, "Frame too long"),
+        }
+    }
+}
+
+/// Collects received bytes, which may arrive in arbitrary pieces, and splits them into frames.
+#[derive(Default)]
+pub struct FrameDecoder {
+    buffer: Vec<u8>,
+}
+
+impl FrameDecoder {
+    /// Adds received bytes to the end of the buffer.
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Discards any partially-received frame.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Takes the next frame from the buffer, or returns `None` if a whole frame hasn't been
+    /// received yet.
+    pub fn next_frame(&mut self) -> Option<Result<Frame, FrameError>> {
+        // Skip any junk before the start of a frame
+        match self.buffer.iter().position(|b| *b == FRAME_START) {
+            Some(start) => { self.buffer.drain(..start); },
+            None => {
+                self.buffer.clear();
+                return None;
+            }
+        }
+
+        if self.buffer.len() < FRAME_HEADER_LENGTH {
+            return None;
+        }
+
+        let length = u32::from_le_bytes(self.buffer[2..FRAME_HEADER_LENGTH].try_into().unwrap()) as usize;
+        if length > MAX_RECEIVED_PAYLOAD_LENGTH {
+            // Drop the start byte, so the search for the next frame begins after it
+            self.buffer.remove(0);
+            return Some(Err(FrameError::TooLong));
+        }
+
+        let total_length = FRAME_HEADER_LENGTH + length + FRAME_CHECKSUM_LENGTH;
+        if self.buffer.len() < total_length {
+            return None;
+        }
+
+        let bytes = self.buffer.drain(..total_length).collect::<Vec<_>>();
+        let checksum_start = total_length - FRAME_CHECKSUM_LENGTH;
+        let checksum = u16::from_le_bytes(bytes[checksum_start..].try_into().unwrap());
+        if crc16(&bytes[1..checksum_start]) != checksum {
+            return Some(Err(FrameError::Checksum));
+        }
+
+        Some(Ok(Frame {
+            kind: bytes[1],
+            payload: bytes[FRAME_HEADER_LENGTH..checksum_start].to_vec(),
+        }))
+    }
+}
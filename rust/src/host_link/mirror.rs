@@ -0,0 +1,95 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{graphics::Sprite, interface::{ApplicationFramework, SerialInterface}, operating_system::OperatingSystem};
+
+use super::{Frame, FrameWriter, RESPONSE_BIT, SCREENSHOT, SCREEN_UPDATE};
+
+/// The most rows sent in one screen update. Larger changes are split over several updates, so that
+/// building a frame never needs a second copy of the whole screen in memory.
+pub const MAX_ROWS_PER_SCREEN_UPDATE: u16 = 32;
+
+/// Hashes each row of a sprite, using FNV-1a. Comparing these against the previous frame's hashes
+/// finds what has changed, without keeping a whole copy of the previous frame in memory.
+pub fn row_hashes(sprite: &Sprite) -> Vec<u32> {
+    sprite.data
+        .chunks(sprite.width as usize)
+        .map(|row| row.iter().fold(0x811C9DC5_u32, |hash, colour| (hash ^ colour.0 as u32).wrapping_mul(0x01000193)))
+        .collect()
+}
+
+/// Finds the bands of consecutive rows whose hashes differ between `hashes` and
+/// `previous_hashes`, as (first row, number of rows). Bands are at most
+/// `MAX_ROWS_PER_SCREEN_UPDATE` rows. If the previous hashes are empty, or from a different sized
+/// sprite, every row is included.
+pub fn changed_bands(hashes: &[u32], previous_hashes: &[u32]) -> Vec<(u16, u16)> {
+    let changed: Vec<bool> = if previous_hashes.len() == hashes.len() {
+        hashes.iter().zip(previous_hashes.iter()).map(|(a, b)| a != b).collect()
+    } else {
+        vec![true; hashes.len()]
+    };
+
+    let mut bands = vec![];
+    let mut y = 0;
+    while y < changed.len() {
+        if !changed[y] {
+            y += 1;
+            continue;
+        }
+
+        let start = y;
+        while y < changed.len() && changed[y] && y - start < MAX_ROWS_PER_SCREEN_UPDATE as usize {
+            y += 1;
+        }
+        bands.push((start as u16, (y - start) as u16));
+    }
+
+    bands
+}
+
+/// Builds a screen update frame containing some rows of a sprite.
+pub fn screen_update(sprite: &Sprite, start: u16, rows: u16) -> Frame {
+    let width = sprite.width as usize;
+    let pixels = &sprite.data[start as usize * width..(start + rows) as usize * width];
+
+    let mut payload = Vec::with_capacity(4 + pixels.len());
+    payload.extend_from_slice(&start.to_be_bytes());
+    payload.extend_from_slice(&rows.to_be_bytes());
+    payload.extend(pixels.iter().map(|colour| colour.0));
+    Frame { kind: SCREEN_UPDATE, payload }
+}
+
+/// Writes the response to a screenshot request: the sprite's width and height, then its pixels.
+/// The pixels are written `MAX_ROWS_PER_SCREEN_UPDATE` rows at a time, since a second copy of
+/// the whole screen may not fit in memory.
+pub fn write_screenshot(sprite: &Sprite, write: impl FnMut(&[u8])) {
+    let mut writer = FrameWriter::begin(SCREENSHOT | RESPONSE_BIT, 4 + sprite.data.len(), write);
+    writer.write_payload(&sprite.width.to_be_bytes());
+    writer.write_payload(&sprite.height.to_be_bytes());
+    for band in sprite.data.chunks(sprite.width as usize * MAX_ROWS_PER_SCREEN_UPDATE as usize) {
+        writer.write_payload(&band.iter().map(|colour| colour.0).collect::<Vec<_>>());
+    }
+    writer.finish();
+}
+
+impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
+    /// If the host has asked for the screen to be mirrored, sends it whatever has changed since
+    /// the last frame. Called whenever the display is drawn.
+    pub fn mirror_frame(&mut self) {
+        if !self.host_link.mirroring {
+            return;
+        }
+
+        // Stop if the host has gone away, so we don't keep hashing every frame for nobody
+        if !self.framework.serial_mut().is_connected() {
+            self.host_link.stop_mirroring();
+            return;
+        }
+
+        let hashes = row_hashes(&self.display_sprite);
+        for (start, rows) in changed_bands(&hashes, &self.host_link.mirror_row_hashes) {
+            let frame = screen_update(&self.display_sprite, start, rows);
+            self.framework.serial_mut().write(&frame.encode());
+        }
+        self.host_link.mirror_row_hashes = hashes;
+    }
+}
@@ -0,0 +1,275 @@
+//! The host link is a protocol for controlling the calculator from a computer, over a serial
+//! connection (a USB CDC-ACM device on real hardware). The `delta-pico-host` tool speaks it.
+//!
+//! # Frames
+//!
+//! All communication is in frames, laid out as:
+//!
+//! | Size        | Contents                                                  |
+//! |-------------|-----------------------------------------------------------|
+//! | 1           | `0x7E`, marking the start of a frame                      |
+//! | 1           | Kind                                                      |
+//! | 4           | Payload length, little-endian                             |
+//! | *length*    | Payload                                                   |
+//! | 2           | CRC-16/CCITT-FALSE of the kind, length and payload, little-endian |
+//!
+//! The computer sends a request frame, and the calculator replies with exactly one response
+//! frame. A successful response has the request's kind with the top bit set (so a response to
+//! `0x03` is `0x83`). A failed request is answered with kind `0xFF`, whose payload is a UTF-8
+//! error message. Frames which fail their checksum are also answered with an error.
+//!
+//! Requests are only handled while the calculator is waiting for a key press, so a response may
+//! be delayed by a long calculation.
+//!
+//! # Requests
+//!
+//! Where a payload contains a *string*, it is a 16-bit big-endian byte length followed by UTF-8.
+//!
+//! | Kind   | Request              | Request payload        | Response payload                     |
+//! |--------|----------------------|------------------------|--------------------------------------|
+//! | `0x01` | Ping                 | -                      | Protocol version byte, then the hardware revision as UTF-8 |
+//! | `0x02` | List files           | -                      | A string per file name               |
+//! | `0x03` | Read file            | Name string            | File contents as UTF-8               |
+//! | `0x04` | Write file           | Name and contents strings | -                                 |
+//! | `0x05` | Delete file          | Name string            | -                                    |
+//! | `0x06` | Read history         | -                      | Serialized calculations              |
+//! | `0x07` | Write history        | Serialized calculations | -                                   |
+//! | `0x08` | Read settings        | -                      | Settings as `key = value` lines      |
+//! | `0x09` | Write settings       | Settings as `key = value` lines | -                           |
+//! | `0x0A` | Screenshot           | -                      | Width and height (16-bit big-endian), then one RGB332 byte per pixel, row by row |
+//! | `0x0B` | Press keys           | A key code per byte (see `ButtonInput::to_code`) | -          |
+//! | `0x0C` | Start mirroring      | -                      | Width and height (16-bit big-endian) |
+//! | `0x0D` | Stop mirroring       | -                      | -                                    |
+//! | `0x0E` | Evaluate             | Expression in linear notation, as UTF-8 | Result as UTF-8, or empty if there is none |
+//!
+//! Notes and programs are both files, told apart by their `.txt` and `.bas` extensions.
+//!
+//! Evaluation uses the linear notation described in `maths::linear`, and the calculator's
+//! settings and user-defined functions. The result may be an error message from the evaluation,
+//! but only a malformed expression fails the request.
+//!
+//! # Screen mirroring
+//!
+//! After a start mirroring request, the calculator sends a screen update frame, of kind `0x40`,
+//! whenever the display is drawn. These are sent without a request, so the host must be ready to
+//! receive them at any time, including while waiting for a response. The first updates after
+//! starting cover the whole screen, and later ones only the rows which have changed.
+//!
+//! The payload of a screen update is the first row's index and the number of rows (both 16-bit
+//! big-endian), followed by one RGB332 byte per pixel for those rows. Rows span the full width.
+//!
+//! Mirroring stops when requested, or when the host closes the serial port. The calculator waits
+//! for each update to be accepted by the host, so the host must keep reading while mirroring.
+
+use alloc::{format, string::String, vec, vec::Vec};
+use rbop::node::unstructured::UnstructuredNodeRoot;
+
+use crate::{filesystem::{deserialize_calculations, deserialize_string, serialize_calculations, serialize_string, File}, interface::{ApplicationFramework, ButtonInput, SerialInterface}, maths::LinearNotation, operating_system::OperatingSystem};
+
+mod frame;
+pub use frame::*;
+
+mod mirror;
+pub use mirror::*;
+
+mod test;
+pub use test::test;
+
+/// Incremented whenever the protocol changes incompatibly.
+pub const PROTOCOL_VERSION: u8 = 1;
+
+/// Set on the kind of a successful response.
+pub const RESPONSE_BIT: u8 = 0x80;
+
+/// The kind of a response to a request which failed.
+pub const ERROR_KIND: u8 = 0xFF;
+
+pub const PING: u8 = 0x01;
+pub const LIST_FILES: u8 = 0x02;
+pub const READ_FILE: u8 = 0x03;
+pub const WRITE_FILE: u8 = 0x04;
+pub const DELETE_FILE: u8 = 0x05;
+pub const READ_HISTORY: u8 = 0x06;
+pub const WRITE_HISTORY: u8 = 0x07;
+pub const READ_SETTINGS: u8 = 0x08;
+pub const WRITE_SETTINGS: u8 = 0x09;
+pub const SCREENSHOT: u8 = 0x0A;
+pub const PRESS_KEYS: u8 = 0x0B;
+pub const START_MIRRORING: u8 = 0x0C;
+pub const STOP_MIRRORING: u8 = 0x0D;
+pub const EVALUATE: u8 = 0x0E;
+
+/// The kind of the unsolicited frames sent while mirroring the screen.
+pub const SCREEN_UPDATE: u8 = 0x40;
+
+/// The OS' state for the host link.
+#[derive(Default)]
+pub struct HostLink {
+    pub decoder: FrameDecoder,
+
+    /// Whether the host has asked for the screen to be mirrored.
+    pub mirroring: bool,
+
+    /// The hashes of each row of the display at the last mirrored frame, from `row_hashes`. Empty
+    /// if the next frame should be sent in full.
+    pub mirror_row_hashes: Vec<u32>,
+}
+
+impl HostLink {
+    pub fn stop_mirroring(&mut self) {
+        self.mirroring = false;
+        self.mirror_row_hashes.clear();
+    }
+}
+
+impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
+    /// Reads anything received over the serial connection, and responds to any complete requests.
+    /// This is called repeatedly while waiting for input.
+    pub fn service_host_link(&mut self) {
+        if !self.framework.serial_mut().is_connected() {
+            self.host_link.decoder.clear();
+            self.host_link.stop_mirroring();
+            return;
+        }
+
+        let mut buffer = [0; 64];
+        loop {
+            let read = self.framework.serial_mut().read(&mut buffer);
+            if read == 0 {
+                break;
+            }
+            self.host_link.decoder.push(&buffer[..read]);
+        }
+
+        while let Some(frame) = self.host_link.decoder.next_frame() {
+            let response = match frame {
+                // A screenshot is too large to build as one frame, so it's written out as it goes
+                Ok(request) if request.kind == SCREENSHOT => {
+                    let serial = self.framework.serial_mut();
+                    write_screenshot(&self.display_sprite, |bytes| serial.write(bytes));
+                    continue;
+                }
+                Ok(request) => self.handle_host_request(&request),
+                Err(error) => Frame { kind: ERROR_KIND, payload: format!("{}", error).into_bytes() },
+            };
+            self.framework.serial_mut().write(&response.encode());
+        }
+    }
+
+    /// Performs a request received over the host link, and returns the response to send back.
+    /// Screenshots aren't handled here, but by `write_screenshot`.
+    pub fn handle_host_request(&mut self, request: &Frame) -> Frame {
+        match self.perform_host_request(request.kind, &request.payload) {
+            Ok(payload) => Frame { kind: request.kind | RESPONSE_BIT, payload },
+            Err(message) => Frame { kind: ERROR_KIND, payload: message.into_bytes() },
+        }
+    }
+
+    fn perform_host_request(&mut self, kind: u8, payload: &[u8]) -> Result<Vec<u8>, String> {
+        const INVALID: &str = "Invalid request payload";
+        const STORAGE_ERROR: &str = "Storage error";
+
+        let mut reader = payload.iter().copied();
+
+        match kind {
+            PING => {
+                let mut response = vec![PROTOCOL_VERSION];
+                response.extend_from_slice(self.framework.hardware_revision().as_bytes());
+                Ok(response)
+            }
+
+            LIST_FILES => {
+                let mut response = vec![];
+                for (_, name) in self.filesystem.files.list_files() {
+                    serialize_string(&name, &mut response);
+                }
+                Ok(response)
+            }
+
+            READ_FILE => {
+                let name = deserialize_string(&mut reader).ok_or(INVALID)?;
+                let idx = self.filesystem.files.find_file(&name).ok_or("No such file")?;
+                let file = self.filesystem.files.read_file(idx).ok_or(STORAGE_ERROR)?;
+                Ok(file.contents.into_bytes())
+            }
+
+            WRITE_FILE => {
+                let name = deserialize_string(&mut reader).ok_or(INVALID)?;
+                let contents = deserialize_string(&mut reader).ok_or(INVALID)?;
+                if name.is_empty() {
+                    return Err("File name cannot be empty".into());
+                }
+                self.filesystem.files.save_file(&File { name, contents })
+                    .ok_or("Could not save file, storage may be full")?;
+                Ok(vec![])
+            }
+
+            DELETE_FILE => {
+                let name = deserialize_string(&mut reader).ok_or(INVALID)?;
+                let idx = self.filesystem.files.find_file(&name).ok_or("No such file")?;
+                self.filesystem.files.delete_file(idx).ok_or(STORAGE_ERROR)?;
+                Ok(vec![])
+            }
+
+            READ_HISTORY => {
+                let calculations = self.filesystem.calculations.read_calculations().ok_or(STORAGE_ERROR)?;
+                Ok(serialize_calculations(&calculations))
+            }
+
+            WRITE_HISTORY => {
+                let calculations = deserialize_calculations(payload).ok_or(INVALID)?;
+                self.filesystem.calculations.replace_calculations(&calculations)
+                    .ok_or("Could not save history, storage may be full")?;
+                Ok(vec![])
+            }
+
+            READ_SETTINGS => Ok(self.filesystem.settings.values.to_text().into_bytes()),
+
+            WRITE_SETTINGS => {
+                let values = core::str::from_utf8(payload).ok()
+                    .and_then(|text| self.filesystem.settings.values.with_text(text))
+                    .ok_or(INVALID)?;
+                self.filesystem.settings.values = values;
+                self.filesystem.settings.save().ok_or(STORAGE_ERROR)?;
+                Ok(vec![])
+            }
+
+            PRESS_KEYS => {
+                // Check every code first, so that an invalid request doesn't press some keys
+                let buttons = payload.iter()
+                    .map(|code| ButtonInput::from_code(*code))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or("Unknown key code")?;
+                for button in buttons {
+                    self.inject_press(button);
+                }
+                Ok(vec![])
+            }
+
+            START_MIRRORING => {
+                // Clearing the hashes makes the next update cover the whole screen
+                self.host_link.mirroring = true;
+                self.host_link.mirror_row_hashes.clear();
+
+                let mut response = vec![];
+                response.extend_from_slice(&self.display_sprite.width.to_be_bytes());
+                response.extend_from_slice(&self.display_sprite.height.to_be_bytes());
+                Ok(response)
+            }
+
+            STOP_MIRRORING => {
+                self.host_link.stop_mirroring();
+                Ok(vec![])
+            }
+
+            EVALUATE => {
+                let text = core::str::from_utf8(payload).map_err(|_| INVALID)?;
+                let root = UnstructuredNodeRoot::from_linear(text).map_err(|error| format!("{}", error))?;
+                let result = self.filesystem.evaluate(&root);
+                Ok(result.to_text().unwrap_or_default().into_bytes())
+            }
+
+            _ => Err("Unknown request".into()),
+        }
+    }
+}
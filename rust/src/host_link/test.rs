@@ -0,0 +1,143 @@
+use alloc::{vec, vec::Vec};
+
+use crate::{interface::{ApplicationFramework, ButtonInput, Colour}, graphics::Sprite, operating_system::{OperatingSystem, OSInput}, filesystem::{serialize_string, File}};
+
+use super::{changed_bands, row_hashes, screen_update, write_screenshot, MAX_ROWS_PER_SCREEN_UPDATE, START_MIRRORING, STOP_MIRRORING, SCREEN_UPDATE, crc16, Frame, FrameDecoder, FrameError, ERROR_KIND, PING, RESPONSE_BIT, LIST_FILES, WRITE_FILE, READ_FILE, DELETE_FILE, READ_SETTINGS, WRITE_SETTINGS, SCREENSHOT, PRESS_KEYS, EVALUATE, PROTOCOL_VERSION};
+
+pub fn test<F: ApplicationFramework>(os: &mut OperatingSystem<F>) {
+    // Standard check value for CRC-16/CCITT-FALSE
+    assert_eq!(crc16(b"123456789"), 0x29B1);
+
+    // Frames survive being split up and preceded by junk
+    let frame = Frame { kind: 0x12, payload: vec![1, 2, 3, 0x7E] };
+    let encoded = frame.encode();
+    let mut decoder = FrameDecoder::default();
+    decoder.push(&[0x00, 0x55]);
+    decoder.push(&encoded[..4]);
+    assert_eq!(decoder.next_frame(), None);
+    decoder.push(&encoded[4..]);
+    assert_eq!(decoder.next_frame(), Some(Ok(frame.clone())));
+    assert_eq!(decoder.next_frame(), None);
+
+    // Corruption is detected
+    let mut corrupted = encoded.clone();
+    corrupted[7] ^= 0xFF;
+    decoder.push(&corrupted);
+    assert_eq!(decoder.next_frame(), Some(Err(FrameError::Checksum)));
+
+    // Frames with absurd lengths are dropped
+    decoder.push(&[0x7E, 0x01, 0xFF, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(decoder.next_frame(), Some(Err(FrameError::TooLong)));
+    decoder.push(&encoded);
+    assert_eq!(decoder.next_frame(), Some(Ok(frame)));
+
+    let request = |os: &mut OperatingSystem<F>, kind: u8, payload: Vec<u8>| {
+        let response = os.handle_host_request(&Frame { kind, payload });
+        assert_eq!(response.kind, kind | RESPONSE_BIT, "request {:x} failed", kind);
+        response.payload
+    };
+
+    // Ping
+    let response = request(os, PING, vec![]);
+    assert_eq!(response[0], PROTOCOL_VERSION);
+
+    // Files
+    os.filesystem.files.table.clear(false);
+    let mut payload = vec![];
+    serialize_string("hello.txt", &mut payload);
+    serialize_string("Hello, world!", &mut payload);
+    request(os, WRITE_FILE, payload);
+    assert_eq!(
+        os.filesystem.files.read_file(os.filesystem.files.find_file("hello.txt").unwrap()),
+        Some(File { name: "hello.txt".into(), contents: "Hello, world!".into() }),
+    );
+
+    let mut expected_list = vec![];
+    serialize_string("hello.txt", &mut expected_list);
+    assert_eq!(request(os, LIST_FILES, vec![]), expected_list);
+
+    let mut name = vec![];
+    serialize_string("hello.txt", &mut name);
+    assert_eq!(request(os, READ_FILE, name.clone()), b"Hello, world!");
+    request(os, DELETE_FILE, name.clone());
+    assert_eq!(os.filesystem.files.find_file("hello.txt"), None);
+
+    // Errors are reported rather than panicking
+    let response = os.handle_host_request(&Frame { kind: READ_FILE, payload: name });
+    assert_eq!(response.kind, ERROR_KIND);
+    assert_eq!(response.payload, b"No such file");
+    let response = os.handle_host_request(&Frame { kind: 0x70, payload: vec![] });
+    assert_eq!(response.kind, ERROR_KIND);
+
+    // Settings
+    let original_settings = os.filesystem.settings.values.clone();
+    let text = request(os, READ_SETTINGS, vec![]);
+    os.filesystem.settings.values.show_frame_time = !original_settings.show_frame_time;
+    request(os, WRITE_SETTINGS, text);
+    assert_eq!(os.filesystem.settings.values, original_settings);
+
+    // Screenshot, which is written in pieces but comes out as one frame
+    let mut written = vec![];
+    write_screenshot(&os.display_sprite, |bytes| written.extend_from_slice(bytes));
+    let (width, height) = (os.display_sprite.width, os.display_sprite.height);
+    let mut payload = vec![];
+    payload.extend_from_slice(&width.to_be_bytes());
+    payload.extend_from_slice(&height.to_be_bytes());
+    payload.extend(os.display_sprite.data.iter().map(|colour| colour.0));
+    assert_eq!(written, Frame { kind: SCREENSHOT | RESPONSE_BIT, payload }.encode());
+
+    // Evaluation, where maths errors are results but malformed expressions fail the request
+    assert_eq!(request(os, EVALUATE, b"1/2+gcd(12, 18)".to_vec()), b"13/2");
+    assert!(!request(os, EVALUATE, b"1/0".to_vec()).is_empty());
+    assert_eq!(request(os, EVALUATE, vec![]), b"");
+    let response = os.handle_host_request(&Frame { kind: EVALUATE, payload: b"1+)".to_vec() });
+    assert_eq!(response.kind, ERROR_KIND);
+    assert_eq!(response.payload, b"Unexpected ')' at column 3");
+
+    // Key presses
+    let codes = vec![
+        ButtonInput::Digit(1).to_code().unwrap(),
+        ButtonInput::Exe.to_code().unwrap(),
+    ];
+    request(os, PRESS_KEYS, codes);
+    assert_eq!(os.virtual_input_queue, vec![
+        Some(OSInput::Button(ButtonInput::Digit(1))), None,
+        Some(OSInput::Button(ButtonInput::Exe)), None,
+    ]);
+    os.virtual_input_queue.clear();
+
+    // An unknown code rejects the whole request
+    let response = os.handle_host_request(&Frame { kind: PRESS_KEYS, payload: vec![2, 0xEE] });
+    assert_eq!(response.kind, ERROR_KIND);
+    assert!(os.virtual_input_queue.is_empty());
+
+    // Mirroring sends everything at first, in bounded bands
+    let mut sprite = Sprite::new(10, 40);
+    let hashes = row_hashes(&sprite);
+    assert_eq!(changed_bands(&hashes, &[]), [(0, MAX_ROWS_PER_SCREEN_UPDATE), (MAX_ROWS_PER_SCREEN_UPDATE, 40 - MAX_ROWS_PER_SCREEN_UPDATE)]);
+    assert!(changed_bands(&hashes, &hashes).is_empty());
+
+    // Then only the rows which changed
+    *sprite.pixel(3, 5) = Colour::WHITE;
+    *sprite.pixel(0, 20) = Colour::WHITE;
+    *sprite.pixel(9, 21) = Colour::RED;
+    let new_hashes = row_hashes(&sprite);
+    assert_eq!(changed_bands(&new_hashes, &hashes), [(5, 1), (20, 2)]);
+
+    let update = screen_update(&sprite, 20, 2);
+    assert_eq!(update.kind, SCREEN_UPDATE);
+    assert_eq!(&update.payload[..4], &[0, 20, 0, 2]);
+    assert_eq!(update.payload.len(), 4 + 10 * 2);
+    assert_eq!(update.payload[4], Colour::WHITE.0);
+    assert_eq!(update.payload[4 + 19], Colour::RED.0);
+
+    // Starting mirroring resets what the host is assumed to have
+    os.host_link.mirror_row_hashes = vec![1, 2, 3];
+    let response = request(os, START_MIRRORING, vec![]);
+    assert_eq!(&response[..2], &width.to_be_bytes());
+    assert_eq!(&response[2..4], &height.to_be_bytes());
+    assert!(os.host_link.mirroring);
+    assert!(os.host_link.mirror_row_hashes.is_empty());
+    request(os, STOP_MIRRORING, vec![]);
+    assert!(!os.host_link.mirroring);
+}
@@ -28,6 +28,83 @@ pub enum ButtonInput {
     Sqrt,
 }
 
+impl ButtonInput {
+    /// Whether this button fires repeatedly while held, if key repeat is enabled. Other buttons
+    /// fire a long press instead.
+    pub fn auto_repeats(self) -> bool {
+        matches!(
+            self,
+            ButtonInput::MoveLeft | ButtonInput::MoveRight | ButtonInput::MoveUp | ButtonInput::MoveDown
+            | ButtonInput::Delete
+        )
+    }
+
+    /// A single-byte code identifying this button, used by the host link. Digits are their ASCII
+    /// characters, and `None` has no code.
+    pub fn to_code(self) -> Option<u8> {
+        Some(match self {
+            ButtonInput::None => return None,
+
+            ButtonInput::Menu => 1,
+            ButtonInput::Exe => 2,
+            ButtonInput::Shift => 3,
+            ButtonInput::List => 4,
+            ButtonInput::Text => 5,
+
+            ButtonInput::MoveLeft => 6,
+            ButtonInput::MoveRight => 7,
+            ButtonInput::MoveUp => 8,
+            ButtonInput::MoveDown => 9,
+            ButtonInput::Delete => 10,
+            ButtonInput::Clear => 11,
+
+            ButtonInput::Point => 12,
+            ButtonInput::Parentheses => 13,
+
+            ButtonInput::Add => 14,
+            ButtonInput::Subtract => 15,
+            ButtonInput::Multiply => 16,
+            ButtonInput::Fraction => 17,
+            ButtonInput::Power => 18,
+            ButtonInput::Sqrt => 19,
+
+            ButtonInput::Digit(d) => b'0' + d,
+        })
+    }
+
+    /// The inverse of `to_code`.
+    pub fn from_code(code: u8) -> Option<Self> {
+        Some(match code {
+            1 => ButtonInput::Menu,
+            2 => ButtonInput::Exe,
+            3 => ButtonInput::Shift,
+            4 => ButtonInput::List,
+            5 => ButtonInput::Text,
+
+            6 => ButtonInput::MoveLeft,
+            7 => ButtonInput::MoveRight,
+            8 => ButtonInput::MoveUp,
+            9 => ButtonInput::MoveDown,
+            10 => ButtonInput::Delete,
+            11 => ButtonInput::Clear,
+
+            12 => ButtonInput::Point,
+            13 => ButtonInput::Parentheses,
+
+            14 => ButtonInput::Add,
+            15 => ButtonInput::Subtract,
+            16 => ButtonInput::Multiply,
+            17 => ButtonInput::Fraction,
+            18 => ButtonInput::Power,
+            19 => ButtonInput::Sqrt,
+
+            b'0'..=b'9' => ButtonInput::Digit(code - b'0'),
+
+            _ => return None,
+        })
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum ButtonEvent {
     Press(ButtonInput),
@@ -35,6 +112,10 @@ pub enum ButtonEvent {
 }
 
 pub trait ButtonsInterface {
+    /// Waits until a button is pressed or released, and returns the event.
     fn wait_event(&mut self) -> ButtonEvent;
+
+    /// Returns the next button event if one has happened, or `None` otherwise. This must return
+    /// immediately rather than waiting for an event.
     fn poll_event(&mut self) -> Option<ButtonEvent>;
 }
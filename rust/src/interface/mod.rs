@@ -9,13 +9,18 @@ pub use buttons::*;
 mod storage;
 pub use storage::*;
 
-// mod usb_mass_storage;
-// pub use usb_mass_storage::*;
+mod usb_mass_storage;
+pub use usb_mass_storage::*;
+
+mod serial;
+pub use serial::*;
 
 pub trait ApplicationFramework {
     type DisplayI : DisplayInterface;
     type ButtonsI : ButtonsInterface;
     type StorageI : StorageInterface;
+    type UsbMassStorageI : UsbMassStorageInterface;
+    type SerialI : SerialInterface;
 
     fn display(&self) -> &Self::DisplayI;
     fn display_mut(&mut self) -> &mut Self::DisplayI;
@@ -26,6 +31,12 @@ pub trait ApplicationFramework {
     fn storage(&self) -> &Self::StorageI;
     fn storage_mut(&mut self) -> &mut Self::StorageI;
 
+    fn usb_mass_storage(&self) -> &Self::UsbMassStorageI;
+    fn usb_mass_storage_mut(&mut self) -> &mut Self::UsbMassStorageI;
+
+    fn serial(&self) -> &Self::SerialI;
+    fn serial_mut(&mut self) -> &mut Self::SerialI;
+
     fn hardware_revision(&self) -> String;
     fn reboot_into_bootloader(&mut self) -> !;
 
@@ -38,6 +49,10 @@ pub trait ApplicationFramework {
     /// Print a debug message. Currently only implemented on the simulator.
     fn debug(&self, message: &str);
 
+    /// Called while waiting for input with nothing else to do, so that the hardware can sleep
+    /// until something happens, such as a button event or USB activity.
+    fn idle(&mut self) {}
+
     /// Called once on boot to determine whether to run the test suite.
     fn should_run_tests(&mut self) -> bool;
 
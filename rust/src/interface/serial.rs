@@ -0,0 +1,15 @@
+/// A serial connection to a computer, such as a USB CDC-ACM device. This carries the host link
+/// protocol.
+pub trait SerialInterface {
+    /// Whether a computer currently has the serial port open.
+    #[allow(clippy::wrong_self_convention)] // &mut self may be required to query the USB stack
+    fn is_connected(&mut self) -> bool;
+
+    /// Reads any bytes which have been received into `buffer`, without blocking, and returns how
+    /// many were read.
+    fn read(&mut self, buffer: &mut [u8]) -> usize;
+
+    /// Writes bytes, blocking until they've all been sent. If the computer disconnects partway
+    /// through, the remaining bytes are discarded.
+    fn write(&mut self, bytes: &[u8]);
+}
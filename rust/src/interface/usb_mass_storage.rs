@@ -1,9 +1,25 @@
-#[repr(C)]
-pub struct UsbMassStorageInterface {
-    pub block_num: usize,
-    pub block_size: usize,
-    pub fat12_filesystem: *mut u8,
-
-    pub active: bool,
-    pub begin: extern "C" fn() -> bool,
+use alloc::vec::Vec;
+
+/// The size of each block of a medium presented over USB mass storage.
+pub const USB_MASS_STORAGE_BLOCK_SIZE: usize = 512;
+
+/// Presents a disk image to a connected computer as the medium of a USB mass storage device, much
+/// like a card reader. While no medium is inserted, the device appears empty.
+///
+/// The framework is responsible for servicing USB requests while a medium is inserted (for example,
+/// from an interrupt), so the OS is free to block waiting for input.
+pub trait UsbMassStorageInterface {
+    /// Inserts a medium with the given contents, which must be a whole number of
+    /// `USB_MASS_STORAGE_BLOCK_SIZE` blocks. Returns `false` if this device does not support USB
+    /// mass storage.
+    fn insert_medium(&mut self, image: Vec<u8>) -> bool;
+
+    /// Whether the computer has ejected the medium since it was inserted. Once ejected, the
+    /// computer has finished writing to it.
+    #[allow(clippy::wrong_self_convention)] // &mut self may be required to query the USB stack
+    fn is_ejected(&mut self) -> bool;
+
+    /// Removes the medium, returning its contents with any changes made by the computer, or `None`
+    /// if no medium was inserted.
+    fn remove_medium(&mut self) -> Option<Vec<u8>>;
 }
@@ -21,6 +21,9 @@ pub mod timer;
 pub mod multi_tap;
 pub mod tests;
 pub mod graphics;
+pub mod maths;
+pub mod scripting;
+pub mod host_link;
 
 use interface::{ApplicationFramework, DisplayInterface, ButtonInput, StorageInterface};
 use operating_system::OperatingSystemPointer;
@@ -40,8 +43,15 @@ pub extern "C" fn delta_pico_main<F: ApplicationFramework + 'static>(framework:
 
     os.application_list.add::<applications::calculator::CalculatorApplication<F>>();
     os.application_list.add::<applications::graph::GraphApplication<F>>();
+    os.application_list.add::<applications::unit_converter::UnitConverterApplication<F>>();
+    os.application_list.add::<applications::base_n::BaseNApplication<F>>();
+    os.application_list.add::<applications::matrix::MatrixApplication<F>>();
+    os.application_list.add::<applications::statistics::StatisticsApplication<F>>();
+    os.application_list.add::<applications::equation::EquationApplication<F>>();
+    os.application_list.add::<applications::programs::ProgramsApplication<F>>();
+    os.application_list.add::<applications::notes::NotesApplication<F>>();
+    os.application_list.add::<applications::files::FilesApplication<F>>();
     os.application_list.add::<applications::numbers_game::NumbersGame<F>>();
-    // os().application_list.add::<applications::files::FilesApplication>();
     os.application_list.add::<applications::about::AboutApplication<F>>();
     os.application_list.add::<applications::settings::SettingsApplication<F>>();
     os.application_list.add::<applications::storage::StorageApplication<F>>();
@@ -56,15 +66,6 @@ pub extern "C" fn delta_pico_main<F: ApplicationFramework + 'static>(framework:
     os.display_sprite.draw_bitmap(60, 80, "splash");
     os.draw();
 
-    // Temporary
-    // framework().storage.with_priority(|| {
-    //     // We use `leak` to ensure `fat` doesn't get dropped at the end of this `with_priority` call
-    //     let fat = os().filesystem.fat.read_all().unwrap();
-    //     framework().usb_mass_storage.fat12_filesystem = fat.leak().as_mut_ptr();    
-        
-    //     (framework().usb_mass_storage.begin)();
-    // });
-
     // Set up menu
     os.menu = Some(applications::menu::MenuApplication::new(os_ptr));
 
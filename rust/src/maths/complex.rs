@@ -0,0 +1,478 @@
+use core::fmt::Display;
+use alloc::{boxed::Box, vec, vec::Vec};
+use rbop::{Number, StructuredNode, UnstructuredNode, UnstructuredNodeList, Token, node::{structured::{EvaluationSettings, AngleUnit}, function::Function}, serialize::Serializable};
+use rust_decimal::{Decimal, MathematicalOps, prelude::{Zero, One, Signed, ToPrimitive}};
+
+/// A complex number, with real and imaginary parts.
+///
+/// Unlike rbop's `Number`, the parts are always decimals. Complex results are rarely exact, and
+/// `Decimal` provides checked versions of the operations we need.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Complex {
+    pub re: Decimal,
+    pub im: Decimal,
+}
+
+/// How complex numbers are displayed.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ComplexFormat {
+    /// `a + bi`
+    Rectangular,
+
+    /// `r e^(θi)`, with θ in radians
+    Polar,
+}
+
+impl Default for ComplexFormat {
+    fn default() -> Self { ComplexFormat::Rectangular }
+}
+
+impl Display for ComplexFormat {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ComplexFormat::Rectangular => write!(f, "Rectangular"),
+            ComplexFormat::Polar => write!(f, "Polar"),
+        }
+    }
+}
+
+/// An error encountered while evaluating a complex expression.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum ComplexError {
+    Overflow,
+    DivisionByZero,
+
+    /// A variable other than `i` was used.
+    MissingVariable(char),
+
+    /// A function was called with complex arguments, but only supports real ones.
+    UnsupportedFunction,
+}
+
+impl Display for ComplexError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ComplexError::Overflow => write!(f, "Overflow"),
+            ComplexError::DivisionByZero => write!(f, "Division by zero"),
+            ComplexError::MissingVariable(c) => write!(f, "No value for variable {}", c),
+            ComplexError::UnsupportedFunction => write!(f, "Function does not support complex arguments"),
+        }
+    }
+}
+
+type ComplexResult = Result<Complex, ComplexError>;
+
+impl Display for Complex {
+    /// Formats this number as plain text in rectangular form, such as `3-2i`, after rounding it.
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let rounded = self.rounded();
+        if rounded.im.is_zero() {
+            return write!(f, "{}", rounded.re);
+        }
+
+        if !rounded.re.is_zero() {
+            write!(f, "{}", rounded.re)?;
+            if rounded.im.is_sign_positive() {
+                write!(f, "+")?;
+            }
+        }
+        if rounded.im.is_negative() {
+            write!(f, "-")?;
+        }
+        if !rounded.im.abs().is_one() {
+            write!(f, "{}", rounded.im.abs())?;
+        }
+        write!(f, "i")
+    }
+}
+
+impl Complex {
+    pub const ZERO: Complex = Complex { re: Decimal::ZERO, im: Decimal::ZERO };
+    pub const I: Complex = Complex { re: Decimal::ZERO, im: Decimal::ONE };
+
+    /// The number of decimal places which results are rounded to, to hide the error accumulated by
+    /// series approximations, such as `e^(pi i)` having a tiny imaginary part.
+    const DISPLAY_DP: u32 = 15;
+
+    pub fn new(re: Decimal, im: Decimal) -> Self {
+        Self { re, im }
+    }
+
+    pub fn from_real(re: Decimal) -> Self {
+        Self { re, im: Decimal::ZERO }
+    }
+
+    pub fn is_real(&self) -> bool {
+        self.im.is_zero()
+    }
+
+    /// Rounds both parts to a sensible number of decimal places for display.
+    pub fn rounded(&self) -> Self {
+        Self {
+            re: self.re.round_dp(Self::DISPLAY_DP).normalize(),
+            im: self.im.round_dp(Self::DISPLAY_DP).normalize(),
+        }
+    }
+
+    pub fn checked_add(&self, other: &Complex) -> ComplexResult {
+        Ok(Self {
+            re: self.re.checked_add(other.re).ok_or(ComplexError::Overflow)?,
+            im: self.im.checked_add(other.im).ok_or(ComplexError::Overflow)?,
+        })
+    }
+
+    pub fn checked_sub(&self, other: &Complex) -> ComplexResult {
+        Ok(Self {
+            re: self.re.checked_sub(other.re).ok_or(ComplexError::Overflow)?,
+            im: self.im.checked_sub(other.im).ok_or(ComplexError::Overflow)?,
+        })
+    }
+
+    pub fn checked_mul(&self, other: &Complex) -> ComplexResult {
+        // (a + bi)(c + di) = (ac - bd) + (ad + bc)i
+        Ok(Self {
+            re: mul(self.re, other.re)?.checked_sub(mul(self.im, other.im)?).ok_or(ComplexError::Overflow)?,
+            im: mul(self.re, other.im)?.checked_add(mul(self.im, other.re)?).ok_or(ComplexError::Overflow)?,
+        })
+    }
+
+    pub fn checked_div(&self, other: &Complex) -> ComplexResult {
+        // (a + bi)/(c + di) = ((ac + bd) + (bc - ad)i) / (c^2 + d^2)
+        let denominator = other.norm_sqr()?;
+        if denominator.is_zero() {
+            return Err(ComplexError::DivisionByZero);
+        }
+
+        let re = mul(self.re, other.re)?.checked_add(mul(self.im, other.im)?).ok_or(ComplexError::Overflow)?;
+        let im = mul(self.im, other.re)?.checked_sub(mul(self.re, other.im)?).ok_or(ComplexError::Overflow)?;
+        Ok(Self {
+            re: re.checked_div(denominator).ok_or(ComplexError::Overflow)?,
+            im: im.checked_div(denominator).ok_or(ComplexError::Overflow)?,
+        })
+    }
+
+    pub fn neg(&self) -> Self {
+        Self { re: -self.re, im: -self.im }
+    }
+
+    /// The square of the modulus, `a^2 + b^2`.
+    pub fn norm_sqr(&self) -> Result<Decimal, ComplexError> {
+        mul(self.re, self.re)?.checked_add(mul(self.im, self.im)?).ok_or(ComplexError::Overflow)
+    }
+
+    /// The modulus, `|z|`.
+    pub fn abs(&self) -> Result<Decimal, ComplexError> {
+        self.norm_sqr()?.sqrt().ok_or(ComplexError::Overflow)
+    }
+
+    /// The argument, in radians, in the range (-pi, pi].
+    pub fn arg(&self) -> Decimal {
+        atan2(self.im, self.re)
+    }
+
+    /// Builds a complex number from polar coordinates.
+    pub fn from_polar(r: Decimal, theta: Decimal) -> ComplexResult {
+        let (sin, cos) = sin_cos(theta)?;
+        Ok(Self {
+            re: mul(r, cos)?,
+            im: mul(r, sin)?,
+        })
+    }
+
+    pub fn exp(&self) -> ComplexResult {
+        let r = self.re.checked_exp().ok_or(ComplexError::Overflow)?;
+        Self::from_polar(r, self.im)
+    }
+
+    /// The principal value of the natural logarithm.
+    pub fn ln(&self) -> ComplexResult {
+        let abs = self.abs()?;
+        if abs.is_zero() {
+            return Err(ComplexError::DivisionByZero);
+        }
+        Ok(Self {
+            re: abs.checked_ln().ok_or(ComplexError::Overflow)?,
+            im: self.arg(),
+        })
+    }
+
+    /// The principal square root.
+    pub fn sqrt(&self) -> ComplexResult {
+        // sqrt(a + bi) = sqrt((|z| + a) / 2) + sign(b) sqrt((|z| - a) / 2) i
+        let abs = self.abs()?;
+        let two = Decimal::TWO;
+        let re = ((abs + self.re) / two).sqrt().ok_or(ComplexError::Overflow)?;
+        let mut im = ((abs - self.re) / two).sqrt().ok_or(ComplexError::Overflow)?;
+        if self.im.is_negative() {
+            im = -im;
+        }
+        Ok(Self { re, im })
+    }
+
+    /// Raises this number to a power. Integer powers are computed by repeated multiplication so
+    /// that results such as `i^2 = -1` are exact.
+    pub fn pow(&self, exponent: &Complex) -> ComplexResult {
+        if exponent.is_real() && exponent.re.fract().is_zero() && exponent.re.abs() <= Decimal::from(1024) {
+            let mut n = exponent.re.abs().to_u64().unwrap();
+            let mut base = *self;
+            let mut result = Complex::from_real(Decimal::ONE);
+            while n > 0 {
+                if n & 1 == 1 {
+                    result = result.checked_mul(&base)?;
+                }
+                base = base.checked_mul(&base)?;
+                n >>= 1;
+            }
+
+            return if exponent.re.is_negative() {
+                Complex::from_real(Decimal::ONE).checked_div(&result)
+            } else {
+                Ok(result)
+            };
+        }
+
+        if *self == Complex::ZERO {
+            return Ok(Complex::ZERO);
+        }
+        self.ln()?.checked_mul(exponent)?.exp()
+    }
+
+    pub fn sin(&self) -> ComplexResult {
+        // sin(a + bi) = sin(a)cosh(b) + cos(a)sinh(b)i
+        let (sin, cos) = sin_cos(self.re)?;
+        let (sinh, cosh) = sinh_cosh(self.im)?;
+        Ok(Self {
+            re: mul(sin, cosh)?,
+            im: mul(cos, sinh)?,
+        })
+    }
+
+    pub fn cos(&self) -> ComplexResult {
+        // cos(a + bi) = cos(a)cosh(b) - sin(a)sinh(b)i
+        let (sin, cos) = sin_cos(self.re)?;
+        let (sinh, cosh) = sinh_cosh(self.im)?;
+        Ok(Self {
+            re: mul(cos, cosh)?,
+            im: -mul(sin, sinh)?,
+        })
+    }
+
+    /// Builds a structured node which renders this number in the given format.
+    pub fn to_display_node(&self, format: ComplexFormat) -> StructuredNode {
+        let number = |d: Decimal| Box::new(StructuredNode::Number(Number::from(d)));
+        let i = || Box::new(StructuredNode::Variable('i'));
+
+        match format {
+            ComplexFormat::Rectangular => {
+                let rounded = self.rounded();
+                let imaginary = if rounded.im.abs().is_one() {
+                    i()
+                } else {
+                    Box::new(StructuredNode::Multiply(number(rounded.im.abs()), i()))
+                };
+
+                match (rounded.re.is_zero(), rounded.im.is_negative()) {
+                    (true, false) => *imaginary,
+
+                    // There's no negation node, so the coefficient carries the sign, even if it's
+                    // -1
+                    (true, true) => StructuredNode::Multiply(number(rounded.im), i()),
+                    (false, false) => StructuredNode::Add(number(rounded.re), imaginary),
+                    (false, true) => StructuredNode::Subtract(number(rounded.re), imaginary),
+                }
+            }
+
+            ComplexFormat::Polar => {
+                let r = self.abs().unwrap_or(Decimal::ZERO).round_dp(Self::DISPLAY_DP).normalize();
+                let theta = self.arg().round_dp(Self::DISPLAY_DP).normalize();
+                StructuredNode::Multiply(
+                    number(r),
+                    Box::new(StructuredNode::Power(
+                        Box::new(StructuredNode::Variable('e')),
+                        Box::new(StructuredNode::Multiply(number(theta), i())),
+                    )),
+                )
+            }
+        }
+    }
+}
+
+impl Serializable for Complex {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Number::from(self.re).serialize();
+        bytes.append(&mut Number::from(self.im).serialize());
+        bytes
+    }
+
+    fn deserialize(bytes: &mut dyn Iterator<Item = u8>) -> Option<Self> {
+        Some(Self {
+            re: Number::deserialize(bytes)?.to_decimal(),
+            im: Number::deserialize(bytes)?.to_decimal(),
+        })
+    }
+}
+
+impl Serializable for ComplexError {
+    fn serialize(&self) -> Vec<u8> {
+        match self {
+            ComplexError::Overflow => vec![0],
+            ComplexError::DivisionByZero => vec![1],
+            ComplexError::MissingVariable(c) => vec![2, *c as u8],
+            ComplexError::UnsupportedFunction => vec![3],
+        }
+    }
+
+    fn deserialize(bytes: &mut dyn Iterator<Item = u8>) -> Option<Self> {
+        match bytes.next()? {
+            0 => Some(ComplexError::Overflow),
+            1 => Some(ComplexError::DivisionByZero),
+            2 => Some(ComplexError::MissingVariable(bytes.next()? as char)),
+            3 => Some(ComplexError::UnsupportedFunction),
+            _ => None,
+        }
+    }
+}
+
+fn mul(a: Decimal, b: Decimal) -> Result<Decimal, ComplexError> {
+    a.checked_mul(b).ok_or(ComplexError::Overflow)
+}
+
+/// The largest magnitude of angle, in radians, which `sin_cos` accepts. Beyond this, reducing the
+/// angle to within one turn loses too much precision for the result to mean anything.
+const MAX_TRIG_ARGUMENT: i64 = 1_000_000_000_000_000;
+
+/// The sine and cosine of `x`, in radians. `Decimal`'s own `sin` and `cos` can panic on large
+/// arguments, so the angle is reduced to within one turn first, and very large angles are an
+/// overflow.
+fn sin_cos(x: Decimal) -> Result<(Decimal, Decimal), ComplexError> {
+    if x.abs() > Decimal::from(MAX_TRIG_ARGUMENT) {
+        return Err(ComplexError::Overflow);
+    }
+    let x = x.checked_rem(Decimal::TWO_PI).ok_or(ComplexError::Overflow)?;
+    Ok((x.sin(), x.cos()))
+}
+
+fn sinh_cosh(x: Decimal) -> Result<(Decimal, Decimal), ComplexError> {
+    let ex = x.checked_exp().ok_or(ComplexError::Overflow)?;
+    let enx = (-x).checked_exp().ok_or(ComplexError::Overflow)?;
+    Ok(((ex - enx) / Decimal::TWO, (ex + enx) / Decimal::TWO))
+}
+
+/// The arctangent of `x`, in radians.
+pub fn atan(x: Decimal) -> Decimal {
+    // Use atan(x) = pi/2 - atan(1/x) to bring the argument into [-1, 1]...
+    if x.abs() > Decimal::ONE {
+        let result = Decimal::HALF_PI - atan(Decimal::ONE / x.abs());
+        return if x.is_negative() { -result } else { result };
+    }
+
+    // ...then atan(x) = 2 atan(x / (1 + sqrt(1 + x^2))) twice, so that the Taylor series converges
+    // quickly
+    let mut x = x;
+    for _ in 0..2 {
+        x /= Decimal::ONE + (Decimal::ONE + x * x).sqrt().unwrap();
+    }
+
+    // atan(x) = x - x^3/3 + x^5/5 - ...
+    let mut result = Decimal::ZERO;
+    let mut power = x;
+    let x_squared = x * x;
+    let mut denominator = Decimal::ONE;
+    let mut positive = true;
+    while !power.is_zero() && denominator < Decimal::from(200) {
+        let term = power / denominator;
+        if term.is_zero() { break }
+        result = if positive { result + term } else { result - term };
+        power *= x_squared;
+        denominator += Decimal::TWO;
+        positive = !positive;
+    }
+
+    result * Decimal::from(4)
+}
+
+/// The angle of the point `(x, y)` from the positive X axis, in radians, in the range (-pi, pi].
+pub fn atan2(y: Decimal, x: Decimal) -> Decimal {
+    // If `y / x` overflows, then `x` is negligible next to `y`, so the point is on the Y axis
+    let ratio = match y.checked_div(x) {
+        Some(ratio) if !x.is_zero() => ratio,
+        _ if y.is_zero() => return Decimal::ZERO,
+        _ if y.is_sign_positive() => return Decimal::HALF_PI,
+        _ => return -Decimal::HALF_PI,
+    };
+
+    if x.is_sign_positive() {
+        atan(ratio)
+    } else if y.is_negative() {
+        atan(ratio) - Decimal::PI
+    } else {
+        atan(ratio) + Decimal::PI
+    }
+}
+
+/// Returns true if the given unstructured node list uses the imaginary unit `i` anywhere, in which
+/// case it should be evaluated with `evaluate_complex` rather than rbop's real evaluator.
+pub fn uses_imaginary_unit(list: &UnstructuredNodeList) -> bool {
+    list.items.iter().any(|node| match node {
+        UnstructuredNode::Token(Token::Variable('i')) => true,
+        UnstructuredNode::Token(_) => false,
+        UnstructuredNode::Sqrt(inner)
+        | UnstructuredNode::Power(inner)
+        | UnstructuredNode::Parentheses(inner) => uses_imaginary_unit(inner),
+        UnstructuredNode::Fraction(top, bottom) => uses_imaginary_unit(top) || uses_imaginary_unit(bottom),
+        UnstructuredNode::FunctionCall(_, args) => args.iter().any(uses_imaginary_unit),
+    })
+}
+
+/// Evaluates a structured node, treating the variable `i` as the imaginary unit.
+pub fn evaluate_complex(node: &StructuredNode, settings: &EvaluationSettings) -> ComplexResult {
+    match node {
+        StructuredNode::Number(n) => Ok(Complex::from_real(n.to_decimal())),
+        StructuredNode::Variable('i') => Ok(Complex::I),
+        StructuredNode::Variable(c) => Err(ComplexError::MissingVariable(*c)),
+
+        StructuredNode::Sqrt(inner) => evaluate_complex(inner, settings)?.sqrt(),
+        StructuredNode::Power(base, exponent) =>
+            evaluate_complex(base, settings)?.pow(&evaluate_complex(exponent, settings)?),
+        StructuredNode::Add(a, b) =>
+            evaluate_complex(a, settings)?.checked_add(&evaluate_complex(b, settings)?),
+        StructuredNode::Subtract(a, b) =>
+            evaluate_complex(a, settings)?.checked_sub(&evaluate_complex(b, settings)?),
+        StructuredNode::Multiply(a, b) =>
+            evaluate_complex(a, settings)?.checked_mul(&evaluate_complex(b, settings)?),
+        StructuredNode::Divide(a, b) =>
+            evaluate_complex(a, settings)?.checked_div(&evaluate_complex(b, settings)?),
+        StructuredNode::Parentheses(inner) => evaluate_complex(inner, settings),
+
+        StructuredNode::FunctionCall(function, args) => {
+            let args = args.iter()
+                .map(|arg| evaluate_complex(arg, settings))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            match function {
+                Function::Sine | Function::Cosine if args.len() == 1 => {
+                    let mut arg = args[0];
+                    if settings.angle_unit == AngleUnit::Degree {
+                        arg = arg.checked_mul(&Complex::from_real(Decimal::PI / Decimal::from(180)))?;
+                    }
+
+                    if matches!(function, Function::Sine) { arg.sin() } else { arg.cos() }
+                }
+
+                // Other functions are only defined for reals, so let rbop evaluate them
+                _ => {
+                    if !args.iter().all(Complex::is_real) {
+                        return Err(ComplexError::UnsupportedFunction);
+                    }
+                    let real_call = StructuredNode::FunctionCall(
+                        function.clone(),
+                        args.iter().map(|arg| StructuredNode::Number(Number::from(arg.re))).collect(),
+                    );
+                    real_call.evaluate(settings)
+                        .map(|n| Complex::from_real(n.to_decimal()))
+                        .map_err(|_| ComplexError::UnsupportedFunction)
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use alloc::{format, vec::Vec};
+use rbop::{Number, UnstructuredNode};
+use rust_decimal::Decimal;
+
+use crate::applications::calculator::catalog::CatalogItem;
+use super::{decimal_from_scientific, number_to_node};
+
+/// A named physical or mathematical constant which can be inserted into an expression.
+///
+/// Values are stored as a mantissa and a power-of-ten exponent (see `decimal_from_scientific`).
+/// `Decimal` can only represent 28 decimal places, so constants which would be too small to
+/// represent in SI units (such as the Planck constant) are given in more convenient units instead;
+/// the `unit` field says which.
+#[derive(Debug, Clone, Copy)]
+pub struct Constant {
+    /// A short name, used as the label in the catalog.
+    pub symbol: &'static str,
+    pub name: &'static str,
+    pub unit: &'static str,
+    pub mantissa: i64,
+    pub exponent: i32,
+}
+
+impl Constant {
+    pub const fn new(symbol: &'static str, name: &'static str, unit: &'static str, mantissa: i64, exponent: i32) -> Self {
+        Self { symbol, name, unit, mantissa, exponent }
+    }
+
+    /// The value of this constant as a `Decimal`.
+    pub fn to_decimal(&self) -> Decimal {
+        decimal_from_scientific(self.mantissa, self.exponent)
+    }
+
+    /// The value of this constant as an rbop `Number`.
+    pub fn to_number(&self) -> Number {
+        self.to_decimal().into()
+    }
+
+    /// Builds an unstructured node which evaluates to this constant.
+    pub fn to_node(&self) -> UnstructuredNode {
+        number_to_node(self.to_number())
+    }
+
+    /// A description of this constant suitable for display in a catalog.
+    pub fn description(&self) -> alloc::string::String {
+        if self.unit.is_empty() {
+            self.name.into()
+        } else {
+            format!("{} ({})", self.name, self.unit)
+        }
+    }
+
+    /// Creates a catalog item which inserts this constant.
+    pub fn to_catalog_item(&self) -> CatalogItem<UnstructuredNode> {
+        CatalogItem::new(self.symbol, self.description(), self.to_node())
+    }
+}
+
+/// All constants available to the user, in the order in which they appear in catalogs.
+pub const CONSTANTS: &[Constant] = &[
+    // Mathematical
+    Constant::new("pi", "Ratio of a circle's circumference to its diameter", "", 3141592653589793238, -18),
+    Constant::new("e", "Euler's number, base of the natural logarithm", "", 2718281828459045235, -18),
+    Constant::new("phi", "Golden ratio", "", 1618033988749894848, -18),
+
+    // Physical
+    Constant::new("c", "Speed of light in a vacuum", "m/s", 299792458, 0),
+    Constant::new("g", "Standard acceleration of gravity", "m/s^2", 980665, -5),
+    Constant::new("G", "Newtonian constant of gravitation", "m^3/kg s^2", 667430, -16),
+    Constant::new("h", "Planck constant", "eV s", 4135667696, -24),
+    Constant::new("kB", "Boltzmann constant", "eV/K", 8617333262, -14),
+    Constant::new("NA", "Avogadro constant", "1/mol", 602214076, 15),
+    Constant::new("R", "Molar gas constant", "J/mol K", 8314462618, -9),
+    Constant::new("F", "Faraday constant", "C/mol", 9648533212, -5),
+    Constant::new("qe", "Elementary charge", "C", 1602176634, -28),
+    Constant::new("me", "Electron mass", "MeV/c^2", 51099895, -8),
+    Constant::new("e0", "Vacuum electric permittivity", "F/m", 88541878128, -22),
+    Constant::new("u0", "Vacuum magnetic permeability", "N/A^2", 125663706212, -17),
+    Constant::new("atm", "Standard atmosphere", "Pa", 101325, 0),
+];
+
+/// Creates catalog items for every constant in `CONSTANTS`.
+pub fn constant_catalog_items() -> Vec<CatalogItem<UnstructuredNode>> {
+    CONSTANTS.iter().map(|c| c.to_catalog_item()).collect()
+}
@@ -0,0 +1,319 @@
+use alloc::{string::String, vec::Vec};
+
+/// A number base which integers can be entered and displayed in.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Radix {
+    Binary,
+    Octal,
+    Decimal,
+    Hexadecimal,
+}
+
+impl Radix {
+    pub const ALL: [Radix; 4] = [Radix::Decimal, Radix::Hexadecimal, Radix::Binary, Radix::Octal];
+
+    pub fn value(&self) -> u32 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Octal => 8,
+            Radix::Decimal => 10,
+            Radix::Hexadecimal => 16,
+        }
+    }
+
+    /// A short name for this radix, for labels.
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            Radix::Binary => "BIN",
+            Radix::Octal => "OCT",
+            Radix::Decimal => "DEC",
+            Radix::Hexadecimal => "HEX",
+        }
+    }
+
+    /// Whether `c` is a valid digit in this radix. Letters are accepted in either case.
+    pub fn is_digit(&self, c: char) -> bool {
+        c.is_digit(self.value())
+    }
+}
+
+/// Describes how integers are stored: how many bits wide they are, and whether they are
+/// interpreted as two's complement signed values.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct IntegerFormat {
+    pub bits: u32,
+    pub signed: bool,
+}
+
+impl Default for IntegerFormat {
+    fn default() -> Self {
+        Self { bits: 32, signed: true }
+    }
+}
+
+impl IntegerFormat {
+    pub const WORD_SIZES: [u32; 4] = [8, 16, 32, 64];
+
+    /// A mask with the lowest `bits` bits set.
+    pub fn mask(&self) -> u64 {
+        if self.bits >= 64 {
+            u64::MAX
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    /// Truncates a value to this word size.
+    pub fn truncate(&self, value: u64) -> u64 {
+        value & self.mask()
+    }
+
+    /// Interprets the truncated bits of `value` as a two's complement signed integer.
+    pub fn to_signed(&self, value: u64) -> i64 {
+        let shift = 64 - self.bits;
+        ((value << shift) as i64) >> shift
+    }
+
+    /// Formats a value in the given radix. Decimal values are signed if this format is signed;
+    /// other radixes always show the raw bits, as programmers would expect.
+    pub fn format(&self, value: u64, radix: Radix) -> String {
+        let value = self.truncate(value);
+        if radix == Radix::Decimal && self.signed {
+            let signed = self.to_signed(value);
+            let mut result = format_unsigned(signed.unsigned_abs(), radix);
+            if signed < 0 {
+                result.insert(0, '-');
+            }
+            result
+        } else {
+            format_unsigned(value, radix)
+        }
+    }
+
+    /// Rewrites every literal in `expression` from one radix into another, leaving operators
+    /// untouched. Literals which aren't valid in `from` are left as they are.
+    pub fn convert_literals(&self, expression: &str, from: Radix, to: Radix) -> String {
+        let mut result = String::new();
+        let mut literal = String::new();
+
+        for c in expression.chars().chain(core::iter::once(' ')) {
+            if c.is_ascii_alphanumeric() {
+                literal.push(c);
+                continue;
+            }
+
+            if !literal.is_empty() {
+                match u64::from_str_radix(&literal, from.value()) {
+                    Ok(value) => result.push_str(&format_unsigned(value, to)),
+                    Err(_) => result.push_str(&literal),
+                }
+                literal.clear();
+            }
+            result.push(c);
+        }
+
+        // Remove the extra space we chained on
+        result.pop();
+        result
+    }
+
+    /// Evaluates an integer expression, with literals in the given radix.
+    ///
+    /// Supports `+ - * / %`, bitwise `& | ^ ~`, shifts `<< >>` and parentheses, with the same
+    /// precedence as C. All arithmetic wraps at the word size.
+    pub fn evaluate(&self, expression: &str, radix: Radix) -> Result<u64, IntegerError> {
+        let mut parser = IntegerParser {
+            chars: expression.chars().filter(|c| !c.is_whitespace()).collect(),
+            index: 0,
+            radix,
+            format: *self,
+        };
+
+        let result = parser.parse_or()?;
+        if parser.index != parser.chars.len() {
+            return Err(IntegerError::Syntax);
+        }
+        Ok(result)
+    }
+}
+
+/// An error encountered while evaluating an integer expression.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum IntegerError {
+    /// The expression was malformed.
+    Syntax,
+
+    /// A literal was too large for the word size.
+    Overflow,
+
+    /// A division or modulo by zero.
+    DivisionByZero,
+}
+
+fn format_unsigned(mut value: u64, radix: Radix) -> String {
+    if value == 0 {
+        return "0".into();
+    }
+
+    let mut digits = Vec::new();
+    while value > 0 {
+        digits.push(core::char::from_digit((value % radix.value() as u64) as u32, radix.value()).unwrap().to_ascii_uppercase());
+        value /= radix.value() as u64;
+    }
+    digits.iter().rev().collect()
+}
+
+/// A recursive descent parser which evaluates as it parses. Each `parse_` method handles one level
+/// of precedence, from lowest to highest.
+struct IntegerParser {
+    chars: Vec<char>,
+    index: usize,
+    radix: Radix,
+    format: IntegerFormat,
+}
+
+impl IntegerParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    /// If the upcoming characters match `s`, consumes them and returns true.
+    fn accept(&mut self, s: &str) -> bool {
+        let len = s.chars().count();
+        if self.index + len <= self.chars.len() && self.chars[self.index..self.index + len].iter().copied().eq(s.chars()) {
+            self.index += len;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<u64, IntegerError> {
+        let mut value = self.parse_xor()?;
+        while self.accept("|") {
+            value |= self.parse_xor()?;
+        }
+        Ok(value)
+    }
+
+    fn parse_xor(&mut self) -> Result<u64, IntegerError> {
+        let mut value = self.parse_and()?;
+        while self.accept("^") {
+            value ^= self.parse_and()?;
+        }
+        Ok(value)
+    }
+
+    fn parse_and(&mut self) -> Result<u64, IntegerError> {
+        let mut value = self.parse_shift()?;
+        while self.accept("&") {
+            value &= self.parse_shift()?;
+        }
+        Ok(value)
+    }
+
+    fn parse_shift(&mut self) -> Result<u64, IntegerError> {
+        let mut value = self.parse_additive()?;
+        loop {
+            if self.accept("<<") {
+                let amount = self.parse_additive()?;
+                value = if amount >= 64 { 0 } else { self.format.truncate(value << amount) };
+            } else if self.accept(">>") {
+                let amount = self.parse_additive()?.min(63) as u32;
+                value = if self.format.signed {
+                    // Arithmetic shift, preserving the sign bit
+                    self.format.truncate((self.format.to_signed(value) >> amount) as u64)
+                } else {
+                    value.checked_shr(amount).unwrap_or(0)
+                };
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn parse_additive(&mut self) -> Result<u64, IntegerError> {
+        let mut value = self.parse_multiplicative()?;
+        loop {
+            if self.accept("+") {
+                value = self.format.truncate(value.wrapping_add(self.parse_multiplicative()?));
+            } else if self.accept("-") {
+                value = self.format.truncate(value.wrapping_sub(self.parse_multiplicative()?));
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<u64, IntegerError> {
+        let mut value = self.parse_unary()?;
+        loop {
+            if self.accept("*") {
+                value = self.format.truncate(value.wrapping_mul(self.parse_unary()?));
+            } else if self.accept("/") {
+                let divisor = self.parse_unary()?;
+                value = self.divide(value, divisor, false)?;
+            } else if self.accept("%") {
+                let divisor = self.parse_unary()?;
+                value = self.divide(value, divisor, true)?;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Divides two values, respecting signedness, and returns either the quotient or remainder.
+    fn divide(&self, dividend: u64, divisor: u64, remainder: bool) -> Result<u64, IntegerError> {
+        if divisor == 0 {
+            return Err(IntegerError::DivisionByZero);
+        }
+
+        let result = if self.format.signed {
+            let (a, b) = (self.format.to_signed(dividend), self.format.to_signed(divisor));
+            (if remainder { a.wrapping_rem(b) } else { a.wrapping_div(b) }) as u64
+        } else if remainder {
+            dividend % divisor
+        } else {
+            dividend / divisor
+        };
+        Ok(self.format.truncate(result))
+    }
+
+    fn parse_unary(&mut self) -> Result<u64, IntegerError> {
+        if self.accept("-") {
+            Ok(self.format.truncate(self.parse_unary()?.wrapping_neg()))
+        } else if self.accept("~") {
+            Ok(self.format.truncate(!self.parse_unary()?))
+        } else if self.accept("+") {
+            self.parse_unary()
+        } else {
+            self.parse_atom()
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<u64, IntegerError> {
+        if self.accept("(") {
+            let value = self.parse_or()?;
+            if !self.accept(")") {
+                return Err(IntegerError::Syntax);
+            }
+            return Ok(value);
+        }
+
+        let start = self.index;
+        while let Some(c) = self.peek() && self.radix.is_digit(c) {
+            self.index += 1;
+        }
+        if start == self.index {
+            return Err(IntegerError::Syntax);
+        }
+
+        let literal: String = self.chars[start..self.index].iter().collect();
+        let value = u64::from_str_radix(&literal, self.radix.value())
+            .map_err(|_| IntegerError::Overflow)?;
+        if value & !self.format.mask() != 0 {
+            return Err(IntegerError::Overflow);
+        }
+        Ok(value)
+    }
+}
@@ -0,0 +1,453 @@
+//! Conversion between rbop's unstructured nodes and a linear text notation, such as
+//! `sqrt(2)/3+sin(x)^2`, through the `LinearNotation` trait. This is used wherever expressions are
+//! written as plain text: exporting and importing history, evaluating expressions over the host
+//! link, and writing tests.
+//!
+//! The notation mirrors the structure of the nodes rather than the maths, so that converting to
+//! text and back gives the same nodes:
+//!   - Digits, `.`, `+`, `-` and `*` are tokens, and each letter is a variable.
+//!   - `(a)` is a pair of parentheses.
+//!   - `a/b` is a fraction. The numerator is the term just before the `/`, and the denominator the
+//!     term just after it, where a term is a number, variable, function call, parentheses or
+//!     fraction, followed by any powers. If a whole numerator or denominator is wrapped in
+//!     parentheses, those parentheses only group it, so `(1+2)/3` has `1+2` as its numerator.
+//!   - `^b` is a power, raising whatever comes before it. The exponent is a single number,
+//!     variable or function call, or a group in parentheses like `^(2x)`. A power after a space
+//!     raises a whole fraction rather than just its denominator, so `1/2 ^3` is one half cubed.
+//!   - `sqrt(a)` is a square root, and `sin(a)`, `cos(a)` and `gcd(a, b)` are function calls. The
+//!     `(` must follow the name immediately; otherwise the letters are variables.
+//!
+//! Otherwise, whitespace is ignored, except that it separates adjacent numbers into different
+//! terms.
+
+use core::fmt::Display;
+
+use alloc::{string::String, vec, vec::Vec};
+use rbop::{Token, UnstructuredNode, UnstructuredNodeList, node::{function::Function, unstructured::UnstructuredNodeRoot}};
+
+/// Functions which can be written in linear notation, found by their `render_name`.
+static FUNCTIONS: [Function; 3] = [Function::Sine, Function::Cosine, Function::GreatestCommonDenominator];
+
+/// The name used for square roots, which are a node of their own rather than a function call.
+const SQRT_NAME: &str = "sqrt";
+
+/// An error encountered while parsing linear notation.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct LinearParseError {
+    /// The index of the character where the error was found.
+    pub position: usize,
+    pub kind: LinearParseErrorKind,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum LinearParseErrorKind {
+    UnexpectedCharacter(char),
+    UnexpectedEnd,
+
+    /// A `/` had nothing before it to use as a numerator.
+    MissingNumerator,
+
+    /// A function was called with the wrong number of arguments.
+    WrongArgumentCount,
+}
+
+impl Display for LinearParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.kind {
+            LinearParseErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected '{}'", c)?,
+            LinearParseErrorKind::UnexpectedEnd => write!(f, "Unexpected end")?,
+            LinearParseErrorKind::MissingNumerator => write!(f, "Fraction has no numerator")?,
+            LinearParseErrorKind::WrongArgumentCount => write!(f, "Wrong number of arguments")?,
+        }
+        write!(f, " at column {}", self.position + 1)
+    }
+}
+
+/// Conversion between nodes and linear notation.
+pub trait LinearNotation: Sized {
+    /// Converts to linear notation.
+    fn to_linear(&self) -> String;
+
+    /// Parses linear notation.
+    fn from_linear(text: &str) -> Result<Self, LinearParseError>;
+}
+
+impl LinearNotation for UnstructuredNodeList {
+    fn to_linear(&self) -> String {
+        let mut result = String::new();
+        write_list(self, &mut result);
+        result
+    }
+
+    fn from_linear(text: &str) -> Result<Self, LinearParseError> {
+        let mut parser = Parser { chars: text.chars().collect(), position: 0 };
+        let items = parser.parse_list()?;
+        parser.skip_whitespace();
+        match parser.peek() {
+            None => Ok(UnstructuredNodeList { items }),
+            Some(c) => Err(parser.error(LinearParseErrorKind::UnexpectedCharacter(c))),
+        }
+    }
+}
+
+impl LinearNotation for UnstructuredNodeRoot {
+    fn to_linear(&self) -> String {
+        self.root.to_linear()
+    }
+
+    fn from_linear(text: &str) -> Result<Self, LinearParseError> {
+        Ok(UnstructuredNodeRoot { root: UnstructuredNodeList::from_linear(text)? })
+    }
+}
+
+fn is_number_token(node: &UnstructuredNode) -> bool {
+    matches!(node, UnstructuredNode::Token(Token::Digit(_)) | UnstructuredNode::Token(Token::Point))
+}
+
+/// Whether `items` can be written as a single term without grouping parentheses, so that parsing
+/// it back as a fraction's numerator or denominator, or an exponent, gives the same nodes.
+fn is_single_term(items: &[UnstructuredNode], allow_powers: bool, allow_fraction: bool) -> bool {
+    let base_length = if allow_powers {
+        items.iter().rposition(|n| !matches!(n, UnstructuredNode::Power(_))).map(|i| i + 1).unwrap_or(0)
+    } else {
+        items.len()
+    };
+    let (base, powers) = items.split_at(base_length);
+
+    if !base.is_empty() && base.iter().all(is_number_token) {
+        return true;
+    }
+
+    match base {
+        [UnstructuredNode::Token(Token::Variable(_))]
+        | [UnstructuredNode::FunctionCall(_, _)]
+        | [UnstructuredNode::Sqrt(_)] => true,
+        [UnstructuredNode::Fraction(_, _)] => allow_fraction,
+
+        // On their own, these would be taken as grouping parentheses
+        [UnstructuredNode::Parentheses(_)] => !powers.is_empty(),
+
+        _ => false,
+    }
+}
+
+/// Writes a list of items as a term, wrapping it in grouping parentheses if required.
+fn write_term(items: &UnstructuredNodeList, single_term: bool, result: &mut String) {
+    if single_term {
+        write_list(items, result);
+    } else {
+        result.push('(');
+        write_list(items, result);
+        result.push(')');
+    }
+}
+
+/// The names which are parsed as a call when followed by `(`.
+fn call_names() -> impl Iterator<Item = &'static str> {
+    FUNCTIONS.iter().map(|f| f.render_name()).chain([SQRT_NAME])
+}
+
+/// Appends text to the result, separating it with a space if it would otherwise run into what came
+/// before: a number into a previous number, or parentheses into variables which spell a function
+/// name and would be read as a call.
+fn push_separated(text: &str, result: &mut String) {
+    let is_number = |c: char| c.is_ascii_digit() || c == '.';
+    let separate = match text.chars().next() {
+        Some('(') => call_names().any(|name| result.ends_with(name)),
+        Some(c) if is_number(c) => result.chars().last().map(is_number).unwrap_or(false),
+        _ => false,
+    };
+    if separate {
+        result.push(' ');
+    }
+    result.push_str(text);
+}
+
+/// If `items` is just a pair of parentheses, returns their contents, since these parentheses were
+/// only used for grouping.
+fn ungroup(mut items: Vec<UnstructuredNode>) -> Vec<UnstructuredNode> {
+    if let [UnstructuredNode::Parentheses(inner)] = items.as_mut_slice() {
+        return core::mem::take(&mut inner.items);
+    }
+    items
+}
+
+fn write_list(list: &UnstructuredNodeList, result: &mut String) {
+    for (i, node) in list.items.iter().enumerate() {
+        let mut text = String::new();
+        match node {
+            UnstructuredNode::Token(token) => text.push(match token {
+                Token::Add => '+',
+                Token::Subtract => '-',
+                Token::Multiply => '*',
+                Token::Divide => '/',
+                Token::Point => '.',
+                Token::Digit(d) => (b'0' + d) as char,
+                Token::Variable(v) => *v,
+            }),
+
+            UnstructuredNode::Sqrt(inner) => {
+                text.push_str(SQRT_NAME);
+                text.push('(');
+                write_list(inner, &mut text);
+                text.push(')');
+            }
+
+            UnstructuredNode::FunctionCall(function, args) => {
+                text.push_str(function.render_name());
+                text.push('(');
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        text.push_str(", ");
+                    }
+                    write_list(arg, &mut text);
+                }
+                text.push(')');
+            }
+
+            UnstructuredNode::Parentheses(inner) => {
+                text.push('(');
+                write_list(inner, &mut text);
+                text.push(')');
+            }
+
+            UnstructuredNode::Power(exponent) => {
+                text.push('^');
+                write_term(exponent, is_single_term(&exponent.items, false, false), &mut text);
+            }
+
+            UnstructuredNode::Fraction(numerator, denominator) => {
+                write_term(numerator, is_single_term(&numerator.items, true, true), &mut text);
+                text.push('/');
+                write_term(denominator, is_single_term(&denominator.items, true, false), &mut text);
+
+                // Without a space, a power after this would be part of the denominator
+                if let Some(UnstructuredNode::Power(_)) = list.items.get(i + 1) {
+                    text.push(' ');
+                }
+            }
+        }
+
+        push_separated(&text, result);
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn error(&self, kind: LinearParseErrorKind) -> LinearParseError {
+        LinearParseError { position: self.position, kind }
+    }
+
+    fn unexpected(&self) -> LinearParseError {
+        match self.peek() {
+            Some(c) => self.error(LinearParseErrorKind::UnexpectedCharacter(c)),
+            None => self.error(LinearParseErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map(char::is_whitespace).unwrap_or(false) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), LinearParseError> {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    /// Parses items until a `)`, `,`, or the end of the input, which is not consumed.
+    fn parse_list(&mut self) -> Result<Vec<UnstructuredNode>, LinearParseError> {
+        let mut items = vec![];
+
+        // The index in `items` where the most recent term starts, used to find the numerator when
+        // a `/` is reached
+        let mut term_start = 0;
+
+        loop {
+            self.skip_whitespace();
+            let c = match self.peek() {
+                Some(')') | Some(',') | None => return Ok(items),
+                Some(c) => c,
+            };
+
+            match c {
+                '+' | '-' | '*' => {
+                    self.position += 1;
+                    items.push(UnstructuredNode::Token(match c {
+                        '+' => Token::Add,
+                        '-' => Token::Subtract,
+                        _ => Token::Multiply,
+                    }));
+                    term_start = items.len();
+                }
+
+                '^' => {
+                    self.position += 1;
+                    let exponent = self.parse_term(false)?;
+                    items.push(UnstructuredNode::Power(UnstructuredNodeList { items: exponent }));
+                }
+
+                '/' => {
+                    if term_start == items.len() {
+                        return Err(self.error(LinearParseErrorKind::MissingNumerator));
+                    }
+                    self.position += 1;
+
+                    let numerator = ungroup(items.split_off(term_start));
+                    let denominator = self.parse_term(true)?;
+
+                    items.push(UnstructuredNode::Fraction(
+                        UnstructuredNodeList { items: numerator },
+                        UnstructuredNodeList { items: denominator },
+                    ));
+                }
+
+                c if c.is_ascii_alphabetic() => {
+                    // Any letters before a function's name are variables
+                    let word_start = self.position;
+                    while self.peek().map(|c| c.is_ascii_alphabetic()).unwrap_or(false) {
+                        self.position += 1;
+                    }
+                    let word = self.chars[word_start..self.position].iter().collect::<String>();
+                    let name_length = self.function_name_suffix(&word);
+                    for v in word.chars().take(word.chars().count() - name_length) {
+                        term_start = items.len();
+                        items.push(UnstructuredNode::Token(Token::Variable(v)));
+                    }
+                    if name_length > 0 {
+                        term_start = items.len();
+                        let name = word.chars().skip(word.chars().count() - name_length).collect::<String>();
+                        items.push(self.parse_call(&name)?);
+                    }
+                }
+
+                _ => {
+                    term_start = items.len();
+                    items.extend(self.parse_primary()?);
+                }
+            }
+        }
+    }
+
+    /// If `word` ends with the name of a function and is immediately followed by `(`, returns the
+    /// length of that name. Otherwise returns 0.
+    fn function_name_suffix(&self, word: &str) -> usize {
+        if self.peek() != Some('(') {
+            return 0;
+        }
+
+        call_names()
+            .filter(|name| word.ends_with(name))
+            .map(|name| name.chars().count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Parses the arguments of a function call or square root, after its name.
+    fn parse_call(&mut self, name: &str) -> Result<UnstructuredNode, LinearParseError> {
+        self.expect('(')?;
+        let mut args = vec![UnstructuredNodeList { items: self.parse_list()? }];
+        self.skip_whitespace();
+        while self.peek() == Some(',') {
+            self.position += 1;
+            args.push(UnstructuredNodeList { items: self.parse_list()? });
+            self.skip_whitespace();
+        }
+        self.expect(')')?;
+
+        if name == SQRT_NAME {
+            if args.len() != 1 {
+                return Err(self.error(LinearParseErrorKind::WrongArgumentCount));
+            }
+            return Ok(UnstructuredNode::Sqrt(args.remove(0)));
+        }
+
+        let function = FUNCTIONS.iter().find(|f| f.render_name() == name).unwrap().clone();
+        let expected_args = match UnstructuredNode::new_function_call(function.clone()) {
+            UnstructuredNode::FunctionCall(_, args) => args.len(),
+            _ => unreachable!(),
+        };
+        if args.len() != expected_args {
+            return Err(self.error(LinearParseErrorKind::WrongArgumentCount));
+        }
+        Ok(UnstructuredNode::FunctionCall(function, args))
+    }
+
+    /// Parses a number, parenthesised group, or single variable or function call.
+    fn parse_primary(&mut self) -> Result<Vec<UnstructuredNode>, LinearParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let mut items = vec![];
+                while let Some(c) = self.peek() {
+                    items.push(UnstructuredNode::Token(match c {
+                        '.' => Token::Point,
+                        c if c.is_ascii_digit() => Token::Digit(c as u8 - b'0'),
+                        _ => break,
+                    }));
+                    self.position += 1;
+                }
+                Ok(items)
+            }
+
+            Some('(') => {
+                self.position += 1;
+                let inner = self.parse_list()?;
+                self.expect(')')?;
+                Ok(vec![UnstructuredNode::Parentheses(UnstructuredNodeList { items: inner })])
+            }
+
+            Some(c) if c.is_ascii_alphabetic() => {
+                // Take the longest function name which is followed by `(`, or otherwise just one
+                // letter as a variable
+                let rest = self.chars[self.position..].iter().collect::<String>();
+                let call = call_names()
+                    .filter(|name| rest.starts_with(name) && rest[name.len()..].starts_with('('))
+                    .max_by_key(|name| name.len());
+                match call {
+                    Some(name) => {
+                        self.position += name.chars().count();
+                        Ok(vec![self.parse_call(name)?])
+                    }
+                    None => {
+                        self.position += 1;
+                        Ok(vec![UnstructuredNode::Token(Token::Variable(c))])
+                    }
+                }
+            }
+
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    /// Parses a term used as a denominator or exponent. Denominators can be followed by powers,
+    /// but only without a space in between, and exponents cannot, since a second power applies to
+    /// the whole of the first. If the term is just a group in parentheses, returns its contents.
+    fn parse_term(&mut self, allow_powers: bool) -> Result<Vec<UnstructuredNode>, LinearParseError> {
+        let mut items = self.parse_primary()?;
+
+        if allow_powers {
+            while self.peek() == Some('^') {
+                self.position += 1;
+                let exponent = self.parse_term(false)?;
+                items.push(UnstructuredNode::Power(UnstructuredNodeList { items: exponent }));
+            }
+        }
+
+        Ok(ungroup(items))
+    }
+}
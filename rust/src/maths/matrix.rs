@@ -0,0 +1,319 @@
+use core::fmt::Display;
+use alloc::{vec, vec::Vec};
+use num_traits::{Zero, One};
+use rbop::Number;
+
+/// A matrix of rbop `Number`s. Vectors are represented as matrices with a single row or column.
+///
+/// Elements are kept as `Number`s rather than decimals so that operations such as inversion and
+/// row reduction give exact fractional results where possible.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Matrix {
+    rows: usize,
+    columns: usize,
+
+    /// Elements in row-major order.
+    elements: Vec<Number>,
+}
+
+/// An error encountered while performing a matrix operation.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum MatrixError {
+    /// The dimensions of the operands are not compatible with the operation.
+    DimensionMismatch,
+
+    /// The operation requires a square matrix.
+    NotSquare,
+
+    /// The operation requires a vector.
+    NotVector,
+
+    /// The matrix has no inverse.
+    Singular,
+}
+
+impl Display for MatrixError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MatrixError::DimensionMismatch => write!(f, "Dimension mismatch"),
+            MatrixError::NotSquare => write!(f, "Matrix is not square"),
+            MatrixError::NotVector => write!(f, "Operands must be vectors"),
+            MatrixError::Singular => write!(f, "Matrix is singular"),
+        }
+    }
+}
+
+impl Matrix {
+    /// Creates a matrix of the given size, filled with zeroes.
+    pub fn zero(rows: usize, columns: usize) -> Self {
+        Self {
+            rows,
+            columns,
+            elements: vec![Number::zero(); rows * columns],
+        }
+    }
+
+    /// Creates an identity matrix of the given size.
+    pub fn identity(size: usize) -> Self {
+        let mut result = Self::zero(size, size);
+        for i in 0..size {
+            result[(i, i)] = Number::one();
+        }
+        result
+    }
+
+    /// Creates a matrix from a list of rows. All rows must be the same length.
+    pub fn from_rows(rows: Vec<Vec<Number>>) -> Self {
+        let row_count = rows.len();
+        let column_count = rows.first().map(|r| r.len()).unwrap_or(0);
+        assert!(rows.iter().all(|r| r.len() == column_count), "rows are not the same length");
+
+        Self {
+            rows: row_count,
+            columns: column_count,
+            elements: rows.into_iter().flatten().collect(),
+        }
+    }
+
+    pub fn rows(&self) -> usize { self.rows }
+    pub fn columns(&self) -> usize { self.columns }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.columns
+    }
+
+    pub fn is_vector(&self) -> bool {
+        self.rows == 1 || self.columns == 1
+    }
+
+    /// Changes the size of this matrix, keeping elements which are still in bounds and filling new
+    /// ones with zeroes.
+    pub fn resize(&mut self, rows: usize, columns: usize) {
+        let mut result = Self::zero(rows, columns);
+        for r in 0..rows.min(self.rows) {
+            for c in 0..columns.min(self.columns) {
+                result[(r, c)] = self[(r, c)];
+            }
+        }
+        *self = result;
+    }
+
+    fn map(&self, func: impl Fn(Number) -> Number) -> Self {
+        Self {
+            rows: self.rows,
+            columns: self.columns,
+            elements: self.elements.iter().map(|e| func(*e)).collect(),
+        }
+    }
+
+    fn zip(&self, other: &Matrix, func: impl Fn(Number, Number) -> Number) -> Result<Self, MatrixError> {
+        if self.rows != other.rows || self.columns != other.columns {
+            return Err(MatrixError::DimensionMismatch);
+        }
+
+        Ok(Self {
+            rows: self.rows,
+            columns: self.columns,
+            elements: self.elements.iter().zip(other.elements.iter()).map(|(a, b)| func(*a, *b)).collect(),
+        })
+    }
+
+    pub fn add(&self, other: &Matrix) -> Result<Self, MatrixError> {
+        self.zip(other, |a, b| a + b)
+    }
+
+    pub fn subtract(&self, other: &Matrix) -> Result<Self, MatrixError> {
+        self.zip(other, |a, b| a - b)
+    }
+
+    pub fn scale(&self, factor: Number) -> Self {
+        self.map(|e| e * factor)
+    }
+
+    pub fn multiply(&self, other: &Matrix) -> Result<Self, MatrixError> {
+        if self.columns != other.rows {
+            return Err(MatrixError::DimensionMismatch);
+        }
+
+        let mut result = Self::zero(self.rows, other.columns);
+        for r in 0..self.rows {
+            for c in 0..other.columns {
+                let mut sum = Number::zero();
+                for i in 0..self.columns {
+                    sum = sum + self[(r, i)] * other[(i, c)];
+                }
+                result[(r, c)] = sum.simplify();
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut result = Self::zero(self.columns, self.rows);
+        for r in 0..self.rows {
+            for c in 0..self.columns {
+                result[(c, r)] = self[(r, c)];
+            }
+        }
+        result
+    }
+
+    /// Reduces this matrix to reduced row echelon form, using Gauss-Jordan elimination. Also
+    /// returns the determinant of the leftmost square part of the matrix, which is only meaningful
+    /// if that part is the whole matrix or is followed by an augmented section.
+    fn gauss_jordan(&self) -> (Self, Number) {
+        let mut result = self.clone();
+        let mut determinant = Number::one();
+        let mut pivot_row = 0;
+
+        for c in 0..result.columns {
+            if pivot_row >= result.rows {
+                break;
+            }
+
+            // Find a row with a non-zero element in this column
+            let found = (pivot_row..result.rows).find(|r| !result[(*r, c)].is_zero());
+            let r = match found {
+                Some(r) => r,
+                None => {
+                    if c < result.rows {
+                        determinant = Number::zero();
+                    }
+                    continue;
+                }
+            };
+
+            // Move it into place
+            if r != pivot_row {
+                result.swap_rows(r, pivot_row);
+                determinant = Number::zero() - determinant;
+            }
+
+            // Scale so the pivot is 1
+            let pivot = result[(pivot_row, c)];
+            if c < result.rows {
+                determinant = determinant * pivot;
+            }
+            for i in 0..result.columns {
+                result[(pivot_row, i)] = (result[(pivot_row, i)] / pivot).simplify();
+            }
+
+            // Eliminate this column from every other row
+            for other in 0..result.rows {
+                if other == pivot_row {
+                    continue;
+                }
+                let factor = result[(other, c)];
+                if factor.is_zero() {
+                    continue;
+                }
+                for i in 0..result.columns {
+                    result[(other, i)] = (result[(other, i)] - factor * result[(pivot_row, i)]).simplify();
+                }
+            }
+
+            pivot_row += 1;
+        }
+
+        (result, determinant.simplify())
+    }
+
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for c in 0..self.columns {
+            self.elements.swap(a * self.columns + c, b * self.columns + c);
+        }
+    }
+
+    /// The reduced row echelon form of this matrix.
+    pub fn rref(&self) -> Self {
+        self.gauss_jordan().0
+    }
+
+    pub fn determinant(&self) -> Result<Number, MatrixError> {
+        if !self.is_square() {
+            return Err(MatrixError::NotSquare);
+        }
+        Ok(self.gauss_jordan().1)
+    }
+
+    pub fn inverse(&self) -> Result<Self, MatrixError> {
+        if !self.is_square() {
+            return Err(MatrixError::NotSquare);
+        }
+
+        // Augment with the identity matrix, reduce, and take the right half
+        let size = self.rows;
+        let mut augmented = Self::zero(size, size * 2);
+        for r in 0..size {
+            for c in 0..size {
+                augmented[(r, c)] = self[(r, c)];
+            }
+            augmented[(r, size + r)] = Number::one();
+        }
+
+        let (reduced, determinant) = augmented.gauss_jordan();
+        if determinant.is_zero() {
+            return Err(MatrixError::Singular);
+        }
+
+        let mut result = Self::zero(size, size);
+        for r in 0..size {
+            for c in 0..size {
+                result[(r, c)] = reduced[(r, size + c)];
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn dot(&self, other: &Matrix) -> Result<Number, MatrixError> {
+        if !self.is_vector() || !other.is_vector() {
+            return Err(MatrixError::NotVector);
+        }
+        if self.elements.len() != other.elements.len() {
+            return Err(MatrixError::DimensionMismatch);
+        }
+
+        Ok(self.elements.iter()
+            .zip(other.elements.iter())
+            .fold(Number::zero(), |sum, (a, b)| sum + *a * *b)
+            .simplify())
+    }
+
+    /// The cross product of two 3-element vectors. The result has the same orientation (row or
+    /// column) as `self`.
+    pub fn cross(&self, other: &Matrix) -> Result<Self, MatrixError> {
+        if !self.is_vector() || !other.is_vector() {
+            return Err(MatrixError::NotVector);
+        }
+        if self.elements.len() != 3 || other.elements.len() != 3 {
+            return Err(MatrixError::DimensionMismatch);
+        }
+
+        let (a, b) = (&self.elements, &other.elements);
+        Ok(Self {
+            rows: self.rows,
+            columns: self.columns,
+            elements: vec![
+                (a[1] * b[2] - a[2] * b[1]).simplify(),
+                (a[2] * b[0] - a[0] * b[2]).simplify(),
+                (a[0] * b[1] - a[1] * b[0]).simplify(),
+            ],
+        })
+    }
+}
+
+impl core::ops::Index<(usize, usize)> for Matrix {
+    type Output = Number;
+
+    fn index(&self, (row, column): (usize, usize)) -> &Number {
+        assert!(row < self.rows && column < self.columns, "matrix index out of bounds");
+        &self.elements[row * self.columns + column]
+    }
+}
+
+impl core::ops::IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut Number {
+        assert!(row < self.rows && column < self.columns, "matrix index out of bounds");
+        &mut self.elements[row * self.columns + column]
+    }
+}
@@ -0,0 +1,86 @@
+use alloc::{format, string::{String, ToString}};
+use rbop::{Number, UnstructuredNode, node::unstructured::UnstructuredNodeRoot};
+use rust_decimal::Decimal;
+
+pub mod constants;
+pub mod units;
+pub mod integer;
+pub mod complex;
+pub mod matrix;
+pub mod statistics;
+pub mod polynomial;
+pub mod solver;
+pub mod linear;
+
+pub use constants::*;
+pub use units::*;
+pub use integer::*;
+pub use complex::*;
+pub use matrix::*;
+pub use statistics::*;
+pub use polynomial::*;
+pub use solver::*;
+pub use linear::*;
+
+/// Builds a `Decimal` from a mantissa and a power-of-ten exponent, i.e. `mantissa * 10^exponent`.
+///
+/// `Decimal` has no literal syntax for scientific notation, so tables of values (such as constants
+/// and unit conversion factors) are written in this form instead.
+pub fn decimal_from_scientific(mantissa: i64, exponent: i32) -> Decimal {
+    if exponent < 0 {
+        Decimal::new(mantissa, (-exponent) as u32)
+    } else {
+        let mut result = Decimal::new(mantissa, 0);
+        for _ in 0..exponent {
+            result *= Decimal::from(10);
+        }
+        result
+    }
+}
+
+/// Formats a number as plain text, as a fraction if it is rational or otherwise as a decimal
+/// rounded to a sensible number of places. Used where a number must fit in a small space, and
+/// rendering it with rbop would be too large.
+pub fn format_number(number: &Number) -> String {
+    match number.simplify() {
+        Number::Rational(numerator, 1) => numerator.to_string(),
+        Number::Rational(numerator, denominator) => format!("{}/{}", numerator, denominator),
+        Number::Decimal(d, _) => d.round_dp(10).normalize().to_string(),
+    }
+}
+
+/// Formats a decimal as plain text, rounded to a number of significant figures. Very large or
+/// small magnitudes are written in scientific notation, such as `1.602176634E-19`, so that small
+/// values aren't rounded away to 0.
+pub fn format_decimal(value: Decimal, significant_figures: u32) -> String {
+    if value.is_zero() {
+        return "0".into();
+    }
+
+    // The power of ten of the leading digit
+    let digits = value.mantissa().unsigned_abs().to_string().len() as i32;
+    let exponent = digits - 1 - value.scale() as i32;
+
+    if (-4..12).contains(&exponent) {
+        let places = (significant_figures as i32 - 1 - exponent).max(0) as u32;
+        return value.round_dp(places).normalize().to_string();
+    }
+
+    let mut significand = Decimal::from_i128_with_scale(value.mantissa(), (digits - 1) as u32)
+        .round_dp(significant_figures.saturating_sub(1));
+    let mut exponent = exponent;
+
+    // Rounding may have carried into another digit, like 9.99 to 10.0
+    if significand.abs() >= Decimal::from(10) {
+        significand /= Decimal::from(10);
+        exponent += 1;
+    }
+    format!("{}E{}", significand.normalize(), exponent)
+}
+
+/// Builds an unstructured node which evaluates to the given number. The number is wrapped in
+/// parentheses so that it behaves as a single term, e.g. when followed by a power or preceded by a
+/// minus sign.
+pub fn number_to_node(number: Number) -> UnstructuredNode {
+    UnstructuredNode::Parentheses(UnstructuredNodeRoot::from_number(number).root)
+}
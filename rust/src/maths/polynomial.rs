@@ -0,0 +1,101 @@
+use core::fmt::Display;
+use alloc::{vec, vec::Vec};
+use rust_decimal::{Decimal, prelude::{Zero, Signed}};
+
+use super::{Complex, ComplexError};
+
+/// An error encountered while finding the roots of a polynomial.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum PolynomialError {
+    /// The coefficient of the highest power is zero, so the polynomial has a lower degree than
+    /// expected.
+    LeadingCoefficientZero,
+
+    /// The iteration did not settle on a set of roots.
+    NoConvergence,
+
+    Overflow,
+}
+
+impl Display for PolynomialError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            PolynomialError::LeadingCoefficientZero => write!(f, "Leading coefficient is zero"),
+            PolynomialError::NoConvergence => write!(f, "Could not find roots"),
+            PolynomialError::Overflow => write!(f, "Overflow"),
+        }
+    }
+}
+
+impl From<ComplexError> for PolynomialError {
+    fn from(_: ComplexError) -> Self {
+        PolynomialError::Overflow
+    }
+}
+
+const MAX_ITERATIONS: usize = 500;
+
+/// The number of decimal places roots are rounded to. Repeated roots converge slowly and only to
+/// around half of `Decimal`'s precision, so this is lower than for other complex results.
+const ROOT_DP: u32 = 10;
+
+/// Evaluates a monic polynomial at `z`, given its coefficients from the second-highest power down.
+fn evaluate_monic(coefficients: &[Complex], z: &Complex) -> Result<Complex, ComplexError> {
+    coefficients.iter().try_fold(Complex::from_real(Decimal::ONE), |acc, c| acc.checked_mul(z)?.checked_add(c))
+}
+
+/// Finds every complex root of a polynomial, given its coefficients from the highest power down,
+/// using the Durand-Kerner method. Real roots are sorted first, in ascending order.
+pub fn polynomial_roots(coefficients: &[Decimal]) -> Result<Vec<Complex>, PolynomialError> {
+    let leading = Complex::from_real(coefficients[0]);
+    if leading.re.is_zero() {
+        return Err(PolynomialError::LeadingCoefficientZero);
+    }
+    let monic = coefficients[1..].iter()
+        .map(|c| Complex::from_real(*c).checked_div(&leading))
+        .collect::<Result<Vec<_>, _>>()?;
+    let degree = monic.len();
+
+    // Start from powers of a number which is neither real nor a root of unity, so that no two
+    // guesses are symmetric
+    let seed = Complex::new(Decimal::new(4, 1), Decimal::new(9, 1));
+    let mut roots = vec![Complex::from_real(Decimal::ONE)];
+    for i in 1..degree {
+        roots.push(roots[i - 1].checked_mul(&seed)?);
+    }
+
+    let tolerance = Decimal::new(1, 24);
+    let mut converged = false;
+    for _ in 0..MAX_ITERATIONS {
+        let mut largest_change = Decimal::ZERO;
+        for i in 0..degree {
+            let mut denominator = Complex::from_real(Decimal::ONE);
+            for j in 0..degree {
+                if i != j {
+                    denominator = denominator.checked_mul(&roots[i].checked_sub(&roots[j])?)?;
+                }
+            }
+
+            let change = evaluate_monic(&monic, &roots[i])?.checked_div(&denominator)?;
+            roots[i] = roots[i].checked_sub(&change)?;
+            largest_change = largest_change.max(change.norm_sqr()?);
+        }
+
+        if largest_change < tolerance {
+            converged = true;
+            break;
+        }
+    }
+    if !converged {
+        return Err(PolynomialError::NoConvergence);
+    }
+
+    let mut roots = roots.iter().map(|root| Complex::new(
+        root.re.round_dp(ROOT_DP).normalize(),
+        root.im.round_dp(ROOT_DP).normalize(),
+    )).collect::<Vec<_>>();
+    roots.sort_by(|a, b|
+        (!a.is_real(), a.re, a.im.is_negative()).cmp(&(!b.is_real(), b.re, b.im.is_negative()))
+    );
+    Ok(roots)
+}
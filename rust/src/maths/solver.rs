@@ -0,0 +1,236 @@
+use core::fmt::Display;
+use alloc::{vec, vec::Vec};
+use rbop::{Number, error::MathsError, node::compiled::CompiledNode, serialize::Serializable};
+use rust_decimal::{Decimal, prelude::Zero};
+
+use super::format_number;
+
+/// An error encountered while numerically solving an equation.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum SolverError {
+    /// The iteration did not settle on a solution.
+    NoConvergence,
+
+    /// The iteration left the bounds it was given without finding a solution.
+    OutOfBounds,
+
+    /// The lower bound is not below the upper bound.
+    InvalidBounds,
+
+    /// The equation could not be evaluated at some point.
+    Evaluation(MathsError),
+}
+
+impl Display for SolverError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SolverError::NoConvergence => write!(f, "Solver did not converge"),
+            SolverError::OutOfBounds => write!(f, "No solution found within bounds"),
+            SolverError::InvalidBounds => write!(f, "Lower bound must be below upper bound"),
+            SolverError::Evaluation(e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+impl Serializable for SolverError {
+    fn serialize(&self) -> Vec<u8> {
+        match self {
+            SolverError::NoConvergence => vec![0],
+            SolverError::OutOfBounds => vec![1],
+            SolverError::InvalidBounds => vec![2],
+            SolverError::Evaluation(e) => {
+                let mut bytes = vec![3];
+                bytes.append(&mut e.serialize());
+                bytes
+            }
+        }
+    }
+
+    fn deserialize(bytes: &mut dyn Iterator<Item = u8>) -> Option<Self> {
+        match bytes.next()? {
+            0 => Some(SolverError::NoConvergence),
+            1 => Some(SolverError::OutOfBounds),
+            2 => Some(SolverError::InvalidBounds),
+            3 => Some(SolverError::Evaluation(MathsError::deserialize(bytes)?)),
+            _ => None,
+        }
+    }
+}
+
+impl From<MathsError> for SolverError {
+    fn from(e: MathsError) -> Self {
+        SolverError::Evaluation(e)
+    }
+}
+
+impl SolverError {
+    /// Converts this into the `MathsError` which is recorded in the calculation history. rbop has
+    /// no errors for a solver which fails to find a solution, so these become `Overflow`, and the
+    /// history shows the solver's own message from `FailedSolve` instead.
+    pub fn to_maths_error(&self) -> MathsError {
+        match self {
+            SolverError::Evaluation(e) => e.clone(),
+            SolverError::NoConvergence | SolverError::OutOfBounds | SolverError::InvalidBounds => MathsError::Overflow,
+        }
+    }
+}
+
+/// The inputs to a solve which failed, kept with its calculation history entry so that the
+/// failure can be described in full. The equation itself is the entry's expression.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct FailedSolve {
+    pub guess: Number,
+    pub bounds: Option<(Number, Number)>,
+    pub error: SolverError,
+}
+
+impl Display for FailedSolve {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}, from x={}", self.error, format_number(&self.guess))?;
+        if let Some((lower, upper)) = &self.bounds {
+            write!(f, " between {} and {}", format_number(lower), format_number(upper))?;
+        }
+        Ok(())
+    }
+}
+
+impl Serializable for FailedSolve {
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = self.guess.serialize();
+        match &self.bounds {
+            Some((lower, upper)) => {
+                bytes.push(1);
+                bytes.append(&mut lower.serialize());
+                bytes.append(&mut upper.serialize());
+            }
+            None => bytes.push(0),
+        }
+        bytes.append(&mut self.error.serialize());
+        bytes
+    }
+
+    fn deserialize(bytes: &mut dyn Iterator<Item = u8>) -> Option<Self> {
+        let guess = Number::deserialize(bytes)?;
+        let bounds = match bytes.next()? {
+            0 => None,
+            1 => Some((Number::deserialize(bytes)?, Number::deserialize(bytes)?)),
+            _ => return None,
+        };
+        Some(FailedSolve { guess, bounds, error: SolverError::deserialize(bytes)? })
+    }
+}
+
+type SolverResult<T> = Result<T, SolverError>;
+
+const MAX_ITERATIONS: usize = 100;
+
+/// The number of decimal places solutions are rounded to. Finite difference derivatives limit
+/// the attainable accuracy well below `Decimal`'s precision.
+const SOLUTION_DP: u32 = 12;
+
+fn overflow<T>(value: Option<T>) -> SolverResult<T> {
+    value.ok_or(SolverError::Evaluation(MathsError::Overflow))
+}
+
+/// An equation `f(x) = g(x)`, compiled so that it can be evaluated quickly for many values of
+/// `x`. Solving it means finding a root of `f(x) - g(x)`.
+pub struct Equation {
+    pub left: CompiledNode,
+    pub right: CompiledNode,
+}
+
+impl Equation {
+    /// Evaluates `f(x) - g(x)`.
+    pub fn evaluate(&self, x: Decimal) -> SolverResult<Decimal> {
+        let x = Number::from(x);
+        let left = self.left.evaluate_raw(x)?.to_decimal();
+        let right = self.right.evaluate_raw(x)?.to_decimal();
+        overflow(left.checked_sub(right))
+    }
+
+    /// Approximates the derivative of `f(x) - g(x)` with a central difference.
+    fn derivative(&self, x: Decimal) -> SolverResult<Decimal> {
+        let h = overflow(x.abs().max(Decimal::ONE).checked_mul(Decimal::new(1, 8)))?;
+        let rise = overflow(self.evaluate(overflow(x.checked_add(h))?)?.checked_sub(self.evaluate(overflow(x.checked_sub(h))?)?))?;
+        overflow(rise.checked_div(h * Decimal::TWO))
+    }
+
+    /// Finds a solution near `guess` using Newton's method.
+    ///
+    /// If `bounds` are given as `(lower, upper)`, the solution must lie between them. When the
+    /// equation changes sign between the bounds, Newton steps which would leave the bracketed
+    /// region are replaced with bisection, which guarantees convergence.
+    pub fn solve(&self, guess: Decimal, bounds: Option<(Decimal, Decimal)>) -> SolverResult<Decimal> {
+        let mut x = guess;
+        let mut bracket = None;
+        if let Some((lower, upper)) = bounds {
+            if lower >= upper {
+                return Err(SolverError::InvalidBounds);
+            }
+            x = x.max(lower).min(upper);
+
+            let (f_lower, f_upper) = (self.evaluate(lower)?, self.evaluate(upper)?);
+            if f_lower.is_zero() {
+                return Ok(Self::round(lower));
+            }
+            if f_upper.is_zero() {
+                return Ok(Self::round(upper));
+            }
+            if f_lower.is_sign_negative() != f_upper.is_sign_negative() {
+                bracket = Some((lower, upper, f_lower.is_sign_negative()));
+            }
+        }
+
+        for _ in 0..MAX_ITERATIONS {
+            let value = self.evaluate(x)?;
+            if value.is_zero() {
+                return Ok(Self::round(x));
+            }
+
+            // Narrow the bracket, keeping the sign change inside it
+            if let Some((lower, upper, lower_is_negative)) = bracket.as_mut() {
+                if value.is_sign_negative() == *lower_is_negative {
+                    *lower = x;
+                } else {
+                    *upper = x;
+                }
+            }
+
+            let derivative = self.derivative(x)?;
+            let newton = if derivative.is_zero() {
+                None
+            } else {
+                value.checked_div(derivative).and_then(|step| x.checked_sub(step))
+            };
+
+            let next = match (bracket, newton) {
+                (Some((lower, upper, _)), Some(next)) if next > lower && next < upper => next,
+                (Some((lower, upper, _)), _) => overflow(lower.checked_add(upper))? / Decimal::TWO,
+                (None, Some(next)) => next,
+                (None, None) => return Err(SolverError::NoConvergence),
+            };
+            if let Some((lower, upper)) = bounds {
+                if next < lower || next > upper {
+                    return Err(SolverError::OutOfBounds);
+                }
+            }
+
+            let tolerance = x.abs().max(Decimal::ONE) * Decimal::new(1, 15);
+            if overflow(next.checked_sub(x))?.abs() <= tolerance {
+                return Ok(Self::round(next));
+            }
+            if let Some((lower, upper, _)) = bracket {
+                if overflow(upper.checked_sub(lower))? <= tolerance {
+                    return Ok(Self::round(next));
+                }
+            }
+            x = next;
+        }
+
+        Err(SolverError::NoConvergence)
+    }
+
+    fn round(x: Decimal) -> Decimal {
+        x.round_dp(SOLUTION_DP).normalize()
+    }
+}
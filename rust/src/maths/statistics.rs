@@ -0,0 +1,436 @@
+use core::fmt::Display;
+use alloc::{vec, vec::Vec};
+use rbop::{Number, UnstructuredNode, UnstructuredNodeList, Token, node::unstructured::UnstructuredNodeRoot};
+use rust_decimal::{Decimal, MathematicalOps, prelude::Zero};
+
+use super::{Matrix, MatrixError, number_to_node};
+
+/// An error encountered while calculating statistics.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum StatisticsError {
+    /// There are too few data points for the calculation.
+    NotEnoughData,
+
+    /// Paired lists have different lengths.
+    MismatchedLengths,
+
+    /// A regression requires a logarithm of data containing zero or negative values.
+    NonPositiveData,
+
+    /// The data does not determine a unique fit, e.g. because every X value is the same.
+    Singular,
+
+    Overflow,
+}
+
+impl Display for StatisticsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            StatisticsError::NotEnoughData => write!(f, "Not enough data"),
+            StatisticsError::MismatchedLengths => write!(f, "Lists have different lengths"),
+            StatisticsError::NonPositiveData => write!(f, "Data must be positive"),
+            StatisticsError::Singular => write!(f, "No unique fit for data"),
+            StatisticsError::Overflow => write!(f, "Overflow"),
+        }
+    }
+}
+
+type StatisticsResult<T> = Result<T, StatisticsError>;
+
+fn add(a: Decimal, b: Decimal) -> StatisticsResult<Decimal> { a.checked_add(b).ok_or(StatisticsError::Overflow) }
+fn sub(a: Decimal, b: Decimal) -> StatisticsResult<Decimal> { a.checked_sub(b).ok_or(StatisticsError::Overflow) }
+fn mul(a: Decimal, b: Decimal) -> StatisticsResult<Decimal> { a.checked_mul(b).ok_or(StatisticsError::Overflow) }
+fn div(a: Decimal, b: Decimal) -> StatisticsResult<Decimal> {
+    if b.is_zero() {
+        return Err(StatisticsError::Singular);
+    }
+    a.checked_div(b).ok_or(StatisticsError::Overflow)
+}
+fn sqrt(a: Decimal) -> StatisticsResult<Decimal> { a.sqrt().ok_or(StatisticsError::Overflow) }
+fn exp(a: Decimal) -> StatisticsResult<Decimal> { a.checked_exp().ok_or(StatisticsError::Overflow) }
+fn ln(a: Decimal) -> StatisticsResult<Decimal> {
+    if a <= Decimal::ZERO {
+        return Err(StatisticsError::NonPositiveData);
+    }
+    a.checked_ln().ok_or(StatisticsError::Overflow)
+}
+
+fn sum(values: impl Iterator<Item = StatisticsResult<Decimal>>) -> StatisticsResult<Decimal> {
+    values.fold(Ok(Decimal::ZERO), |acc, x| add(acc?, x?))
+}
+
+/// The median of an already-sorted, non-empty slice.
+fn median(sorted: &[Decimal]) -> StatisticsResult<Decimal> {
+    let middle = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        div(add(sorted[middle - 1], sorted[middle])?, Decimal::TWO)
+    } else {
+        Ok(sorted[middle])
+    }
+}
+
+/// Summary statistics for a single list of data.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct OneVariableSummary {
+    pub n: usize,
+    pub mean: Decimal,
+    pub sum: Decimal,
+    pub sum_of_squares: Decimal,
+
+    /// The sample standard deviation, which is only defined when there are at least two values.
+    pub sample_sd: Option<Decimal>,
+    pub population_sd: Decimal,
+
+    pub min: Decimal,
+    pub q1: Decimal,
+    pub median: Decimal,
+    pub q3: Decimal,
+    pub max: Decimal,
+}
+
+impl OneVariableSummary {
+    pub fn calculate(data: &[Decimal]) -> StatisticsResult<Self> {
+        if data.is_empty() {
+            return Err(StatisticsError::NotEnoughData);
+        }
+
+        let n = Decimal::from(data.len());
+        let total = sum(data.iter().map(|x| Ok(*x)))?;
+        let mean = div(total, n)?;
+        let sum_of_squares = sum(data.iter().map(|x| mul(*x, *x)))?;
+        let deviations = sum(data.iter().map(|x| sub(*x, mean).and_then(|d| mul(d, d))))?;
+
+        let population_sd = sqrt(div(deviations, n)?)?;
+        let sample_sd = if data.len() >= 2 {
+            Some(sqrt(div(deviations, sub(n, Decimal::ONE)?)?)?)
+        } else {
+            None
+        };
+
+        // Quartiles are the medians of the lower and upper halves, excluding the overall median
+        // when there is an odd number of values
+        let mut sorted = data.to_vec();
+        sorted.sort_unstable();
+        let half = sorted.len() / 2;
+        let (lower, upper) = if sorted.len() == 1 {
+            (&sorted[..], &sorted[..])
+        } else {
+            (&sorted[..half], &sorted[(sorted.len() - half)..])
+        };
+
+        Ok(Self {
+            n: data.len(),
+            mean,
+            sum: total,
+            sum_of_squares,
+            sample_sd,
+            population_sd,
+            min: sorted[0],
+            q1: median(lower)?,
+            median: median(&sorted)?,
+            q3: median(upper)?,
+            max: sorted[sorted.len() - 1],
+        })
+    }
+
+    /// Labelled values for display, in order.
+    pub fn rows(&self) -> Vec<(&'static str, Option<Decimal>)> {
+        vec![
+            ("n", Some(Decimal::from(self.n))),
+            ("Mean", Some(self.mean)),
+            ("Sum", Some(self.sum)),
+            ("Sum x^2", Some(self.sum_of_squares)),
+            ("Sample SD", self.sample_sd),
+            ("Pop. SD", Some(self.population_sd)),
+            ("Min", Some(self.min)),
+            ("Q1", Some(self.q1)),
+            ("Median", Some(self.median)),
+            ("Q3", Some(self.q3)),
+            ("Max", Some(self.max)),
+        ]
+    }
+}
+
+/// Summary statistics for a pair of lists, treated as X and Y values of points.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct TwoVariableSummary {
+    pub n: usize,
+    pub mean_x: Decimal,
+    pub mean_y: Decimal,
+    pub sum_x: Decimal,
+    pub sum_y: Decimal,
+    pub sum_x_squared: Decimal,
+    pub sum_y_squared: Decimal,
+    pub sum_xy: Decimal,
+
+    /// The correlation coefficient, which is undefined if either list has no variation.
+    pub r: Option<Decimal>,
+}
+
+impl TwoVariableSummary {
+    pub fn calculate(xs: &[Decimal], ys: &[Decimal]) -> StatisticsResult<Self> {
+        check_paired(xs, ys, 1)?;
+
+        let n = Decimal::from(xs.len());
+        let pairs = || xs.iter().zip(ys.iter());
+        let sum_x = sum(xs.iter().map(|x| Ok(*x)))?;
+        let sum_y = sum(ys.iter().map(|y| Ok(*y)))?;
+        let mean_x = div(sum_x, n)?;
+        let mean_y = div(sum_y, n)?;
+
+        let sxx = sum(xs.iter().map(|x| sub(*x, mean_x).and_then(|d| mul(d, d))))?;
+        let syy = sum(ys.iter().map(|y| sub(*y, mean_y).and_then(|d| mul(d, d))))?;
+        let sxy = sum(pairs().map(|(x, y)| mul(sub(*x, mean_x)?, sub(*y, mean_y)?)))?;
+        let r = if sxx.is_zero() || syy.is_zero() {
+            None
+        } else {
+            Some(div(sxy, sqrt(mul(sxx, syy)?)?)?)
+        };
+
+        Ok(Self {
+            n: xs.len(),
+            mean_x,
+            mean_y,
+            sum_x,
+            sum_y,
+            sum_x_squared: sum(xs.iter().map(|x| mul(*x, *x)))?,
+            sum_y_squared: sum(ys.iter().map(|y| mul(*y, *y)))?,
+            sum_xy: sum(pairs().map(|(x, y)| mul(*x, *y)))?,
+            r,
+        })
+    }
+
+    /// Labelled values for display, in order.
+    pub fn rows(&self) -> Vec<(&'static str, Option<Decimal>)> {
+        vec![
+            ("n", Some(Decimal::from(self.n))),
+            ("Mean x", Some(self.mean_x)),
+            ("Mean y", Some(self.mean_y)),
+            ("Sum x", Some(self.sum_x)),
+            ("Sum y", Some(self.sum_y)),
+            ("Sum x^2", Some(self.sum_x_squared)),
+            ("Sum y^2", Some(self.sum_y_squared)),
+            ("Sum xy", Some(self.sum_xy)),
+            ("r", self.r),
+        ]
+    }
+}
+
+/// Checks that two lists are paired and contain at least `minimum` points.
+fn check_paired(xs: &[Decimal], ys: &[Decimal], minimum: usize) -> StatisticsResult<()> {
+    if xs.len() != ys.len() {
+        Err(StatisticsError::MismatchedLengths)
+    } else if xs.len() < minimum {
+        Err(StatisticsError::NotEnoughData)
+    } else {
+        Ok(())
+    }
+}
+
+/// The kinds of curve which a regression can fit.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum RegressionModel {
+    /// `y = ax + b`
+    Linear,
+
+    /// `y = ax^2 + bx + c`
+    Quadratic,
+
+    /// `y = a b^x`
+    Exponential,
+
+    /// `y = a + b ln x`
+    Logarithmic,
+
+    /// `y = a x^b`
+    Power,
+}
+
+impl RegressionModel {
+    pub const ALL: [RegressionModel; 5] = [
+        RegressionModel::Linear,
+        RegressionModel::Quadratic,
+        RegressionModel::Exponential,
+        RegressionModel::Logarithmic,
+        RegressionModel::Power,
+    ];
+
+    /// The general form of this model's equation.
+    pub fn equation(&self) -> &'static str {
+        match self {
+            RegressionModel::Linear => "y = ax + b",
+            RegressionModel::Quadratic => "y = ax^2 + bx + c",
+            RegressionModel::Exponential => "y = a * b^x",
+            RegressionModel::Logarithmic => "y = a + b ln x",
+            RegressionModel::Power => "y = a * x^b",
+        }
+    }
+
+    /// The names of this model's coefficients, in the order they appear in `equation`.
+    pub fn coefficient_names(&self) -> &'static [&'static str] {
+        match self {
+            RegressionModel::Quadratic => &["a", "b", "c"],
+            _ => &["a", "b"],
+        }
+    }
+}
+
+impl Display for RegressionModel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            RegressionModel::Linear => write!(f, "Linear"),
+            RegressionModel::Quadratic => write!(f, "Quadratic"),
+            RegressionModel::Exponential => write!(f, "Exponential"),
+            RegressionModel::Logarithmic => write!(f, "Logarithmic"),
+            RegressionModel::Power => write!(f, "Power"),
+        }
+    }
+}
+
+/// A curve fitted to paired data.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Regression {
+    pub model: RegressionModel,
+
+    /// The coefficients of the model, in the same order as `RegressionModel::coefficient_names`.
+    pub coefficients: Vec<Decimal>,
+
+    /// The coefficient of determination, calculated against the original (not linearised) data.
+    /// Undefined if every Y value is the same.
+    pub r_squared: Option<Decimal>,
+}
+
+/// Fits `y = slope * x + intercept` by least squares, returning `(slope, intercept)`.
+fn least_squares(xs: &[Decimal], ys: &[Decimal]) -> StatisticsResult<(Decimal, Decimal)> {
+    let n = Decimal::from(xs.len());
+    let mean_x = div(sum(xs.iter().map(|x| Ok(*x)))?, n)?;
+    let mean_y = div(sum(ys.iter().map(|y| Ok(*y)))?, n)?;
+    let sxx = sum(xs.iter().map(|x| sub(*x, mean_x).and_then(|d| mul(d, d))))?;
+    let sxy = sum(xs.iter().zip(ys.iter()).map(|(x, y)| mul(sub(*x, mean_x)?, sub(*y, mean_y)?)))?;
+
+    let slope = div(sxy, sxx)?;
+    Ok((slope, sub(mean_y, mul(slope, mean_x)?)?))
+}
+
+/// Fits `y = ax^2 + bx + c` by solving the least-squares normal equations, returning `[a, b, c]`.
+fn quadratic_least_squares(xs: &[Decimal], ys: &[Decimal]) -> StatisticsResult<Vec<Decimal>> {
+    // Sums of x^0 to x^4, and of y * x^0 to y * x^2
+    let mut power_sums = [Decimal::ZERO; 5];
+    let mut y_sums = [Decimal::ZERO; 3];
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let mut power = Decimal::ONE;
+        for i in 0..5 {
+            power_sums[i] = add(power_sums[i], power)?;
+            if i < 3 {
+                y_sums[i] = add(y_sums[i], mul(power, *y)?)?;
+            }
+            power = mul(power, *x)?;
+        }
+    }
+
+    let number = |d: Decimal| Number::from(d);
+    let system = Matrix::from_rows((0..3).map(|r|
+        (0..3).map(|c| number(power_sums[4 - r - c])).collect()
+    ).collect());
+    let rhs = Matrix::from_rows((0..3).map(|r| vec![number(y_sums[2 - r])]).collect());
+
+    let solution = system.inverse()
+        .and_then(|inverse| inverse.multiply(&rhs))
+        .map_err(|e| match e {
+            MatrixError::Singular => StatisticsError::Singular,
+            _ => StatisticsError::Overflow,
+        })?;
+    Ok((0..3).map(|r| solution[(r, 0)].to_decimal()).collect())
+}
+
+impl Regression {
+    /// Fits the given model to paired data.
+    pub fn fit(model: RegressionModel, xs: &[Decimal], ys: &[Decimal]) -> StatisticsResult<Self> {
+        let minimum = if model == RegressionModel::Quadratic { 3 } else { 2 };
+        check_paired(xs, ys, minimum)?;
+
+        let ln_all = |values: &[Decimal]| values.iter().map(|v| ln(*v)).collect::<StatisticsResult<Vec<_>>>();
+        let coefficients = match model {
+            RegressionModel::Linear => {
+                let (slope, intercept) = least_squares(xs, ys)?;
+                vec![slope, intercept]
+            }
+            RegressionModel::Quadratic => quadratic_least_squares(xs, ys)?,
+            RegressionModel::Exponential => {
+                // ln y = ln a + x ln b
+                let (slope, intercept) = least_squares(xs, &ln_all(ys)?)?;
+                vec![exp(intercept)?, exp(slope)?]
+            }
+            RegressionModel::Logarithmic => {
+                let (slope, intercept) = least_squares(&ln_all(xs)?, ys)?;
+                vec![intercept, slope]
+            }
+            RegressionModel::Power => {
+                // ln y = ln a + b ln x
+                let (slope, intercept) = least_squares(&ln_all(xs)?, &ln_all(ys)?)?;
+                vec![exp(intercept)?, slope]
+            }
+        };
+
+        let mut regression = Self { model, coefficients, r_squared: None };
+
+        let mean_y = div(sum(ys.iter().map(|y| Ok(*y)))?, Decimal::from(ys.len()))?;
+        let total = sum(ys.iter().map(|y| sub(*y, mean_y).and_then(|d| mul(d, d))))?;
+        let residual = sum(xs.iter().zip(ys.iter()).map(|(x, y)|
+            sub(*y, regression.evaluate(*x)?).and_then(|d| mul(d, d))
+        ))?;
+        if !total.is_zero() {
+            regression.r_squared = Some(sub(Decimal::ONE, div(residual, total)?)?);
+        }
+
+        Ok(regression)
+    }
+
+    /// Evaluates the fitted curve at a given X value.
+    pub fn evaluate(&self, x: Decimal) -> StatisticsResult<Decimal> {
+        let c = &self.coefficients;
+        match self.model {
+            RegressionModel::Linear => add(mul(c[0], x)?, c[1]),
+            RegressionModel::Quadratic => add(mul(add(mul(c[0], x)?, c[1])?, x)?, c[2]),
+            RegressionModel::Exponential => mul(c[0], exp(mul(x, ln(c[1])?)?)?),
+            RegressionModel::Logarithmic => add(c[0], mul(c[1], ln(x)?)?),
+            RegressionModel::Power => mul(c[0], exp(mul(c[1], ln(x)?)?)?),
+        }
+    }
+
+    /// Builds an expression in terms of `x` for the fitted curve, for displaying alongside a plot of
+    /// it.
+    ///
+    /// rbop has no logarithm function, so the logarithmic model's `ln` is written as the letters
+    /// `l` and `n`. This is only for display - plot the curve with `evaluate` instead of compiling
+    /// this.
+    pub fn to_unstructured(&self) -> UnstructuredNodeRoot {
+        let coefficient = |i: usize| number_to_node(Number::from(self.coefficients[i].round_dp(10).normalize()));
+        let x = || UnstructuredNode::Token(Token::Variable('x'));
+        let token = UnstructuredNode::Token;
+        let list = |items| UnstructuredNodeList { items };
+
+        let items = match self.model {
+            RegressionModel::Linear => vec![
+                coefficient(0), token(Token::Multiply), x(), token(Token::Add), coefficient(1),
+            ],
+            RegressionModel::Quadratic => vec![
+                coefficient(0), token(Token::Multiply), x(), UnstructuredNode::Power(list(vec![token(Token::Digit(2))])),
+                token(Token::Add), coefficient(1), token(Token::Multiply), x(),
+                token(Token::Add), coefficient(2),
+            ],
+            RegressionModel::Exponential => vec![
+                coefficient(0), token(Token::Multiply), coefficient(1), UnstructuredNode::Power(list(vec![x()])),
+            ],
+            RegressionModel::Logarithmic => vec![
+                coefficient(0), token(Token::Add), coefficient(1), token(Token::Multiply),
+                token(Token::Variable('l')), token(Token::Variable('n')), UnstructuredNode::Parentheses(list(vec![x()])),
+            ],
+            RegressionModel::Power => vec![
+                coefficient(0), token(Token::Multiply), x(), UnstructuredNode::Power(list(vec![coefficient(1)])),
+            ],
+        };
+
+        UnstructuredNodeRoot { root: list(items) }
+    }
+}
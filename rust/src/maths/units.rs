@@ -0,0 +1,160 @@
+use rust_decimal::Decimal;
+
+use super::decimal_from_scientific;
+
+/// A unit of measurement, defined by how to convert it into the base unit of its category.
+///
+/// A value `v` in this unit is equal to `(v + offset) * factor / divisor` in the base unit. Most
+/// units only need a `factor`; `offset` and `divisor` exist for temperatures and for factors which
+/// aren't exact decimals.
+#[derive(Debug, Clone, Copy)]
+pub struct Unit {
+    pub name: &'static str,
+    pub symbol: &'static str,
+    factor: (i64, i32),
+    divisor: i64,
+    offset: (i64, i32),
+}
+
+impl Unit {
+    /// A unit which is `mantissa * 10^exponent` base units.
+    pub const fn new(name: &'static str, symbol: &'static str, mantissa: i64, exponent: i32) -> Self {
+        Self { name, symbol, factor: (mantissa, exponent), divisor: 1, offset: (0, 0) }
+    }
+
+    /// A unit which is `factor / divisor` base units, once `offset` has been added to it.
+    pub const fn new_affine(name: &'static str, symbol: &'static str, offset: (i64, i32), factor: (i64, i32), divisor: i64) -> Self {
+        Self { name, symbol, factor, divisor, offset }
+    }
+
+    /// Converts a value in this unit into the base unit of its category. Returns `None` if the
+    /// calculation overflows.
+    pub fn to_base(&self, value: Decimal) -> Option<Decimal> {
+        value
+            .checked_add(decimal_from_scientific(self.offset.0, self.offset.1))?
+            .checked_mul(decimal_from_scientific(self.factor.0, self.factor.1))?
+            .checked_div(Decimal::from(self.divisor))
+    }
+
+    /// Converts a value in the base unit of this unit's category into this unit. Returns `None` if
+    /// the calculation overflows.
+    pub fn from_base(&self, value: Decimal) -> Option<Decimal> {
+        value
+            .checked_mul(Decimal::from(self.divisor))?
+            .checked_div(decimal_from_scientific(self.factor.0, self.factor.1))?
+            .checked_sub(decimal_from_scientific(self.offset.0, self.offset.1))
+    }
+
+    /// Converts a value in this unit into `other`, which must be in the same category.
+    pub fn convert(&self, value: Decimal, other: &Unit) -> Option<Decimal> {
+        other.from_base(self.to_base(value)?)
+    }
+}
+
+/// A group of units which measure the same quantity, and can therefore be converted between.
+#[derive(Debug, Clone, Copy)]
+pub struct UnitCategory {
+    pub name: &'static str,
+    pub units: &'static [Unit],
+}
+
+/// All unit categories. The first unit in each category is its base unit.
+pub const UNIT_CATEGORIES: &[UnitCategory] = &[
+    UnitCategory {
+        name: "Length",
+        units: &[
+            Unit::new("metre", "m", 1, 0),
+            Unit::new("kilometre", "km", 1, 3),
+            Unit::new("centimetre", "cm", 1, -2),
+            Unit::new("millimetre", "mm", 1, -3),
+            Unit::new("micrometre", "um", 1, -6),
+            Unit::new("nanometre", "nm", 1, -9),
+            Unit::new("inch", "in", 254, -4),
+            Unit::new("foot", "ft", 3048, -4),
+            Unit::new("yard", "yd", 9144, -4),
+            Unit::new("mile", "mi", 1609344, -3),
+            Unit::new("nautical mile", "nmi", 1852, 0),
+            Unit::new("astronomical unit", "au", 149597870700, 0),
+            Unit::new("light-year", "ly", 9460730472580800, 0),
+        ],
+    },
+    UnitCategory {
+        name: "Mass",
+        units: &[
+            Unit::new("kilogram", "kg", 1, 0),
+            Unit::new("gram", "g", 1, -3),
+            Unit::new("milligram", "mg", 1, -6),
+            Unit::new("tonne", "t", 1, 3),
+            Unit::new("ounce", "oz", 28349523125, -12),
+            Unit::new("pound", "lb", 45359237, -8),
+            Unit::new("stone", "st", 635029318, -8),
+            Unit::new("short ton", "tn", 90718474, -5),
+            Unit::new("long ton", "LT", 10160469088, -7),
+        ],
+    },
+    UnitCategory {
+        name: "Time",
+        units: &[
+            Unit::new("second", "s", 1, 0),
+            Unit::new("nanosecond", "ns", 1, -9),
+            Unit::new("microsecond", "us", 1, -6),
+            Unit::new("millisecond", "ms", 1, -3),
+            Unit::new("minute", "min", 60, 0),
+            Unit::new("hour", "h", 3600, 0),
+            Unit::new("day", "d", 86400, 0),
+            Unit::new("week", "wk", 604800, 0),
+            Unit::new("year (365.25 d)", "yr", 31557600, 0),
+        ],
+    },
+    UnitCategory {
+        name: "Temperature",
+        units: &[
+            Unit::new("kelvin", "K", 1, 0),
+            Unit::new_affine("degree Celsius", "C", (27315, -2), (1, 0), 1),
+            Unit::new_affine("degree Fahrenheit", "F", (45967, -2), (5, 0), 9),
+            Unit::new_affine("degree Rankine", "R", (0, 0), (5, 0), 9),
+        ],
+    },
+    UnitCategory {
+        name: "Energy",
+        units: &[
+            Unit::new("joule", "J", 1, 0),
+            Unit::new("kilojoule", "kJ", 1, 3),
+            Unit::new("megajoule", "MJ", 1, 6),
+            Unit::new("calorie", "cal", 4184, -3),
+            Unit::new("kilocalorie", "kcal", 4184, 0),
+            Unit::new("watt-hour", "Wh", 3600, 0),
+            Unit::new("kilowatt-hour", "kWh", 3600000, 0),
+            Unit::new("electronvolt", "eV", 1602176634, -28),
+            Unit::new("British thermal unit", "BTU", 105505585262, -8),
+        ],
+    },
+    UnitCategory {
+        name: "Pressure",
+        units: &[
+            Unit::new("pascal", "Pa", 1, 0),
+            Unit::new("kilopascal", "kPa", 1, 3),
+            Unit::new("bar", "bar", 1, 5),
+            Unit::new("millibar", "mbar", 1, 2),
+            Unit::new("atmosphere", "atm", 101325, 0),
+            Unit::new("pound per square inch", "psi", 6894757293168, -9),
+            Unit::new("millimetre of mercury", "mmHg", 133322387415, -9),
+            Unit::new_affine("torr", "Torr", (0, 0), (101325, 0), 760),
+        ],
+    },
+    UnitCategory {
+        name: "Data size",
+        units: &[
+            Unit::new("byte", "B", 1, 0),
+            Unit::new("bit", "b", 125, -3),
+            Unit::new("kilobyte", "kB", 1, 3),
+            Unit::new("megabyte", "MB", 1, 6),
+            Unit::new("gigabyte", "GB", 1, 9),
+            Unit::new("terabyte", "TB", 1, 12),
+            Unit::new("kibibyte", "KiB", 1024, 0),
+            Unit::new("mebibyte", "MiB", 1048576, 0),
+            Unit::new("gibibyte", "GiB", 1073741824, 0),
+            Unit::new("tebibyte", "TiB", 1099511627776, 0),
+        ],
+    },
+];
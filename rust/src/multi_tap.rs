@@ -19,6 +19,9 @@ const PRESS_COOLDOWN_MS: u64 = 750;
 //   4   5   6       4   5   6
 //   7   8   9       1   2   3
 //   *   0   #       0   .   ^
+// Like a phone, the key in the "1" position gives punctuation, and 0 gives a space.
+const ZERO_CHAR_LIST:  [char; 1] = [' '];
+const SEVEN_CHAR_LIST: [char; 10] = ['.', ',', '?', '!', '\'', '"', ':', '=', '<', '>'];
 const EIGHT_CHAR_LIST: [char; 3] = ['a', 'b', 'c'];
 const NINE_CHAR_LIST:  [char; 3] = ['d', 'e', 'f'];
 const FOUR_CHAR_LIST:  [char; 3] = ['g', 'h', 'i'];
@@ -53,8 +56,10 @@ impl<F: ApplicationFramework> MultiTapState<F> {
             self.last_press_ms = now_ms;
 
             // Did the user press the same digit again?
+            // (Keys with only one character always start a new one, so that e.g. spaces can be
+            // typed in quick succession)
             if let Some(current_digit) = &mut self.current_digit {
-                if digit == *current_digit {
+                if digit == *current_digit && self.current_list.unwrap().len() > 1 {
                     // Increment current list index, wrapping if necessary
                     self.current_index = Some(
                         (self.current_index.unwrap() + 1) % self.current_list.unwrap().len()
@@ -73,8 +78,8 @@ impl<F: ApplicationFramework> MultiTapState<F> {
             // If we didn't return, we pressed our first digit, or a different digit than the
             // last - switch to new digit list
             self.current_list = Some(match digit {
-                0 => return None,
-                7 => return None,
+                0 => &ZERO_CHAR_LIST,
+                7 => &SEVEN_CHAR_LIST,
 
                 1 => &ONE_CHAR_LIST,
                 2 => &TWO_CHAR_LIST,
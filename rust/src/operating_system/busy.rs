@@ -0,0 +1,108 @@
+use alloc::{format, string::String, vec};
+
+use crate::interface::{ApplicationFramework, ButtonInput, Colour, ShapeFill};
+
+use super::{OperatingSystem, OSInput};
+
+/// Long-running work which was started with `begin_busy`.
+pub struct BusyState {
+    pub message: String,
+    pub started_millis: u64,
+
+    /// When the busy indicator was last drawn, or `None` if it hasn't been yet.
+    pub drawn_millis: Option<u64>,
+}
+
+impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
+    /// How long work must run before the busy indicator appears, so that quick work doesn't make
+    /// the screen flicker.
+    pub const BUSY_INDICATOR_DELAY_MILLIS: u64 = 250;
+
+    /// How often the busy indicator is redrawn, at most.
+    pub const BUSY_INDICATOR_INTERVAL_MILLIS: u64 = 100;
+
+    /// Starts some long-running work, which should then call `busy_tick` regularly until it's
+    /// finished or cancelled, followed by `end_busy`.
+    pub fn begin_busy(&mut self, message: &str) {
+        self.busy = Some(BusyState {
+            message: message.into(),
+            started_millis: self.framework.millis(),
+            drawn_millis: None,
+        });
+    }
+
+    /// Called regularly during long-running work. Once the work has taken a while, draws the busy
+    /// indicator at the bottom of the screen, with a progress bar if `progress` is given as
+    /// (done, total), and checks whether the user wants to stop. Returns true if they've pressed AC
+    /// or opened the menu, in which case the work should stop.
+    ///
+    /// Quick work never checks for cancellation, so that it doesn't take an AC press meant for
+    /// whatever comes after it.
+    pub fn busy_tick(&mut self, progress: Option<(usize, usize)>) -> bool {
+        let now = self.framework.millis();
+        let started_millis = match &self.busy {
+            Some(busy) => busy.started_millis,
+            None => return false,
+        };
+        if now - started_millis < Self::BUSY_INDICATOR_DELAY_MILLIS {
+            return false;
+        }
+        if self.cancel_requested() {
+            return true;
+        }
+
+        let busy = self.busy.as_mut().unwrap();
+        if busy.drawn_millis.map(|drawn| now - drawn < Self::BUSY_INDICATOR_INTERVAL_MILLIS).unwrap_or(false) {
+            return false;
+        }
+        busy.drawn_millis = Some(now);
+        let message = format!("{}...", busy.message);
+
+        const HEIGHT: u16 = 50;
+        const PADDING: u16 = 8;
+        let width = self.display_sprite.width;
+        let y = (self.display_sprite.height - HEIGHT) as i16;
+
+        self.display_sprite.draw_rect(0, y, width, HEIGHT, Colour::GREY, ShapeFill::Filled, 0);
+        self.display_sprite.draw_rect(0, y, width, HEIGHT, Colour::WHITE, ShapeFill::Hollow, 0);
+        self.display_sprite.print_at(PADDING as i16, y + PADDING as i16, &message);
+        self.display_sprite.print_at(width as i16 - 100, y + PADDING as i16, "[AC] Stop");
+
+        if let Some((done, total)) = progress && total > 0 {
+            let bar_y = y + HEIGHT as i16 - PADDING as i16 - 8;
+            let bar_width = width - PADDING * 2;
+            self.display_sprite.draw_rect(PADDING as i16, bar_y, bar_width, 8, Colour::BLACK, ShapeFill::Filled, 0);
+            let done_width = (bar_width as usize * done.min(total) / total) as u16;
+            if done_width > 0 {
+                self.display_sprite.draw_rect(PADDING as i16, bar_y, done_width, 8, Colour::BLUE, ShapeFill::Filled, 0);
+            }
+        }
+
+        self.draw();
+        false
+    }
+
+    /// Finishes the long-running work started with `begin_busy`. The application should redraw
+    /// afterwards to remove the busy indicator.
+    pub fn end_busy(&mut self) {
+        self.busy = None;
+    }
+
+    /// Checks, without waiting, whether AC has been pressed or the menu opened. Input is read as
+    /// `poll_input` would, including virtual presses and the host link. Other presses are put back,
+    /// so that `input` returns them once the long-running work is done.
+    pub fn cancel_requested(&mut self) -> bool {
+        let mut kept = vec![];
+        let mut cancelled = false;
+        while let Some(input) = self.check_input() {
+            if let Some(OSInput::Button(ButtonInput::Clear | ButtonInput::Menu) | OSInput::ShiftedButton(ButtonInput::Clear)) = input {
+                cancelled = true;
+                break;
+            }
+            kept.push(input);
+        }
+
+        self.virtual_input_queue.splice(0..0, kept);
+        cancelled
+    }
+}
@@ -0,0 +1,147 @@
+use alloc::{format, string::String, vec, vec::Vec};
+
+use crate::{interface::{ApplicationFramework, Colour, ShapeFill}, filesystem::ChunkIndex};
+
+use super::{OperatingSystemPointer, os_accessor};
+
+/// A scrolling list of the stored files with a particular extension, such as notes or programs.
+/// Files are shown and named without their extension, so an application can treat the files it
+/// owns as named documents.
+pub struct FileList<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+
+    /// The extension of the files in this list, including the dot, like `.txt`.
+    pub extension: &'static str,
+
+    /// What each file is called in messages, in lower case, like `note`.
+    pub noun: &'static str,
+
+    /// The index and name (without extension) of each file.
+    pub files: Vec<(ChunkIndex, String)>,
+    pub selected_index: usize,
+}
+
+os_accessor!(FileList<F>);
+
+impl<F: ApplicationFramework> FileList<F> {
+    const ROW_HEIGHT: i16 = 30;
+    const VISIBLE_ROWS: usize = 8;
+    const LIST_Y: i16 = 36;
+
+    pub fn new(os: OperatingSystemPointer<F>, extension: &'static str, noun: &'static str) -> Self {
+        let mut list = Self {
+            os,
+            extension,
+            noun,
+            files: vec![],
+            selected_index: 0,
+        };
+        list.load();
+        list
+    }
+
+    /// Reloads the list of files from storage, keeping the selection where possible.
+    pub fn load(&mut self) {
+        let extension = self.extension;
+        self.files = self.os_mut().filesystem.files.list_files()
+            .into_iter()
+            .filter_map(|(idx, name)| name.strip_suffix(extension).map(|name| (idx, name.into())))
+            .collect();
+        self.selected_index = self.selected_index.min(self.files.len().saturating_sub(1));
+    }
+
+    /// The index and name of the selected file, if there are any files.
+    pub fn selected(&self) -> Option<(ChunkIndex, String)> {
+        self.files.get(self.selected_index).cloned()
+    }
+
+    pub fn move_up(&mut self) {
+        self.selected_index = self.selected_index.saturating_sub(1);
+    }
+
+    pub fn move_down(&mut self) {
+        if self.selected_index + 1 < self.files.len() {
+            self.selected_index += 1;
+        }
+    }
+
+    /// The full name of the file with the given name, by adding the extension.
+    pub fn file_name(&self, name: &str) -> String {
+        format!("{}{}", name, self.extension)
+    }
+
+    /// Asks for a name for a file, returning `None` if the user cancelled or the name is already
+    /// taken.
+    pub fn input_name(&mut self, initial: &str) -> Option<String> {
+        let noun = self.noun;
+        let name = self.os_mut().ui_input_text(&format!("{} name", capitalise(noun)), initial)?;
+        let name = String::from(name.trim());
+        if name.is_empty() {
+            return None;
+        }
+
+        let file_name = self.file_name(&name);
+        if self.os_mut().filesystem.files.find_file(&file_name).is_some() {
+            self.os_mut().ui_text_dialog(&format!("A {} with this name already exists", noun));
+            return None;
+        }
+
+        Some(name)
+    }
+
+    /// Asks for a new name for the selected file, then renames it.
+    pub fn rename_selected(&mut self) {
+        let (idx, name) = if let Some(selected) = self.selected() { selected } else { return };
+        if let Some(new_name) = self.input_name(&name) {
+            let file_name = self.file_name(&new_name);
+            if self.os_mut().filesystem.files.rename_file(idx, &file_name).is_none() {
+                let noun = self.noun;
+                self.os_mut().ui_text_dialog(&format!("Could not rename {}, storage may be full", noun));
+            }
+            self.load();
+        }
+    }
+
+    /// Deletes the selected file.
+    pub fn delete_selected(&mut self) {
+        if let Some((idx, _)) = self.selected() {
+            self.os_mut().filesystem.files.delete_file(idx);
+            self.load();
+        }
+    }
+
+    /// Draws the list under a title bar, with a hint about which keys to press at the bottom, and
+    /// then updates the screen.
+    pub fn draw(&mut self, title: &str, hint: &str) {
+        let noun = self.noun;
+        let mut os = self.os;
+        os.display_sprite.fill(Colour::BLACK);
+        os.ui_draw_title(title);
+
+        if self.files.is_empty() {
+            os.display_sprite.print_at(10, Self::LIST_Y + 10, &format!("No {}s yet", noun));
+        }
+
+        let first_row = self.selected_index.saturating_sub(Self::VISIBLE_ROWS - 1);
+        for (i, (_, name)) in self.files.iter().enumerate().skip(first_row).take(Self::VISIBLE_ROWS) {
+            let y = Self::LIST_Y + (i - first_row) as i16 * Self::ROW_HEIGHT;
+            if i == self.selected_index {
+                os.display_sprite.draw_rect(5, y, 230, Self::ROW_HEIGHT as u16, Colour::BLUE, ShapeFill::Filled, 7);
+            }
+            let name = os.display_sprite.fit_text(name, 210);
+            os.display_sprite.print_at(15, y + 4, &name);
+        }
+
+        os.display_sprite.print_at(5, 290, hint);
+        os.draw();
+    }
+}
+
+/// Returns a word with its first letter in upper case.
+fn capitalise(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
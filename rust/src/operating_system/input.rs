@@ -8,6 +8,37 @@ pub enum OSInput {
     ShiftedButton(ButtonInput),
     TextMultiTapNew(char),
     TextMultiTapCycle(char),
+
+    /// A button which doesn't auto-repeat was held down for longer than the `long_press_time`
+    /// setting. This fires in addition to the button's usual input, unless buttons fire on
+    /// release, in which case it fires instead.
+    LongPress(ButtonInput),
+}
+
+/// A button which is being held down on the keypad.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct HeldButton {
+    pub button: ButtonInput,
+
+    /// When the button should next fire by itself, by repeating or as a long press, in
+    /// milliseconds. `None` once a long press has fired.
+    pub next_fire_millis: Option<u64>,
+
+    /// Whether the button has fired by itself while held, in which case its release doesn't.
+    pub fired_while_held: bool,
+}
+
+/// The OS' record of recent events from the keypad, used to decide which of them fire.
+#[derive(Default)]
+pub struct KeypadState {
+    /// The button most recently released, and the time it was released in milliseconds.
+    pub last_release: Option<(ButtonInput, u64)>,
+
+    /// A button whose press was ignored as switch bounce, so its release should be too.
+    pub bounced_button: Option<ButtonInput>,
+
+    /// The button currently held down, if any.
+    pub held: Option<HeldButton>,
 }
 
 impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
@@ -57,19 +88,131 @@ impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
     /// application to tick and redraw.
     /// 
     /// Alternatively, if virtual presses have been queued with `queue_virtual_presses` as part of a
-    /// test, pops the queue and returns the next one.
+    /// test, or injected by the host link, pops the queue and returns the next one.
+    /// 
+    /// The host link is serviced while waiting, and held buttons auto-repeat or long-press. When
+    /// there's nothing to check for until the next event, the framework is left idle.
     pub fn input(&mut self) -> Option<OSInput> {
+        loop {
+            if let Some(input) = self.check_input() {
+                return input;
+            }
+
+            // A held button which is due to fire by itself has to be checked on time, and the host
+            // link may have queued virtual presses
+            let held_button_due = self.keypad.held.as_ref().and_then(|held| held.next_fire_millis).is_some();
+            if !held_button_due && self.virtual_input_queue.is_empty() {
+                self.framework.idle();
+            }
+        }
+    }
+
+    /// Like `input`, but returns `None` straight away if no key has been pressed, rather than
+    /// waiting. This lets applications keep animating or computing while watching for input.
+    pub fn poll_input(&mut self) -> Option<OSInput> {
+        self.check_input().flatten()
+    }
+
+    /// Checks for input once, without waiting. Returns `Some` with what `input` should return if
+    /// there was any, otherwise `None`.
+    pub(super) fn check_input(&mut self) -> Option<Option<OSInput>> {
         if let Some(input) = self.virtual_input_queue.get(0).cloned() {
             self.virtual_input_queue.remove(0);
-            return input;
+            return Some(input);
         }
 
-        loop {
-            let event = self.framework.buttons_mut().wait_event();
-            if let ButtonEvent::Press(btn_input) = event {
-                self.last_input_millis = self.framework.millis();
-                return self.button_input_to_os_input(btn_input)
+        // This may queue virtual presses, which are picked up on the next check
+        self.service_host_link();
+
+        if let Some(event) = self.framework.buttons_mut().poll_event()
+            && let Some(btn_input) = self.button_event_to_fire(event)
+        {
+            self.last_input_millis = self.framework.millis();
+            return Some(self.button_input_to_os_input(btn_input));
+        }
+
+        if let Some(input) = self.held_button_input() {
+            self.last_input_millis = self.framework.millis();
+            return Some(Some(input));
+        }
+
+        None
+    }
+
+    /// Decides whether an event from the keypad should fire its button. Buttons fire when pressed,
+    /// or when released if the `fire_button_press_only` setting is off, unless they've already
+    /// fired by being held down. A press of the button which was just released, sooner than the
+    /// `debounce_time` setting, is ignored as switch bounce, along with its release.
+    pub fn button_event_to_fire(&mut self, event: ButtonEvent) -> Option<ButtonInput> {
+        let now = self.framework.millis();
+        let settings = &self.filesystem.settings.values;
+
+        match event {
+            ButtonEvent::Press(btn) => {
+                if let Some((released, released_millis)) = self.keypad.last_release
+                    && released == btn
+                    && now.saturating_sub(released_millis) < settings.debounce_time as u64
+                {
+                    self.keypad.bounced_button = Some(btn);
+                    return None;
+                }
+                self.keypad.bounced_button = None;
+
+                let delay = if settings.key_repeat && btn.auto_repeats() {
+                    settings.key_repeat_delay
+                } else {
+                    settings.long_press_time
+                };
+                self.keypad.held = Some(HeldButton {
+                    button: btn,
+                    next_fire_millis: Some(now + delay as u64),
+                    fired_while_held: false,
+                });
+
+                if settings.fire_button_press_only { Some(btn) } else { None }
             }
+
+            ButtonEvent::Release(btn) => {
+                self.keypad.last_release = Some((btn, now));
+                if self.keypad.bounced_button == Some(btn) {
+                    self.keypad.bounced_button = None;
+                    return None;
+                }
+
+                // Another button may have been pressed since, in which case that one is still held
+                let fired_while_held = match &self.keypad.held {
+                    Some(held) if held.button == btn => {
+                        let fired_while_held = held.fired_while_held;
+                        self.keypad.held = None;
+                        fired_while_held
+                    }
+                    _ => false,
+                };
+                if settings.fire_button_press_only || fired_while_held { None } else { Some(btn) }
+            }
+        }
+    }
+
+    /// Checks whether the button being held down is due to fire by itself, and if so, returns its
+    /// input. Buttons which auto-repeat fire after the `key_repeat_delay` setting, and then every
+    /// `key_repeat_interval`, if the `key_repeat` setting is on. Other buttons fire a single
+    /// `OSInput::LongPress` after the `long_press_time` setting.
+    pub fn held_button_input(&mut self) -> Option<OSInput> {
+        let now = self.framework.millis();
+        let settings = &self.filesystem.settings.values;
+        let held = self.keypad.held.as_mut()?;
+        if now < held.next_fire_millis? {
+            return None;
+        }
+
+        held.fired_while_held = true;
+        let button = held.button;
+        if settings.key_repeat && button.auto_repeats() {
+            held.next_fire_millis = Some(now + settings.key_repeat_interval as u64);
+            self.button_input_to_os_input(button)
+        } else {
+            held.next_fire_millis = None;
+            Some(OSInput::LongPress(button))
         }
     }
 
@@ -81,4 +224,15 @@ impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
             self.virtual_input_queue.push(None);
         }
     }
+
+    /// Handles a press which didn't come from the keypad, such as one sent over the host link.
+    /// Unlike `queue_virtual_presses`, this translates the press as if it were real, so it
+    /// affects the shift and text mode state, and can open the menu.
+    pub fn inject_press(&mut self, input: ButtonInput) {
+        let os_input = self.button_input_to_os_input(input);
+        if os_input.is_some() {
+            self.virtual_input_queue.push(os_input);
+            self.virtual_input_queue.push(None);
+        }
+    }
 }
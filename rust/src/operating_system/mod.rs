@@ -1,8 +1,8 @@
 use core::ops::{DerefMut};
 
-use alloc::{boxed::Box, format, vec::Vec};
+use alloc::{boxed::Box, format, vec, vec::Vec};
 
-use crate::{applications::{Application, ApplicationList, menu::MenuApplication, }, interface::{Colour, ShapeFill, ApplicationFramework, DisplayInterface}, multi_tap::MultiTapState, filesystem::{Filesystem, Settings, RawStorage, CHUNK_SIZE, CHUNK_ADDRESS_SIZE, ChunkTable, CalculationHistory}, graphics::Sprite};
+use crate::{applications::{Application, ApplicationList, menu::MenuApplication, }, interface::{Colour, ShapeFill, ApplicationFramework, DisplayInterface, ButtonInput, UsbMassStorageInterface}, multi_tap::MultiTapState, filesystem::{Filesystem, Settings, RawStorage, CHUNK_SIZE, CHUNK_ADDRESS_SIZE, ChunkTable, CalculationHistory, DataLists, UserFunctions, USER_FUNCTION_NAMES, FileStore}, maths::Regression, graphics::Sprite, host_link::HostLink};
 
 mod pointer;
 pub use pointer::*;
@@ -16,9 +16,21 @@ pub use context_menu::*;
 mod input;
 pub use input::*;
 
+mod busy;
+pub use busy::*;
+
 mod ui;
 pub use ui::*;
 
+mod text_editor;
+pub use text_editor::*;
+
+mod file_list;
+pub use file_list::*;
+
+mod test;
+pub use test::test;
+
 pub struct OperatingSystem<F: ApplicationFramework + 'static> {
     pub ptr: OperatingSystemPointer<F>,
     pub framework: F,
@@ -36,9 +48,18 @@ pub struct OperatingSystem<F: ApplicationFramework + 'static> {
     pub text_mode: bool,
     pub multi_tap: MultiTapState<F>,
     pub virtual_input_queue: Vec<Option<OSInput>>,
+    pub keypad: KeypadState,
+    pub host_link: HostLink,
 
     pub display_sprite: Sprite,
     pub last_input_millis: u64,
+
+    /// Long-running work which is in progress, if any.
+    pub busy: Option<BusyState>,
+
+    /// Fitted curves which the Statistics application has asked to be plotted. The Graph
+    /// application takes these when it is next launched.
+    pub pending_plots: Vec<Regression>,
 }
 
 impl<F: ApplicationFramework> OperatingSystem<F> {
@@ -83,15 +104,69 @@ impl<F: ApplicationFramework> OperatingSystem<F> {
                         },
                     }
                 },
+
+                data_lists: DataLists {
+                    table: ChunkTable {
+                        start_address: 0x6000,
+                        chunks: 256,
+                        storage: RawStorage {
+                            os: OperatingSystemPointer::none(),
+                            start_address: 0x6000,
+
+                            length:
+                                CHUNK_SIZE * 256
+                                + 256 / 8
+                                + CHUNK_ADDRESS_SIZE * 256,
+                        },
+                    }
+                },
+
+                user_functions: UserFunctions {
+                    table: ChunkTable {
+                        start_address: 0x7400,
+                        chunks: 128,
+                        storage: RawStorage {
+                            os: OperatingSystemPointer::none(),
+                            start_address: 0x7400,
+
+                            length:
+                                CHUNK_SIZE * 128
+                                + 128 / 8
+                                + CHUNK_ADDRESS_SIZE * 128,
+                        },
+                    },
+                    definitions: vec![None; USER_FUNCTION_NAMES.len()],
+                },
+
+                files: FileStore {
+                    table: ChunkTable {
+                        start_address: 0x8000,
+                        chunks: 1024,
+                        storage: RawStorage {
+                            os: OperatingSystemPointer::none(),
+                            start_address: 0x8000,
+
+                            length:
+                                CHUNK_SIZE * 1024
+                                + 1024 / 8
+                                + CHUNK_ADDRESS_SIZE * 1024,
+                        },
+                    }
+                },
             },
 
             text_mode: false,
             multi_tap: MultiTapState::new(OperatingSystemPointer::none()),
             input_shift: false,
             virtual_input_queue: Vec::new(),
+            keypad: KeypadState::default(),
+            host_link: HostLink::default(),
 
             display_sprite: Sprite::new(display_width, display_height),
             last_input_millis: 0,
+            busy: None,
+
+            pending_plots: Vec::new(),
         }
     }
 
@@ -103,10 +178,14 @@ impl<F: ApplicationFramework> OperatingSystem<F> {
         ptr.application_list.os = ptr;
         ptr.filesystem.settings.storage.os = ptr;
         ptr.filesystem.calculations.table.storage.os = ptr;
+        ptr.filesystem.data_lists.table.storage.os = ptr;
+        ptr.filesystem.user_functions.table.storage.os = ptr;
+        ptr.filesystem.files.table.storage.os = ptr;
         ptr.multi_tap.os = ptr;
 
         // Load storage values
         ptr.filesystem.settings.load_into_self();
+        ptr.filesystem.user_functions.load_into_self();
     }
 
     /// Replaces the currently-running application with a new instance of the application at `index`
@@ -201,7 +280,8 @@ impl<F: ApplicationFramework> OperatingSystem<F> {
             });
         }
 
-        self.framework.display_mut().draw_display_sprite(&self.display_sprite)
+        self.framework.display_mut().draw_display_sprite(&self.display_sprite);
+        self.mirror_frame();
     }
 
     /// Toggles whether the global menu is currently being shown.
@@ -209,22 +289,58 @@ impl<F: ApplicationFramework> OperatingSystem<F> {
         self.showing_menu = !self.showing_menu;
     }
 
-    /// Enables USB mass storage mode. The calculator will appear as a mass storage device, and hang
-    /// until it is either ejected or the user presses DEL.
-    /// Temporary, can be removed when driver interacts directly with storage.
-    pub fn save_usb_mass_storage(&mut self) {
+    /// Connects to a computer as a USB drive containing the calculation history, settings, and
+    /// stored files. Once the drive has been ejected and EXE is pressed, any changes made on the
+    /// computer are imported. DEL disconnects without importing anything.
+    pub fn usb_mass_storage_mode(&mut self) {
+        let image = if let Some(image) = self.filesystem.export_disk_image() { image } else {
+            self.ui_text_dialog("There is too much data to fit on a USB drive.");
+            return;
+        };
+        if !self.framework.usb_mass_storage_mut().insert_medium(image) {
+            self.ui_text_dialog("This device does not support USB drives.");
+            return;
+        }
+
+        loop {
+            self.display_sprite.fill(Colour::BLACK);
+            self.ui_draw_title("USB Drive");
+            self.display_sprite.draw_bitmap(10, 50, "pc_connection");
+            let width = self.framework.display().width();
+            self.display_sprite.print_centred(0, 210, width, "Connected as a USB drive.");
+            self.display_sprite.print_centred(0, 235, width, "Eject it, then press EXE.");
+            self.display_sprite.print_centred(0, 290, width, "[DEL] Cancel");
+            self.draw();
+
+            match self.input() {
+                Some(OSInput::Button(ButtonInput::Exe)) => {
+                    if self.framework.usb_mass_storage_mut().is_ejected() {
+                        break;
+                    }
+                    self.ui_text_dialog("Eject the drive on your computer first, so that it finishes writing any changes.");
+                }
+
+                // Opening the menu also cancels, since the drive would otherwise stay connected
+                Some(OSInput::Button(ButtonInput::Delete)) | Some(OSInput::Button(ButtonInput::Menu)) => {
+                    self.framework.usb_mass_storage_mut().remove_medium();
+                    return;
+                }
+
+                _ => (),
+            }
+        }
+
         self.display_sprite.fill(Colour::BLACK);
-        self.ui_draw_title("USB Mass Storage");
+        self.ui_draw_title("USB Drive");
         let width = self.framework.display().width();
-        self.display_sprite.print_centred(0, 100, width, "Saving...");
+        self.display_sprite.print_centred(0, 100, width, "Importing...");
         self.draw();
 
-        // TODO
-        // unsafe {
-        //     os().filesystem.fat.write_all(slice::from_raw_parts_mut(
-        //         framework().usb_mass_storage.fat12_filesystem,
-        //         framework().usb_mass_storage.block_size * framework().usb_mass_storage.block_num
-        //     )).unwrap();
-        // }
+        let import = self.framework.usb_mass_storage_mut().remove_medium()
+            .and_then(|image| self.filesystem.import_disk_image(image));
+        match import {
+            Some(import) => self.ui_text_dialog(&format!("{}", import)),
+            None => self.ui_text_dialog("Could not read the USB drive."),
+        }
     }
 }
@@ -0,0 +1,123 @@
+use crate::interface::{ApplicationFramework, ButtonEvent, ButtonInput};
+
+use super::{HeldButton, KeypadState, OperatingSystem, OSInput};
+
+pub fn test<F: ApplicationFramework>(os: &mut OperatingSystem<F>) {
+    let original_values = os.filesystem.settings.values.clone();
+    os.keypad = KeypadState::default();
+
+    // Buttons fire when pressed by default
+    os.filesystem.settings.values.fire_button_press_only = true;
+    os.filesystem.settings.values.debounce_time = 0;
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(1))), Some(ButtonInput::Digit(1)));
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(1))), None);
+
+    // Or when released
+    os.filesystem.settings.values.fire_button_press_only = false;
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(1))), None);
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(1))), Some(ButtonInput::Digit(1)));
+
+    // Pressing the same button again straight after releasing it is ignored when debouncing, along
+    // with its release, but other buttons aren't affected
+    os.keypad = KeypadState::default();
+    os.filesystem.settings.values.fire_button_press_only = true;
+    os.filesystem.settings.values.debounce_time = 200;
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(1))), Some(ButtonInput::Digit(1)));
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(1))), None);
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(1))), None);
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(1))), None);
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(2))), Some(ButtonInput::Digit(2)));
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(2))), None);
+
+    // When buttons fire on release, it's the bounce's release which doesn't fire
+    os.filesystem.settings.values.fire_button_press_only = false;
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(2))), None);
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(2))), None);
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(3))), None);
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(3))), Some(ButtonInput::Digit(3)));
+    os.filesystem.settings.values.fire_button_press_only = true;
+
+    // Held arrow keys repeat. Rather than waiting, time passing is simulated by making the held
+    // button due to fire
+    let make_due = |os: &mut OperatingSystem<F>| {
+        if let Some(HeldButton { next_fire_millis: Some(next), .. }) = &mut os.keypad.held {
+            *next = 0;
+        }
+    };
+    os.keypad = KeypadState::default();
+    os.filesystem.settings.values.debounce_time = 0;
+    os.filesystem.settings.values.key_repeat = true;
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::MoveRight)), Some(ButtonInput::MoveRight));
+    assert_eq!(os.held_button_input(), None);
+    make_due(os);
+    assert_eq!(os.held_button_input(), Some(OSInput::Button(ButtonInput::MoveRight)));
+    assert_eq!(os.held_button_input(), None);
+    make_due(os);
+    assert_eq!(os.held_button_input(), Some(OSInput::Button(ButtonInput::MoveRight)));
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::MoveRight)), None);
+    assert_eq!(os.held_button_input(), None);
+
+    // Other keys fire a long press once instead, as do arrow keys without key repeat
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(5))), Some(ButtonInput::Digit(5)));
+    make_due(os);
+    assert_eq!(os.held_button_input(), Some(OSInput::LongPress(ButtonInput::Digit(5))));
+    make_due(os);
+    assert_eq!(os.held_button_input(), None);
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(5))), None);
+
+    // Releasing a different button, such as one let go after this was pressed, leaves it held
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(6))), Some(ButtonInput::Digit(6)));
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(4))), None);
+    make_due(os);
+    assert_eq!(os.held_button_input(), Some(OSInput::LongPress(ButtonInput::Digit(6))));
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(6))), None);
+
+    os.filesystem.settings.values.key_repeat = false;
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::MoveUp)), Some(ButtonInput::MoveUp));
+    make_due(os);
+    assert_eq!(os.held_button_input(), Some(OSInput::LongPress(ButtonInput::MoveUp)));
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::MoveUp)), None);
+
+    // When firing on release, a long press replaces the usual input
+    os.filesystem.settings.values.fire_button_press_only = false;
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Press(ButtonInput::Digit(5))), None);
+    make_due(os);
+    assert_eq!(os.held_button_input(), Some(OSInput::LongPress(ButtonInput::Digit(5))));
+    assert_eq!(os.button_event_to_fire(ButtonEvent::Release(ButtonInput::Digit(5))), None);
+
+    // Polling returns queued input without waiting, skipping the redraws between presses, and
+    // then returns nothing rather than blocking
+    os.keypad = KeypadState::default();
+    os.queue_virtual_presses(&[OSInput::Button(ButtonInput::Digit(7))]);
+    assert_eq!(os.poll_input(), Some(OSInput::Button(ButtonInput::Digit(7))));
+    assert_eq!(os.poll_input(), None);
+    assert!(os.virtual_input_queue.is_empty());
+    assert_eq!(os.poll_input(), None);
+
+    // Busy work carries on until AC is pressed, and nothing is drawn before the indicator's delay
+    os.begin_busy("Testing");
+    assert!(!os.busy_tick(Some((1, 10))));
+    assert_eq!(os.busy.as_ref().unwrap().drawn_millis, None);
+    os.end_busy();
+    assert!(os.busy.is_none());
+
+    // Once it's taken a while, AC stops it. Presses before the AC are kept for afterwards
+    os.queue_virtual_presses(&[
+        OSInput::Button(ButtonInput::Digit(1)),
+        OSInput::Button(ButtonInput::Clear),
+        OSInput::Button(ButtonInput::Digit(2)),
+    ]);
+    os.begin_busy("Testing");
+    assert!(!os.busy_tick(None));
+    while os.framework.millis() < OperatingSystem::<F>::BUSY_INDICATOR_DELAY_MILLIS {}
+    os.busy.as_mut().unwrap().started_millis = os.framework.millis() - OperatingSystem::<F>::BUSY_INDICATOR_DELAY_MILLIS;
+    assert!(os.busy_tick(None));
+    os.end_busy();
+    assert_eq!(os.poll_input(), Some(OSInput::Button(ButtonInput::Digit(1))));
+    assert_eq!(os.poll_input(), None);
+    assert_eq!(os.poll_input(), None);
+    assert_eq!(os.poll_input(), Some(OSInput::Button(ButtonInput::Digit(2))));
+    os.virtual_input_queue.clear();
+
+    os.filesystem.settings.values = original_values;
+}
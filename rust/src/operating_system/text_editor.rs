@@ -0,0 +1,313 @@
+use alloc::{string::String, vec::Vec};
+
+use crate::{interface::{ButtonInput, Colour, ShapeFill}, graphics::Sprite};
+
+use super::OSInput;
+
+/// An editable area of plain text, with a cursor. Letters are entered with multi-tap text mode,
+/// and other characters come from the keypad:
+///
+///   - Digits and operators insert themselves, and parentheses are inserted in pairs.
+///   - SHIFT with 0, point, parentheses, +, - and × insert a space, `,`, `"`, `=`, `<` and `>`
+///     respectively, since these are often needed when not in text mode.
+///   - EXE starts a new line, if the editor allows more than one.
+///
+/// If `wrap` is set, lines which are too wide for the editor are wrapped at word boundaries, and
+/// the up and down keys move between the wrapped rows rather than whole lines.
+///
+/// The editor only handles input and drawing; it's up to the caller to decide when editing ends.
+pub struct TextEditor {
+    /// The text being edited, split into lines. There is always at least one line.
+    pub lines: Vec<String>,
+
+    /// The cursor position, as (line, column). Characters are inserted before the column.
+    pub cursor: (usize, usize),
+
+    /// Whether EXE inserts a new line. If not, EXE is left unhandled so the caller can use it.
+    pub multi_line: bool,
+
+    /// Whether long lines are wrapped onto multiple rows when drawn.
+    pub wrap: bool,
+
+    /// Whether the character before the cursor was inserted by a multi-tap keypress, and may
+    /// therefore be replaced by a subsequent `TextMultiTapCycle`.
+    cursor_after_multi_tap: bool,
+
+    /// The index of the first visible row.
+    scroll_row: usize,
+
+    /// The rows which the text was split into when it was last drawn. This is cleared whenever
+    /// the text changes, since the rows may no longer be accurate.
+    rows: Vec<Row>,
+}
+
+/// A part of a line which is drawn on a single row of the editor.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Row {
+    line: usize,
+
+    /// The range of columns in the line which are drawn on this row.
+    start: usize,
+    end: usize,
+}
+
+impl TextEditor {
+    const PADDING: i16 = 4;
+
+    /// Creates an editor containing the given text, with the cursor at the end.
+    pub fn new(text: &str, multi_line: bool) -> Self {
+        let lines: Vec<String> = text.split('\n').map(|l| l.into()).collect();
+        let last = lines.len() - 1;
+        let column = lines[last].chars().count();
+        Self {
+            lines,
+            cursor: (last, column),
+            multi_line,
+            wrap: false,
+            cursor_after_multi_tap: false,
+            scroll_row: 0,
+            rows: Vec::new(),
+        }
+    }
+
+    /// The text being edited, with lines joined by newlines.
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Returns the byte offset into the current line of the cursor's column.
+    fn cursor_byte_offset(&self) -> usize {
+        let (line, column) = self.cursor;
+        self.lines[line].char_indices().nth(column).map(|(i, _)| i).unwrap_or(self.lines[line].len())
+    }
+
+    /// Inserts text at the cursor, and moves the cursor after it.
+    pub fn insert(&mut self, text: &str) {
+        for c in text.chars() {
+            self.insert_char(c);
+        }
+    }
+
+    /// Inserts a character at the cursor, and moves the cursor after it. A newline splits the line.
+    fn insert_char(&mut self, c: char) {
+        self.rows.clear();
+        let offset = self.cursor_byte_offset();
+        let (line, column) = self.cursor;
+        if c == '\n' {
+            let rest = self.lines[line].split_off(offset);
+            self.lines.insert(line + 1, rest);
+            self.cursor = (line + 1, 0);
+        } else {
+            self.lines[line].insert(offset, c);
+            self.cursor.1 = column + 1;
+        }
+    }
+
+    /// Deletes the character before the cursor, joining lines if it's at the start of one.
+    pub fn delete(&mut self) {
+        self.rows.clear();
+        let (line, column) = self.cursor;
+        if column > 0 {
+            self.cursor.1 -= 1;
+            let offset = self.cursor_byte_offset();
+            self.lines[line].remove(offset);
+        } else if line > 0 {
+            let removed = self.lines.remove(line);
+            let previous_length = self.lines[line - 1].chars().count();
+            self.lines[line - 1].push_str(&removed);
+            self.cursor = (line - 1, previous_length);
+        }
+    }
+
+    /// Handles a key press, returning `true` if it was used by the editor.
+    pub fn input(&mut self, input: &OSInput) -> bool {
+        let was_multi_tap = self.cursor_after_multi_tap;
+        self.cursor_after_multi_tap = false;
+
+        let (line, column) = self.cursor;
+        let line_length = |editor: &Self, line: usize| editor.lines[line].chars().count();
+
+        match input {
+            OSInput::TextMultiTapNew(c) => {
+                self.insert_char(*c);
+                self.cursor_after_multi_tap = true;
+            }
+            OSInput::TextMultiTapCycle(c) => {
+                if was_multi_tap {
+                    self.delete();
+                }
+                self.insert_char(*c);
+                self.cursor_after_multi_tap = true;
+            }
+
+            OSInput::Button(ButtonInput::Digit(d)) => self.insert_char(core::char::from_digit(*d as u32, 10).unwrap()),
+            OSInput::Button(ButtonInput::Point) => self.insert("."),
+            OSInput::Button(ButtonInput::Add) => self.insert("+"),
+            OSInput::Button(ButtonInput::Subtract) => self.insert("-"),
+            OSInput::Button(ButtonInput::Multiply) => self.insert("*"),
+            OSInput::Button(ButtonInput::Fraction) => self.insert("/"),
+            OSInput::Button(ButtonInput::Power) => self.insert("^"),
+            OSInput::Button(ButtonInput::Parentheses) => {
+                self.insert("()");
+                self.cursor.1 -= 1;
+            }
+
+            OSInput::ShiftedButton(ButtonInput::Digit(0)) => self.insert(" "),
+            OSInput::ShiftedButton(ButtonInput::Point) => self.insert(","),
+            OSInput::ShiftedButton(ButtonInput::Parentheses) => self.insert("\""),
+            OSInput::ShiftedButton(ButtonInput::Add) => self.insert("="),
+            OSInput::ShiftedButton(ButtonInput::Subtract) => self.insert("<"),
+            OSInput::ShiftedButton(ButtonInput::Multiply) => self.insert(">"),
+
+            OSInput::Button(ButtonInput::Exe) if self.multi_line => self.insert("\n"),
+            OSInput::Button(ButtonInput::Delete) => self.delete(),
+            OSInput::Button(ButtonInput::Clear) => {
+                self.rows.clear();
+                self.lines[line].clear();
+                self.cursor.1 = 0;
+            }
+
+            OSInput::Button(ButtonInput::MoveLeft) => {
+                if column > 0 {
+                    self.cursor.1 -= 1;
+                } else if line > 0 {
+                    self.cursor = (line - 1, line_length(self, line - 1));
+                }
+            }
+            OSInput::Button(ButtonInput::MoveRight) => {
+                if column < line_length(self, line) {
+                    self.cursor.1 += 1;
+                } else if line + 1 < self.lines.len() {
+                    self.cursor = (line + 1, 0);
+                }
+            }
+            OSInput::Button(ButtonInput::MoveUp) => return self.move_vertically(false),
+            OSInput::Button(ButtonInput::MoveDown) => return self.move_vertically(true),
+
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Moves the cursor to the row above or below, keeping its position within the row where
+    /// possible. Returns `false` if there is no row to move to.
+    fn move_vertically(&mut self, down: bool) -> bool {
+        let (line, column) = self.cursor;
+
+        // Without rows from a previous draw, move between whole lines
+        if self.rows.is_empty() {
+            let target = if down { line + 1 } else { line.wrapping_sub(1) };
+            if target >= self.lines.len() {
+                return false;
+            }
+            self.cursor = (target, column.min(self.lines[target].chars().count()));
+            return true;
+        }
+
+        let current = self.cursor_row();
+        let target = if down { current + 1 } else { current.wrapping_sub(1) };
+        if target >= self.rows.len() {
+            return false;
+        }
+
+        let (from, to) = (self.rows[current], self.rows[target]);
+        let mut new_column = to.start + (column - from.start);
+
+        // The end of a row which isn't the last of its line is the start of the next row, so stop
+        // just before it
+        let last_row_of_line = self.rows.get(target + 1).map(|r| r.line != to.line).unwrap_or(true);
+        let max_column = if last_row_of_line { to.end } else { to.end.saturating_sub(1).max(to.start) };
+        new_column = new_column.min(max_column);
+
+        self.cursor = (to.line, new_column);
+        true
+    }
+
+    /// The index into `rows` of the row containing the cursor. `rows` must not be empty.
+    fn cursor_row(&self) -> usize {
+        let (line, column) = self.cursor;
+        self.rows.iter()
+            .rposition(|r| r.line == line && r.start <= column)
+            .unwrap_or(0)
+    }
+
+    /// Splits the text into rows for drawing at the given width.
+    fn layout(&mut self, sprite: &mut Sprite, width: u16) {
+        self.rows.clear();
+
+        for (line_index, line) in self.lines.iter().enumerate() {
+            let length = line.chars().count();
+            if !self.wrap {
+                self.rows.push(Row { line: line_index, start: 0, end: length });
+                continue;
+            }
+
+            // `wrap_text` collapses whitespace, so find where its rows fall in the original line
+            // by counting the other characters
+            let (mut wrapped, _, _) = sprite.wrap_text(line, width);
+            wrapped.retain(|row| !row.trim().is_empty());
+            let chars: Vec<char> = line.chars().collect();
+
+            let mut start = 0;
+            for (i, row) in wrapped.iter().enumerate() {
+                let mut end = start;
+                if i + 1 == wrapped.len() {
+                    end = length;
+                } else {
+                    let mut remaining = row.chars().filter(|c| !c.is_whitespace()).count();
+                    while end < length && remaining > 0 {
+                        if !chars[end].is_whitespace() {
+                            remaining -= 1;
+                        }
+                        end += 1;
+                    }
+                    while end < length && chars[end].is_whitespace() {
+                        end += 1;
+                    }
+                }
+
+                self.rows.push(Row { line: line_index, start, end });
+                start = end;
+            }
+
+            // Blank lines still take up a row
+            if wrapped.is_empty() {
+                self.rows.push(Row { line: line_index, start: 0, end: length });
+            }
+        }
+    }
+
+    /// Draws the editor onto a sprite, within the given area. The view scrolls to keep the cursor
+    /// visible.
+    pub fn draw(&mut self, sprite: &mut Sprite, x: i16, y: i16, width: u16, height: u16) {
+        sprite.draw_rect(x, y, width, height, Colour::GREY, ShapeFill::Filled, 0);
+
+        let line_height = sprite.font.string_size("A").1;
+        let visible_rows = ((height as i16 - Self::PADDING * 2) / line_height).max(1) as usize;
+
+        self.layout(sprite, width - Self::PADDING as u16 * 2);
+
+        // Scroll so that the cursor's row is visible
+        let cursor_row = self.cursor_row();
+        if cursor_row < self.scroll_row {
+            self.scroll_row = cursor_row;
+        } else if cursor_row >= self.scroll_row + visible_rows {
+            self.scroll_row = cursor_row + 1 - visible_rows;
+        }
+
+        let text_x = x + Self::PADDING;
+        for (i, row) in self.rows.iter().enumerate().skip(self.scroll_row).take(visible_rows) {
+            let row_y = y + Self::PADDING + (i - self.scroll_row) as i16 * line_height;
+            let line = &self.lines[row.line];
+            let text: String = line.chars().skip(row.start).take(row.end - row.start).collect();
+            sprite.print_at(text_x, row_y, &text);
+
+            if i == cursor_row {
+                let before_cursor: String = line.chars().skip(row.start).take(self.cursor.1 - row.start).collect();
+                let cursor_x = text_x + sprite.font.string_size(&before_cursor).0;
+                sprite.draw_line(cursor_x, row_y, cursor_x, row_y + line_height - 2, Colour::WHITE);
+            }
+        }
+    }
+}
@@ -1,8 +1,8 @@
-use alloc::{format, vec::Vec, vec};
+use alloc::{format, string::String, vec::Vec, vec};
 use az::SaturatingAs;
-use rbop::{render::{Viewport, Area}, node::unstructured::{UnstructuredNodeRoot, Upgradable}, Number, nav::NavPath};
+use rbop::{render::{Viewport, Area}, node::unstructured::{UnstructuredNodeRoot, Upgradable}, Number};
 
-use crate::{interface::{ApplicationFramework, Colour, ShapeFill, DISPLAY_WIDTH, ButtonInput}, operating_system::{OSInput, OperatingSystemPointer}, rbop_impl::{RbopContext, RbopSpriteRenderer}, applications::calculator::{catalog::Catalog, CalculatorApplication}};
+use crate::{interface::{ApplicationFramework, Colour, ShapeFill, DISPLAY_WIDTH, ButtonInput}, operating_system::{OSInput, OperatingSystemPointer, TextEditor}, rbop_impl::{RbopContext, RbopSpriteRenderer}, applications::calculator::{catalog::Catalog, CalculatorApplication}};
 
 use super::OperatingSystem;
 
@@ -48,8 +48,7 @@ impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
 
         // If we've been given an existing root to use, then set that and move the cursor to the end
         if let Some(unr) = root {
-            rbop_ctx.root = unr;
-            rbop_ctx.nav_path = NavPath::new(vec![rbop_ctx.root.root.items.len()]);
+            rbop_ctx.set_root(unr);
         }
 
         // Don't let the box get any shorter than the maximum height it has achieved, or you'll get
@@ -97,10 +96,12 @@ impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
                         // and I've rarely seen more than 100kB used, so should be fine.)
                         let display_sprite_before_catalog = self.display_sprite.clone();
 
+                        let mut items = CalculatorApplication::<F>::catalog_items();
+                        items.extend(CalculatorApplication::<F>::user_function_catalog_items(&self.filesystem.user_functions));
                         let catalog = Catalog::new(
                             OperatingSystemPointer::new(self as *mut _),
                             "Catalog",
-                            CalculatorApplication::<F>::catalog_items(),
+                            items,
                         );
                         if let Some(item) = catalog.tick_until_complete() {
                             rbop_ctx.root.insert(
@@ -141,9 +142,8 @@ impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
             } else {
                 return None
             };
-            match unr
-                .as_ref()
-                .unwrap()
+            match self.filesystem.user_functions
+                .expand(unr.as_ref().unwrap())
                 .upgrade()
                 .map_err(|e| format!("{:?}", e))
                 .and_then(|sn| sn
@@ -161,6 +161,37 @@ impl<F: ApplicationFramework + 'static> OperatingSystem<F> {
         }
     }
 
+    /// Opens a text input box with the given `title`, starting with the text `initial`. When the
+    /// user presses EXE, returns the entered text.
+    ///
+    /// If the user opens the menu, returns `None`.
+    pub fn ui_input_text(&mut self, title: &str, initial: &str) -> Option<String> {
+        const PADDING: i16 = 10;
+        const HEIGHT: u16 = 90;
+
+        let mut editor = TextEditor::new(initial, false);
+        loop {
+            // Draw background of dialog
+            let y = (self.display_sprite.height - HEIGHT) as i16;
+            self.display_sprite.draw_rect(0, y, DISPLAY_WIDTH, 400, Colour::GREY, ShapeFill::Filled, 10);
+            self.display_sprite.draw_rect(0, y, DISPLAY_WIDTH, 400, Colour::WHITE, ShapeFill::Hollow, 10);
+
+            // Draw title and text
+            self.display_sprite.print_at(PADDING, y + PADDING, title);
+            editor.draw(&mut self.display_sprite, PADDING, y + 30 + PADDING, DISPLAY_WIDTH - PADDING as u16 * 2, 30);
+
+            self.draw();
+
+            if let Some(input) = self.input() {
+                match input {
+                    OSInput::Button(ButtonInput::Exe) => return Some(editor.text()),
+                    OSInput::Button(ButtonInput::Menu) => return None,
+                    input => { editor.input(&input); }
+                }
+            }
+        }
+    }
+
     /// Opens a text dialog in the centre of the screen which can be dismissed with EXE.
     pub fn ui_text_dialog(&mut self, s: &str) {
         const H_PADDING: u16 = 30;
@@ -25,6 +25,12 @@ impl<F: ApplicationFramework> RbopContext<F> {
         }
     }
 
+    /// Replaces the expression being edited, and moves the cursor to the end of it.
+    pub fn set_root(&mut self, root: UnstructuredNodeRoot) {
+        self.root = root;
+        self.nav_path = NavPath::new(vec![self.root.root.items.len()]);
+    }
+
     pub fn input(&mut self, input: OSInput) -> Option<(MoveVerticalDirection, MoveResult)> {
         let mut renderer = RbopSpriteRenderer::new();
 
@@ -79,11 +85,14 @@ impl<F: ApplicationFramework> RbopContext<F> {
                 UnstructuredNodeList { items: vec![] },
             )),
 
-            OSInput::TextMultiTapNew(c) => Some(UnstructuredNode::Token(Token::Variable(c))),
-            OSInput::TextMultiTapCycle(c) => {
+            // Only letters can be variables, not the spaces or punctuation which multi-tap can
+            // also produce
+            OSInput::TextMultiTapNew(c) if c.is_ascii_alphabetic() => Some(UnstructuredNode::Token(Token::Variable(c))),
+            OSInput::TextMultiTapCycle(c) if c.is_ascii_alphabetic() => {
                 self.root.delete(&mut self.nav_path, &mut renderer, self.viewport.as_mut());
                 Some(UnstructuredNode::Token(Token::Variable(c)))
             }
+            OSInput::TextMultiTapNew(_) | OSInput::TextMultiTapCycle(_) => return None,
 
             OSInput::Button(ButtonInput::Exe) => return None,
             OSInput::Button(ButtonInput::List) => return None,
@@ -97,6 +106,7 @@ impl<F: ApplicationFramework> RbopContext<F> {
             OSInput::ShiftedButton(ButtonInput::Digit(2)) => Some(UnstructuredNode::new_function_call(Function::Cosine)),
             OSInput::ShiftedButton(ButtonInput::Digit(3)) => Some(UnstructuredNode::new_function_call(Function::GreatestCommonDenominator)),
             OSInput::ShiftedButton(_) => return None,
+            OSInput::LongPress(_) => return None,
         };
     
         if let Some(node) = node_to_insert {
@@ -0,0 +1,410 @@
+use core::fmt::Display;
+use alloc::{collections::BTreeMap, format, string::{String, ToString}, vec::Vec};
+use rust_decimal::{Decimal, MathematicalOps, prelude::{One, Zero, ToPrimitive}};
+
+use crate::{interface::{ApplicationFramework, ButtonInput, Colour, ShapeFill}, operating_system::{OSInput, OperatingSystem, OperatingSystemPointer, os_accessor}};
+
+use super::{BinaryOperator, DrawCommand, Expression, Line, ScriptError, ScriptErrorKind, ScriptResult, Statement, UnaryOperator};
+
+/// A value which a variable can hold.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Value {
+    Number(Decimal),
+    String(String),
+}
+
+impl Value {
+    fn number(&self) -> Result<Decimal, ScriptErrorKind> {
+        match self {
+            Value::Number(n) => Ok(*n),
+            Value::String(_) => Err(ScriptErrorKind::TypeMismatch),
+        }
+    }
+
+    fn boolean(value: bool) -> Value {
+        Value::Number(if value { Decimal::ONE } else { Decimal::ZERO })
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n.normalize()),
+            Value::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Codes returned by the `key` function for keys other than digits, which return themselves.
+const KEY_CODES: [(ButtonInput, i64); 16] = [
+    (ButtonInput::Exe, 10),
+    (ButtonInput::MoveLeft, 11),
+    (ButtonInput::MoveRight, 12),
+    (ButtonInput::MoveUp, 13),
+    (ButtonInput::MoveDown, 14),
+    (ButtonInput::Delete, 15),
+    (ButtonInput::Clear, 16),
+    (ButtonInput::Point, 17),
+    (ButtonInput::Parentheses, 18),
+    (ButtonInput::Add, 19),
+    (ButtonInput::Subtract, 20),
+    (ButtonInput::Multiply, 21),
+    (ButtonInput::Fraction, 22),
+    (ButtonInput::Power, 23),
+    (ButtonInput::Sqrt, 24),
+    (ButtonInput::List, 25),
+];
+
+/// The most lines kept in the console. Older lines are discarded.
+const MAX_CONSOLE_LINES: usize = 100;
+
+/// The message shown by the busy indicator while a program runs.
+const BUSY_MESSAGE: &str = "Running";
+
+/// Whether execution should carry on after a statement.
+enum Flow {
+    Continue,
+    Stop,
+}
+
+/// Runs parsed programs, drawing to the operating system's display and reading its input.
+pub struct Interpreter<F: ApplicationFramework + 'static> {
+    os: OperatingSystemPointer<F>,
+    pub variables: BTreeMap<String, Value>,
+
+    /// Lines written by `print`, oldest first.
+    pub console: Vec<String>,
+
+    /// The colour used by drawing statements.
+    colour: Colour,
+}
+
+os_accessor!(Interpreter<F>);
+
+impl<F: ApplicationFramework> Interpreter<F> {
+    pub fn new(os: OperatingSystemPointer<F>) -> Self {
+        Self {
+            os,
+            variables: BTreeMap::new(),
+            console: Vec::new(),
+            colour: Colour::WHITE,
+        }
+    }
+
+    /// Runs a program until it finishes, stops, or encounters an error. The OS' busy indicator is
+    /// shown while the program runs for a while without waiting for input.
+    pub fn run(&mut self, program: &[Line]) -> ScriptResult<()> {
+        self.draw_console();
+        self.os_mut().begin_busy(BUSY_MESSAGE);
+        let result = self.execute_block(program).map(|_| ());
+        self.os_mut().end_busy();
+        result
+    }
+
+    fn execute_block(&mut self, lines: &[Line]) -> ScriptResult<Flow> {
+        for line in lines {
+            if let Flow::Stop = self.execute(line)? {
+                return Ok(Flow::Stop);
+            }
+        }
+        Ok(Flow::Continue)
+    }
+
+    fn execute(&mut self, line: &Line) -> ScriptResult<Flow> {
+        let at_line = |kind| ScriptError { line: line.line, kind };
+        let evaluate = |this: &mut Self, expression| this.evaluate(expression).map_err(at_line);
+        let evaluate_number = |this: &mut Self, expression| this.evaluate(expression)
+            .and_then(|v| v.number())
+            .map_err(at_line);
+
+        match &line.statement {
+            Statement::Assign(variable, expression) => {
+                let value = evaluate(self, expression)?;
+                self.variables.insert(variable.clone(), value);
+            }
+
+            Statement::Print(expressions) => {
+                let mut values = Vec::with_capacity(expressions.len());
+                for expression in expressions {
+                    values.push(evaluate(self, expression)?.to_string());
+                }
+                self.print(values.join(" "));
+            }
+
+            Statement::Input(prompt, variable) => {
+                let prompt = match prompt {
+                    Some(prompt) => evaluate(self, prompt)?.to_string(),
+                    None => format!("{} =", variable),
+                };
+                let (number, _) = self.os_mut().ui_input_expression_and_evaluate(&prompt, None, || ())
+                    .ok_or_else(|| at_line(ScriptErrorKind::Stopped))?;
+                self.variables.insert(variable.clone(), Value::Number(number.to_decimal()));
+                self.draw_console();
+                self.restart_busy();
+            }
+
+            Statement::If { condition, then, otherwise } => {
+                let branch = if evaluate_number(self, condition)?.is_zero() { otherwise } else { then };
+                return self.execute_block(branch);
+            }
+
+            Statement::While(condition, body) => {
+                while !evaluate_number(self, condition)?.is_zero() {
+                    self.check_stopped().map_err(at_line)?;
+                    if let Flow::Stop = self.execute_block(body)? {
+                        return Ok(Flow::Stop);
+                    }
+                }
+            }
+
+            Statement::For { variable, from, to, step, body } => {
+                let from = evaluate_number(self, from)?;
+                let to = evaluate_number(self, to)?;
+                let step = match step {
+                    Some(step) => evaluate_number(self, step)?,
+                    None => Decimal::ONE,
+                };
+                if step.is_zero() {
+                    return Err(at_line(ScriptErrorKind::InvalidArgument));
+                }
+
+                let mut value = from;
+                while (step.is_sign_positive() && value <= to) || (step.is_sign_negative() && value >= to) {
+                    self.check_stopped().map_err(at_line)?;
+                    self.variables.insert(variable.clone(), Value::Number(value));
+                    if let Flow::Stop = self.execute_block(body)? {
+                        return Ok(Flow::Stop);
+                    }
+
+                    // The body may have changed the variable
+                    value = self.variables.get(variable)
+                        .ok_or_else(|| at_line(ScriptErrorKind::UndefinedVariable(variable.clone())))?
+                        .number()
+                        .map_err(at_line)?;
+                    value = value.checked_add(step).ok_or_else(|| at_line(ScriptErrorKind::Overflow))?;
+                }
+            }
+
+            Statement::Draw(command, arguments) => {
+                let mut numbers = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    numbers.push(evaluate(self, argument)?);
+                }
+                self.draw(*command, &numbers).map_err(at_line)?;
+            }
+
+            Statement::Stop => return Ok(Flow::Stop),
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    /// Checks, without waiting, whether the user has pressed AC or opened the menu to stop the
+    /// program. Loops call this on every iteration, so that a program which never ends can still
+    /// be stopped.
+    fn check_stopped(&mut self) -> Result<(), ScriptErrorKind> {
+        if self.os_mut().busy_tick(None) {
+            Err(ScriptErrorKind::Stopped)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Starts timing the busy indicator again after waiting for input, so that interactive
+    /// programs aren't drawn over.
+    fn restart_busy(&mut self) {
+        self.os_mut().begin_busy(BUSY_MESSAGE);
+    }
+
+    /// Adds a line to the console, and redraws it.
+    fn print(&mut self, line: String) {
+        self.console.push(line);
+        if self.console.len() > MAX_CONSOLE_LINES {
+            self.console.remove(0);
+        }
+        self.draw_console();
+    }
+
+    /// Draws the most recent lines of the console to the screen.
+    fn draw_console(&mut self) {
+        let line_height = self.os().display_sprite.font.string_size("A").1;
+        let top = OperatingSystem::<F>::TITLE_BAR_HEIGHT as i16 + 5;
+        let visible_lines = ((self.os().display_sprite.height as i16 - top) / line_height) as usize;
+
+        self.os_mut().display_sprite.fill(Colour::BLACK);
+        self.os_mut().ui_draw_title("Running");
+        let first = self.console.len().saturating_sub(visible_lines);
+        for (i, line) in self.console[first..].iter().enumerate() {
+            self.os.display_sprite.print_at(5, top + i as i16 * line_height, line);
+        }
+        self.os_mut().draw();
+    }
+
+    /// Performs a drawing statement, given its evaluated arguments.
+    fn draw(&mut self, command: DrawCommand, arguments: &[Value]) -> Result<(), ScriptErrorKind> {
+        // Coordinates are clamped rather than rejected, since drawing off-screen is harmless
+        let coordinate = |i: usize| -> Result<i16, ScriptErrorKind> {
+            let n = arguments[i].number()?.round();
+            Ok(n.to_i64().unwrap_or(0).clamp(i16::MIN as i64, i16::MAX as i64) as i16)
+        };
+        let size = |i: usize| coordinate(i).map(|n| n.max(0) as u16);
+        let component = |i: usize| coordinate(i).map(|n| n.clamp(0, 255) as u8);
+
+        let colour = self.colour;
+        let sprite = &mut self.os.display_sprite;
+        match command {
+            DrawCommand::Clear => sprite.fill(Colour::BLACK),
+            DrawCommand::Colour => self.colour = Colour::from_parts(component(0)?, component(1)?, component(2)?),
+            DrawCommand::Pixel => sprite.draw_pixel(coordinate(0)?, coordinate(1)?, colour),
+            DrawCommand::Line => sprite.draw_line(coordinate(0)?, coordinate(1)?, coordinate(2)?, coordinate(3)?, colour),
+            DrawCommand::Rect =>
+                sprite.draw_rect(coordinate(0)?, coordinate(1)?, size(2)?, size(3)?, colour, ShapeFill::Hollow, 0),
+            DrawCommand::Fill =>
+                sprite.draw_rect(coordinate(0)?, coordinate(1)?, size(2)?, size(3)?, colour, ShapeFill::Filled, 0),
+            DrawCommand::Text => sprite.print_at(coordinate(0)?, coordinate(1)?, &arguments[2].to_string()),
+            DrawCommand::Show => self.os_mut().draw(),
+        }
+        Ok(())
+    }
+
+    fn evaluate(&mut self, expression: &Expression) -> Result<Value, ScriptErrorKind> {
+        Ok(match expression {
+            Expression::Number(n) => Value::Number(*n),
+            Expression::String(s) => Value::String(s.clone()),
+            Expression::Variable(name) => self.variables.get(name)
+                .cloned()
+                .ok_or_else(|| ScriptErrorKind::UndefinedVariable(name.clone()))?,
+
+            Expression::Unary(operator, operand) => {
+                let operand = self.evaluate(operand)?.number()?;
+                match operator {
+                    UnaryOperator::Negate => Value::Number(-operand),
+                    UnaryOperator::Not => Value::boolean(operand.is_zero()),
+                }
+            }
+
+            Expression::Binary(left, operator, right) => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Self::binary(left, *operator, right)?
+            }
+
+            Expression::Call(name, arguments) => {
+                let mut values = Vec::with_capacity(arguments.len());
+                for argument in arguments {
+                    values.push(self.evaluate(argument)?);
+                }
+                self.call(name, &values)?
+            }
+        })
+    }
+
+    fn binary(left: Value, operator: BinaryOperator, right: Value) -> Result<Value, ScriptErrorKind> {
+        // Adding anything to a string concatenates them
+        if operator == BinaryOperator::Add && (matches!(left, Value::String(_)) || matches!(right, Value::String(_))) {
+            return Ok(Value::String(format!("{}{}", left, right)));
+        }
+
+        // Strings can be compared for equality
+        if let (Value::String(l), Value::String(r)) = (&left, &right) {
+            return match operator {
+                BinaryOperator::Equal => Ok(Value::boolean(l == r)),
+                BinaryOperator::NotEqual => Ok(Value::boolean(l != r)),
+                _ => Err(ScriptErrorKind::TypeMismatch),
+            };
+        }
+
+        let (l, r) = (left.number()?, right.number()?);
+        let overflow = |n: Option<Decimal>| n.map(Value::Number).ok_or(ScriptErrorKind::Overflow);
+        match operator {
+            BinaryOperator::Add => overflow(l.checked_add(r)),
+            BinaryOperator::Subtract => overflow(l.checked_sub(r)),
+            BinaryOperator::Multiply => overflow(l.checked_mul(r)),
+            BinaryOperator::Divide | BinaryOperator::Modulo if r.is_zero() => Err(ScriptErrorKind::DivisionByZero),
+            BinaryOperator::Divide => overflow(l.checked_div(r)),
+            BinaryOperator::Modulo => overflow(l.checked_rem(r)),
+            BinaryOperator::Power => Self::power(l, r).map(Value::Number),
+
+            BinaryOperator::Equal => Ok(Value::boolean(l == r)),
+            BinaryOperator::NotEqual => Ok(Value::boolean(l != r)),
+            BinaryOperator::Less => Ok(Value::boolean(l < r)),
+            BinaryOperator::LessEqual => Ok(Value::boolean(l <= r)),
+            BinaryOperator::Greater => Ok(Value::boolean(l > r)),
+            BinaryOperator::GreaterEqual => Ok(Value::boolean(l >= r)),
+            BinaryOperator::And => Ok(Value::boolean(!l.is_zero() && !r.is_zero())),
+            BinaryOperator::Or => Ok(Value::boolean(!l.is_zero() || !r.is_zero())),
+        }
+    }
+
+    /// Raises `base` to `exponent`. Whole exponents are computed exactly by repeated squaring.
+    fn power(base: Decimal, exponent: Decimal) -> Result<Decimal, ScriptErrorKind> {
+        if exponent.fract().is_zero() {
+            let mut remaining = exponent.abs().to_u64().ok_or(ScriptErrorKind::Overflow)?;
+            let mut square = base;
+            let mut result = Decimal::one();
+            while remaining > 0 {
+                if remaining & 1 == 1 {
+                    result = result.checked_mul(square).ok_or(ScriptErrorKind::Overflow)?;
+                }
+                remaining >>= 1;
+                if remaining > 0 {
+                    square = square.checked_mul(square).ok_or(ScriptErrorKind::Overflow)?;
+                }
+            }
+
+            if exponent.is_sign_negative() {
+                if result.is_zero() {
+                    return Err(ScriptErrorKind::DivisionByZero);
+                }
+                result = Decimal::one().checked_div(result).ok_or(ScriptErrorKind::Overflow)?;
+            }
+            Ok(result)
+        } else if base.is_sign_positive() && !base.is_zero() {
+            base.checked_ln()
+                .and_then(|ln| ln.checked_mul(exponent))
+                .and_then(|x| x.checked_exp())
+                .ok_or(ScriptErrorKind::Overflow)
+        } else {
+            Err(ScriptErrorKind::InvalidArgument)
+        }
+    }
+
+    fn call(&mut self, name: &str, arguments: &[Value]) -> Result<Value, ScriptErrorKind> {
+        let single = || match arguments {
+            [argument] => argument.number(),
+            _ => Err(ScriptErrorKind::WrongArgumentCount(name.into())),
+        };
+
+        Ok(Value::Number(match name {
+            "abs" => single()?.abs(),
+            "int" => single()?.floor(),
+            "sqrt" => single()?.sqrt().ok_or(ScriptErrorKind::InvalidArgument)?,
+            "key" if arguments.is_empty() => {
+                let key = self.wait_key()?;
+                self.restart_busy();
+                Decimal::from(key)
+            }
+            "key" => return Err(ScriptErrorKind::WrongArgumentCount(name.into())),
+            _ => return Err(ScriptErrorKind::UnknownFunction(name.into())),
+        }))
+    }
+
+    /// Shows what has been drawn, then waits for a key and returns its code. Digits give their own
+    /// value, and other keys give the codes in `KEY_CODES`. Opening the menu stops the program.
+    fn wait_key(&mut self) -> Result<i64, ScriptErrorKind> {
+        self.os_mut().draw();
+        loop {
+            match self.os_mut().input() {
+                Some(OSInput::Button(ButtonInput::Menu)) => return Err(ScriptErrorKind::Stopped),
+                Some(OSInput::Button(ButtonInput::Digit(d)) | OSInput::ShiftedButton(ButtonInput::Digit(d))) =>
+                    return Ok(d as i64),
+                Some(OSInput::Button(button) | OSInput::ShiftedButton(button)) => {
+                    if let Some((_, code)) = KEY_CODES.iter().find(|(b, _)| *b == button) {
+                        return Ok(*code);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use core::str::FromStr;
+use alloc::{string::String, vec::Vec};
+use rust_decimal::Decimal;
+
+use super::{ScriptError, ScriptErrorKind, ScriptResult};
+
+/// A token in a program's source.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Token {
+    Number(Decimal),
+    String(String),
+
+    /// A keyword or variable name, converted to lowercase.
+    Word(String),
+
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+    LeftParen,
+    RightParen,
+    Comma,
+
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+
+    /// The end of a line, which also ends a statement.
+    Newline,
+}
+
+/// A `Token`, with the line it appeared on, starting from 1.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct LineToken {
+    pub token: Token,
+    pub line: usize,
+}
+
+/// Splits a program's source into tokens. Comments are discarded.
+pub fn tokenize(source: &str) -> ScriptResult<Vec<LineToken>> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = source.chars().peekable();
+    let error = |line, kind| ScriptError { line, kind };
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\n' => {
+                tokens.push(LineToken { token: Token::Newline, line });
+                line += 1;
+                continue;
+            }
+            c if c.is_whitespace() => continue,
+
+            // Comments run to the end of the line
+            '\'' => {
+                while chars.peek().map(|c| *c != '\n').unwrap_or(false) {
+                    chars.next();
+                }
+                continue;
+            }
+
+            '0'..='9' | '.' => {
+                let mut number = String::from(c);
+                while let Some(c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                    number.push(*c);
+                    chars.next();
+                }
+                Token::Number(Decimal::from_str(&number).map_err(|_| error(line, ScriptErrorKind::InvalidNumber))?)
+            }
+
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\n') | None => return Err(error(line, ScriptErrorKind::UnterminatedString)),
+                        Some(c) => string.push(c),
+                    }
+                }
+                Token::String(string)
+            }
+
+            c if c.is_ascii_alphabetic() => {
+                let mut word = String::from(c.to_ascii_lowercase());
+                while let Some(c) = chars.peek().filter(|c| c.is_ascii_alphanumeric() || **c == '_') {
+                    word.push(c.to_ascii_lowercase());
+                    chars.next();
+                }
+
+                if word == "rem" {
+                    while chars.peek().map(|c| *c != '\n').unwrap_or(false) {
+                        chars.next();
+                    }
+                    continue;
+                }
+                Token::Word(word)
+            }
+
+            '+' => Token::Add,
+            '-' => Token::Subtract,
+            '*' => Token::Multiply,
+            '/' => Token::Divide,
+            '^' => Token::Power,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ',' => Token::Comma,
+            '=' => Token::Equal,
+            '<' => match chars.peek() {
+                Some('=') => { chars.next(); Token::LessEqual }
+                Some('>') => { chars.next(); Token::NotEqual }
+                _ => Token::Less,
+            },
+            '>' => match chars.peek() {
+                Some('=') => { chars.next(); Token::GreaterEqual }
+                _ => Token::Greater,
+            },
+
+            c => return Err(error(line, ScriptErrorKind::UnexpectedCharacter(c))),
+        };
+
+        tokens.push(LineToken { token, line });
+    }
+
+    tokens.push(LineToken { token: Token::Newline, line });
+    Ok(tokens)
+}
@@ -0,0 +1,113 @@
+//! A small structured BASIC, for writing programs on the calculator.
+//!
+//! Programs are made of one statement per line. Keywords and variable names are case-insensitive,
+//! and comments start with `'` or `rem`.
+//!
+//! ```text
+//! ' Count down, then draw a box
+//! input "Start from", n
+//! for i = n to 1 step -1
+//!   print "T-minus", i
+//! next
+//! if n > 5 then
+//!   print "That was a long one"
+//! else
+//!   print "Liftoff"
+//! end
+//! cls
+//! colour 255, 128, 0
+//! rect 20, 60, 200, 100
+//! text 30, 70, "Press a key"
+//! k = key()
+//! ```
+//!
+//! Statements:
+//!   - `x = expr` (or `let x = expr`) assigns a variable.
+//!   - `print a, b, ...` writes values to the console, separated by spaces.
+//!   - `input "prompt", x` asks for a number. The prompt is optional.
+//!   - `if cond then` ... `else` ... `end` runs code conditionally. The `else` is optional, and
+//!     `if cond then statement` can be written on one line.
+//!   - `while cond` ... `end` loops while the condition holds.
+//!   - `for i = a to b step s` ... `next` loops over a range. The `step` is optional.
+//!   - `stop` ends the program. Pressing AC or opening the menu also stops it.
+//!   - `cls`, `colour r, g, b`, `pixel x, y`, `line x1, y1, x2, y2`, `rect x, y, w, h`,
+//!     `fill x, y, w, h` and `text x, y, value` draw to the screen, and `show` displays what has
+//!     been drawn.
+//!
+//! Values are numbers or strings. Expressions support `+ - * / ^ mod`, comparisons (`= <> < > <=
+//! >=`, giving 1 or 0), `and`, `or` and `not`, and the functions `abs`, `int`, `sqrt` and `key`.
+//! Adding a string to anything concatenates them.
+
+use core::fmt::Display;
+use alloc::string::String;
+
+mod lexer;
+pub use lexer::*;
+
+mod parser;
+pub use parser::*;
+
+mod interpreter;
+pub use interpreter::*;
+
+/// An error encountered while parsing or running a program.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum ScriptErrorKind {
+    UnexpectedCharacter(char),
+    UnterminatedString,
+    InvalidNumber,
+
+    /// Something else was found where the given construct was expected.
+    Expected(&'static str),
+
+    UndefinedVariable(String),
+    UnknownFunction(String),
+    WrongArgumentCount(String),
+
+    /// An operation was given a string where it needed a number, or vice versa.
+    TypeMismatch,
+
+    DivisionByZero,
+    Overflow,
+
+    /// A function or statement was given a value it can't use, like the square root of a negative.
+    InvalidArgument,
+
+    /// The user stopped the program by pressing AC or opening the menu.
+    Stopped,
+}
+
+impl Display for ScriptErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ScriptErrorKind::UnexpectedCharacter(c) => write!(f, "Unexpected character '{}'", c),
+            ScriptErrorKind::UnterminatedString => write!(f, "Unterminated string"),
+            ScriptErrorKind::InvalidNumber => write!(f, "Invalid number"),
+            ScriptErrorKind::Expected(what) => write!(f, "Expected {}", what),
+            ScriptErrorKind::UndefinedVariable(name) => write!(f, "Undefined variable '{}'", name),
+            ScriptErrorKind::UnknownFunction(name) => write!(f, "Unknown function '{}'", name),
+            ScriptErrorKind::WrongArgumentCount(name) => write!(f, "Wrong number of arguments to '{}'", name),
+            ScriptErrorKind::TypeMismatch => write!(f, "Type mismatch"),
+            ScriptErrorKind::DivisionByZero => write!(f, "Division by zero"),
+            ScriptErrorKind::Overflow => write!(f, "Overflow"),
+            ScriptErrorKind::InvalidArgument => write!(f, "Invalid argument"),
+            ScriptErrorKind::Stopped => write!(f, "Stopped"),
+        }
+    }
+}
+
+/// A `ScriptErrorKind`, with the line of the program where it occurred.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct ScriptError {
+    /// The line number, starting from 1.
+    pub line: usize,
+    pub kind: ScriptErrorKind,
+}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.kind)
+    }
+}
+
+pub type ScriptResult<T> = Result<T, ScriptError>;
@@ -0,0 +1,462 @@
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use rust_decimal::Decimal;
+
+use super::{LineToken, Token, ScriptError, ScriptErrorKind, ScriptResult, tokenize};
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Power,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Expression {
+    Number(Decimal),
+    String(String),
+    Variable(String),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(Box<Expression>, BinaryOperator, Box<Expression>),
+    Call(String, Vec<Expression>),
+}
+
+/// A statement which draws to the screen, taking a fixed number of arguments.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum DrawCommand {
+    Clear,
+    Colour,
+    Pixel,
+    Line,
+    Rect,
+    Fill,
+    Text,
+    Show,
+}
+
+impl DrawCommand {
+    fn from_keyword(keyword: &str) -> Option<DrawCommand> {
+        Some(match keyword {
+            "cls" => DrawCommand::Clear,
+            "colour" | "color" => DrawCommand::Colour,
+            "pixel" => DrawCommand::Pixel,
+            "line" => DrawCommand::Line,
+            "rect" => DrawCommand::Rect,
+            "fill" => DrawCommand::Fill,
+            "text" => DrawCommand::Text,
+            "show" => DrawCommand::Show,
+            _ => return None,
+        })
+    }
+
+    /// The number of arguments this command takes.
+    pub fn arity(&self) -> usize {
+        match self {
+            DrawCommand::Clear | DrawCommand::Show => 0,
+            DrawCommand::Pixel => 2,
+            DrawCommand::Colour | DrawCommand::Text => 3,
+            DrawCommand::Line | DrawCommand::Rect | DrawCommand::Fill => 4,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub enum Statement {
+    Assign(String, Expression),
+    Print(Vec<Expression>),
+    Input(Option<Expression>, String),
+    If {
+        condition: Expression,
+        then: Vec<Line>,
+        otherwise: Vec<Line>,
+    },
+    While(Expression, Vec<Line>),
+    For {
+        variable: String,
+        from: Expression,
+        to: Expression,
+        step: Option<Expression>,
+        body: Vec<Line>,
+    },
+    Draw(DrawCommand, Vec<Expression>),
+    Stop,
+}
+
+/// A `Statement`, with the line it started on, starting from 1.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Line {
+    pub statement: Statement,
+    pub line: usize,
+}
+
+/// Words which can't be used as variable names.
+const KEYWORDS: [&str; 17] = [
+    "let", "print", "input", "if", "then", "else", "end", "while", "for", "to", "step", "next",
+    "stop", "and", "or", "not", "mod",
+];
+
+/// Parses a program's source into a list of statements.
+pub fn parse(source: &str) -> ScriptResult<Vec<Line>> {
+    let mut parser = Parser { tokens: tokenize(source)?, position: 0 };
+    Ok(parser.parse_block(&[])?.0)
+}
+
+struct Parser {
+    tokens: Vec<LineToken>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|t| &t.token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.position).or_else(|| self.tokens.last()).map(|t| t.line).unwrap_or(1)
+    }
+
+    fn error(&self, kind: ScriptErrorKind) -> ScriptError {
+        ScriptError { line: self.line(), kind }
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).map(|t| t.token.clone());
+        self.position += 1;
+        token
+    }
+
+    /// Consumes the next token if it is `token`, returning whether it was.
+    fn accept(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn accept_word(&mut self, word: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Word(w)) if w == word) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, name: &'static str) -> ScriptResult<()> {
+        if self.accept(token) { Ok(()) } else { Err(self.error(ScriptErrorKind::Expected(name))) }
+    }
+
+    fn expect_word(&mut self, word: &'static str) -> ScriptResult<()> {
+        if self.accept_word(word) { Ok(()) } else { Err(self.error(ScriptErrorKind::Expected(word))) }
+    }
+
+    fn expect_variable(&mut self) -> ScriptResult<String> {
+        match self.peek() {
+            Some(Token::Word(w)) if !KEYWORDS.contains(&w.as_str()) => {
+                let name = w.clone();
+                self.position += 1;
+                Ok(name)
+            }
+            _ => Err(self.error(ScriptErrorKind::Expected("variable"))),
+        }
+    }
+
+    fn expect_end_of_statement(&mut self) -> ScriptResult<()> {
+        self.expect(&Token::Newline, "end of line")
+    }
+
+    /// Parses statements until the end of the program, or until a line starting with one of the
+    /// `terminators`. Returns the statements, and the terminator found, which is consumed.
+    fn parse_block(&mut self, terminators: &[&'static str]) -> ScriptResult<(Vec<Line>, Option<&'static str>)> {
+        let mut lines = vec![];
+        loop {
+            // Skip blank lines
+            while self.accept(&Token::Newline) {}
+
+            match self.peek() {
+                None => return Ok((lines, None)),
+                Some(Token::Word(w)) => if let Some(terminator) = terminators.iter().find(|t| **t == w.as_str()) {
+                    let terminator = *terminator;
+                    self.position += 1;
+                    return Ok((lines, Some(terminator)));
+                },
+                _ => (),
+            }
+
+            lines.push(self.parse_line()?);
+        }
+    }
+
+    /// Parses a block which must be ended by one of the `terminators`.
+    fn parse_terminated_block(&mut self, terminators: &[&'static str]) -> ScriptResult<(Vec<Line>, &'static str)> {
+        match self.parse_block(terminators)? {
+            (lines, Some(terminator)) => Ok((lines, terminator)),
+            (_, None) => Err(self.error(ScriptErrorKind::Expected(terminators[0]))),
+        }
+    }
+
+    fn parse_line(&mut self) -> ScriptResult<Line> {
+        let line = self.line();
+        let statement = self.parse_statement()?;
+        Ok(Line { statement, line })
+    }
+
+    fn parse_statement(&mut self) -> ScriptResult<Statement> {
+        let word = match self.peek() {
+            Some(Token::Word(w)) => w.clone(),
+            _ => return Err(self.error(ScriptErrorKind::Expected("statement"))),
+        };
+
+        if let Some(command) = DrawCommand::from_keyword(&word) {
+            self.position += 1;
+            let arguments = if command.arity() == 0 { vec![] } else { self.parse_expression_list()? };
+            if arguments.len() != command.arity() {
+                return Err(self.error(ScriptErrorKind::WrongArgumentCount(word)));
+            }
+            self.expect_end_of_statement()?;
+            return Ok(Statement::Draw(command, arguments));
+        }
+
+        let statement = match word.as_str() {
+            "print" => {
+                self.position += 1;
+                let values = if self.peek() == Some(&Token::Newline) { vec![] } else { self.parse_expression_list()? };
+                Statement::Print(values)
+            }
+
+            "input" => {
+                self.position += 1;
+                let prompt = if let Some(Token::String(_)) = self.peek() {
+                    let prompt = self.parse_expression()?;
+                    self.expect(&Token::Comma, ",")?;
+                    Some(prompt)
+                } else {
+                    None
+                };
+                Statement::Input(prompt, self.expect_variable()?)
+            }
+
+            "if" => {
+                self.position += 1;
+                let condition = self.parse_expression()?;
+                self.expect_word("then")?;
+
+                // A single statement can follow on the same line, without an `end`
+                if self.peek() != Some(&Token::Newline) {
+                    let statement = self.parse_line()?;
+                    return Ok(Statement::If { condition, then: vec![statement], otherwise: vec![] });
+                }
+
+                let (then, terminator) = self.parse_terminated_block(&["end", "else"])?;
+                let otherwise = if terminator == "else" {
+                    self.parse_terminated_block(&["end"])?.0
+                } else {
+                    vec![]
+                };
+                Statement::If { condition, then, otherwise }
+            }
+
+            "while" => {
+                self.position += 1;
+                let condition = self.parse_expression()?;
+                self.expect_end_of_statement()?;
+                let (body, _) = self.parse_terminated_block(&["end"])?;
+                Statement::While(condition, body)
+            }
+
+            "for" => {
+                self.position += 1;
+                let variable = self.expect_variable()?;
+                self.expect(&Token::Equal, "=")?;
+                let from = self.parse_expression()?;
+                self.expect_word("to")?;
+                let to = self.parse_expression()?;
+                let step = if self.accept_word("step") { Some(self.parse_expression()?) } else { None };
+                self.expect_end_of_statement()?;
+
+                let (body, _) = self.parse_terminated_block(&["next"])?;
+
+                // The variable can optionally be repeated after `next`
+                if let Some(Token::Word(_)) = self.peek() {
+                    if self.expect_variable()? != variable {
+                        return Err(self.error(ScriptErrorKind::Expected("matching next")));
+                    }
+                }
+                Statement::For { variable, from, to, step, body }
+            }
+
+            "stop" => {
+                self.position += 1;
+                Statement::Stop
+            }
+
+            _ => {
+                self.accept_word("let");
+                let variable = self.expect_variable()?;
+                self.expect(&Token::Equal, "=")?;
+                Statement::Assign(variable, self.parse_expression()?)
+            }
+        };
+
+        // Blocks have already consumed the rest of their line, after their terminator
+        if !matches!(statement, Statement::If { .. } | Statement::While(..)) {
+            self.expect_end_of_statement()?;
+        } else {
+            // ...but `end` may be followed by what it ends, like `end if`
+            if !self.accept_word("if") {
+                self.accept_word("while");
+            }
+            self.expect_end_of_statement()?;
+        }
+        Ok(statement)
+    }
+
+    fn parse_expression_list(&mut self) -> ScriptResult<Vec<Expression>> {
+        let mut expressions = vec![self.parse_expression()?];
+        while self.accept(&Token::Comma) {
+            expressions.push(self.parse_expression()?);
+        }
+        Ok(expressions)
+    }
+
+    fn parse_expression(&mut self) -> ScriptResult<Expression> {
+        self.parse_or()
+    }
+
+    fn binary(left: Expression, operator: BinaryOperator, right: Expression) -> Expression {
+        Expression::Binary(Box::new(left), operator, Box::new(right))
+    }
+
+    fn parse_or(&mut self) -> ScriptResult<Expression> {
+        let mut left = self.parse_and()?;
+        while self.accept_word("or") {
+            left = Self::binary(left, BinaryOperator::Or, self.parse_and()?);
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> ScriptResult<Expression> {
+        let mut left = self.parse_not()?;
+        while self.accept_word("and") {
+            left = Self::binary(left, BinaryOperator::And, self.parse_not()?);
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> ScriptResult<Expression> {
+        if self.accept_word("not") {
+            Ok(Expression::Unary(UnaryOperator::Not, Box::new(self.parse_not()?)))
+        } else {
+            self.parse_comparison()
+        }
+    }
+
+    fn parse_comparison(&mut self) -> ScriptResult<Expression> {
+        let left = self.parse_additive()?;
+        let operator = match self.peek() {
+            Some(Token::Equal) => BinaryOperator::Equal,
+            Some(Token::NotEqual) => BinaryOperator::NotEqual,
+            Some(Token::Less) => BinaryOperator::Less,
+            Some(Token::LessEqual) => BinaryOperator::LessEqual,
+            Some(Token::Greater) => BinaryOperator::Greater,
+            Some(Token::GreaterEqual) => BinaryOperator::GreaterEqual,
+            _ => return Ok(left),
+        };
+        self.position += 1;
+        Ok(Self::binary(left, operator, self.parse_additive()?))
+    }
+
+    fn parse_additive(&mut self) -> ScriptResult<Expression> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Add) => BinaryOperator::Add,
+                Some(Token::Subtract) => BinaryOperator::Subtract,
+                _ => return Ok(left),
+            };
+            self.position += 1;
+            left = Self::binary(left, operator, self.parse_multiplicative()?);
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> ScriptResult<Expression> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let operator = match self.peek() {
+                Some(Token::Multiply) => BinaryOperator::Multiply,
+                Some(Token::Divide) => BinaryOperator::Divide,
+                Some(Token::Word(w)) if w == "mod" => BinaryOperator::Modulo,
+                _ => return Ok(left),
+            };
+            self.position += 1;
+            left = Self::binary(left, operator, self.parse_unary()?);
+        }
+    }
+
+    fn parse_unary(&mut self) -> ScriptResult<Expression> {
+        if self.accept(&Token::Subtract) {
+            Ok(Expression::Unary(UnaryOperator::Negate, Box::new(self.parse_unary()?)))
+        } else {
+            self.parse_power()
+        }
+    }
+
+    fn parse_power(&mut self) -> ScriptResult<Expression> {
+        let base = self.parse_atom()?;
+        if self.accept(&Token::Power) {
+            // Right-associative, and binds tighter than negation on the left but not the right,
+            // so `-2^2` is -4 and `2^-1` is 0.5
+            Ok(Self::binary(base, BinaryOperator::Power, self.parse_unary()?))
+        } else {
+            Ok(base)
+        }
+    }
+
+    fn parse_atom(&mut self) -> ScriptResult<Expression> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expression::Number(n)),
+            Some(Token::String(s)) => Ok(Expression::String(s)),
+            Some(Token::LeftParen) => {
+                let expression = self.parse_expression()?;
+                self.expect(&Token::RightParen, ")")?;
+                Ok(expression)
+            }
+            Some(Token::Word(w)) if !KEYWORDS.contains(&w.as_str()) => {
+                if self.accept(&Token::LeftParen) {
+                    let arguments = if self.accept(&Token::RightParen) {
+                        vec![]
+                    } else {
+                        let arguments = self.parse_expression_list()?;
+                        self.expect(&Token::RightParen, ")")?;
+                        arguments
+                    };
+                    Ok(Expression::Call(w, arguments))
+                } else {
+                    Ok(Expression::Variable(w))
+                }
+            }
+            _ => {
+                self.position -= 1;
+                Err(self.error(ScriptErrorKind::Expected("value")))
+            }
+        }
+    }
+}
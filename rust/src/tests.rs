@@ -1,4 +1,6 @@
-use crate::{operating_system::{OperatingSystem, OSInput, OsAccessor}, interface::{ApplicationFramework, Colour}, applications::Application};
+use rbop::node::unstructured::UnstructuredNodeRoot;
+
+use crate::{operating_system::{OperatingSystem, OSInput, OsAccessor}, interface::{ApplicationFramework, Colour}, applications::Application, maths::LinearNotation};
 
 /// Launches the suite of tests.
 /// 
@@ -11,6 +13,10 @@ pub fn run_test_suite<F: ApplicationFramework + 'static>(os: &mut OperatingSyste
     os.display_sprite.print_at(10, 10, "Clearing history...");
     os.draw();
     os.filesystem.calculations.table.clear(false);
+    os.filesystem.data_lists.table.clear(false);
+    os.filesystem.user_functions.table.clear(false);
+    os.filesystem.user_functions.load_into_self();
+    os.filesystem.files.table.clear(false);
 
     // Kick off calculator tests
     os.launch_application_by_name("Calculator");
@@ -20,6 +26,47 @@ pub fn run_test_suite<F: ApplicationFramework + 'static>(os: &mut OperatingSyste
     os.launch_application_by_name("Graph");
     os.application_to_tick().test();
 
+    // Then unit conversion tests
+    os.launch_application_by_name("Unit Converter");
+    os.application_to_tick().test();
+
+    // Then integer arithmetic tests
+    os.launch_application_by_name("Base N");
+    os.application_to_tick().test();
+
+    // Then matrix tests
+    os.launch_application_by_name("Matrix");
+    os.application_to_tick().test();
+
+    // Then statistics tests
+    os.launch_application_by_name("Statistics");
+    os.application_to_tick().test();
+
+    // Then equation solver tests
+    os.launch_application_by_name("Equation");
+    os.application_to_tick().test();
+
+    // Then programming tests
+    os.launch_application_by_name("Programs");
+    os.application_to_tick().test();
+
+    // Then note-taking tests
+    os.launch_application_by_name("Notes");
+    os.application_to_tick().test();
+
+    // Then file browser tests
+    os.launch_application_by_name("Files");
+    os.application_to_tick().test();
+
+    // Then input tests, which don't need an application
+    crate::operating_system::test(os);
+
+    // Then storage tests
+    crate::filesystem::test(os);
+
+    // Then host link tests
+    crate::host_link::test(os);
+
     // Failures are panics, so all good if we got here
     os.framework.tests_success_hook();
     os.showing_menu = true;
@@ -43,3 +90,14 @@ where
         app.tick();
     }
 }
+
+/// A helper method for use in tests. Parses an expression written in linear notation, such as
+/// `sqrt(2)/3+sin(x)^2`, to save spelling out its nodes or key presses.
+///
+/// Panics if the expression is invalid, since that's a mistake in the test.
+pub fn linear(text: &str) -> UnstructuredNodeRoot {
+    match UnstructuredNodeRoot::from_linear(text) {
+        Ok(root) => root,
+        Err(error) => panic!("invalid linear expression {:?}: {}", text, error),
+    }
+}